@@ -0,0 +1,74 @@
+use eframe::wgpu::naga;
+use rhai::Engine;
+use std::path::{Path, PathBuf};
+use crate::sdf_ast::register_rhai_types;
+use crate::settings::SdfSettings;
+use crate::SdfApp;
+
+/// `sdf_poc --check <folder>`: runs every `.rhai` script under `folder` (recursively), generates
+/// its shader with the default settings and validates it with naga, without opening a window or
+/// touching the GPU. Prints one line per script, plus any thin-wall warnings, and returns whether
+/// all of them passed. Relative asset paths in the scripts resolve against the working directory,
+/// as in the app.
+pub fn check_scenes(folder: &Path) -> bool {
+    let mut scripts = Vec::new();
+    if let Err(e) = collect_scripts(folder, &mut scripts) {
+        eprintln!("Could not read {}: {}", folder.display(), e);
+        return false;
+    }
+    scripts.sort();
+    if scripts.is_empty() {
+        eprintln!("No .rhai files under {}", folder.display());
+        return false;
+    }
+
+    let mut engine = Engine::new();
+    register_rhai_types(&mut engine);
+    let settings = SdfSettings::default();
+    let mut failed = 0;
+    for path in &scripts {
+        match check_script(&engine, &settings, path) {
+            Ok(warnings) => {
+                println!("ok    {}", path.display());
+                for warning in warnings {
+                    println!("      warning: {}", warning);
+                }
+            }
+            Err(e) => {
+                failed += 1;
+                println!("FAIL  {}", path.display());
+                for line in e.lines() {
+                    println!("      {}", line);
+                }
+            }
+        }
+    }
+    println!("{} of {} scripts passed", scripts.len() - failed, scripts.len());
+    failed == 0
+}
+
+/// Evaluates, compiles and validates one script; returns its warnings or the first error.
+fn check_script(engine: &Engine, settings: &SdfSettings, path: &Path) -> Result<Vec<String>, String> {
+    let code = std::fs::read_to_string(path).map_err(|e| format!("Could not open: {}", e))?;
+    let scene = SdfApp::eval_scene(engine, &code).map_err(|e| e.message)?;
+    // Every object is visible on load, so this is only `None` for a script without objects
+    let Some(scene) = scene.combined(0.0) else { return Ok(vec!["no objects".to_string()]) };
+    let wgsl = SdfApp::compile_shader(&scene, settings);
+    let module = naga::front::wgsl::parse_str(&wgsl).map_err(|e| format!("Shader error: {}", e.emit_to_string(&wgsl)))?;
+    naga::valid::Validator::new(naga::valid::ValidationFlags::all(), naga::valid::Capabilities::default())
+        .validate(&module)
+        .map_err(|e| format!("Shader error: {}", e.emit_to_string(&wgsl)))?;
+    Ok(scene.thin_result_warnings())
+}
+
+fn collect_scripts(dir: &Path, out: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_scripts(&path, out)?;
+        } else if path.extension().is_some_and(|ext| ext == "rhai") {
+            out.push(path);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,418 @@
+use eframe::egui::{self, text::LayoutJob, TextFormat};
+use std::collections::VecDeque;
+use std::ops::Range;
+use crate::sdf_ast::{RHAI_CONSTRUCTORS, RHAI_METHODS};
+
+/// Maximum number of code snapshots kept for undo/redo.
+const HISTORY_CAPACITY: usize = 50;
+
+/// Ring buffer of editor snapshots. egui's built-in `TextEdit` undo is per-widget and easily
+/// lost, so the app keeps its own history, pushed on compile and periodically while typing.
+pub struct CodeHistory {
+    states: VecDeque<String>,
+    cursor: usize,
+}
+
+impl CodeHistory {
+    pub fn new(initial: &str) -> Self {
+        Self { states: VecDeque::from([initial.to_string()]), cursor: 0 }
+    }
+
+    /// Records `code` as the newest state, discarding anything that was undone.
+    pub fn push(&mut self, code: &str) {
+        if self.states[self.cursor] == code {
+            return;
+        }
+        self.states.truncate(self.cursor + 1);
+        self.states.push_back(code.to_string());
+        if self.states.len() > HISTORY_CAPACITY {
+            self.states.pop_front();
+        }
+        self.cursor = self.states.len() - 1;
+    }
+
+    /// Steps back one state. Unsaved edits in `current` are recorded first so redo can restore them.
+    pub fn undo(&mut self, current: &str) -> Option<String> {
+        self.push(current);
+        if self.cursor == 0 {
+            return None;
+        }
+        self.cursor -= 1;
+        Some(self.states[self.cursor].clone())
+    }
+
+    pub fn redo(&mut self) -> Option<String> {
+        if self.cursor + 1 >= self.states.len() {
+            return None;
+        }
+        self.cursor += 1;
+        Some(self.states[self.cursor].clone())
+    }
+}
+
+/// State of the Ctrl+F find/replace bar shown above the code editor.
+#[derive(Default)]
+pub struct FindReplace {
+    pub open: bool,
+    pub query: String,
+    pub replacement: String,
+    /// Index into `matches()` of the highlighted match.
+    pub current: usize,
+    /// Set when the current match changed and the editor should select and scroll to it.
+    pub jump_pending: bool,
+}
+
+impl FindReplace {
+    /// Byte ranges of every (non-overlapping) occurrence of the query.
+    pub fn matches(&self, text: &str) -> Vec<Range<usize>> {
+        if self.query.is_empty() {
+            return Vec::new();
+        }
+        text.match_indices(&self.query).map(|(start, m)| start..start + m.len()).collect()
+    }
+
+    fn step(&mut self, count: usize, forward: bool) {
+        if count == 0 {
+            return;
+        }
+        self.current = if forward { (self.current + 1) % count } else { (self.current + count - 1) % count };
+        self.jump_pending = true;
+    }
+
+    /// Draws the bar; edits `text` in place when replacing.
+    pub fn ui(&mut self, ui: &mut egui::Ui, text: &mut String) {
+        let matches = self.matches(text);
+        if self.current >= matches.len() {
+            self.current = 0;
+        }
+
+        ui.horizontal(|ui| {
+            let find = ui.add(egui::TextEdit::singleline(&mut self.query).hint_text("Find").desired_width(150.0));
+            if find.changed() {
+                self.current = 0;
+                self.jump_pending = true;
+            }
+            if find.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                self.step(matches.len(), !ui.input(|i| i.modifiers.shift));
+                find.request_focus();
+            }
+            if ui.small_button("<").clicked() { self.step(matches.len(), false); }
+            if ui.small_button(">").clicked() { self.step(matches.len(), true); }
+            if matches.is_empty() {
+                ui.label("0/0");
+            } else {
+                ui.label(format!("{}/{}", self.current + 1, matches.len()));
+            }
+            if ui.small_button("x").clicked() { self.open = false; }
+        });
+
+        ui.horizontal(|ui| {
+            ui.add(egui::TextEdit::singleline(&mut self.replacement).hint_text("Replace").desired_width(150.0));
+            if ui.button("Replace").clicked() {
+                if let Some(range) = matches.get(self.current) {
+                    text.replace_range(range.clone(), &self.replacement);
+                    self.jump_pending = true;
+                }
+            }
+            if ui.button("Replace All").clicked() && !matches.is_empty() {
+                *text = text.replace(&self.query, &self.replacement);
+                self.current = 0;
+            }
+        });
+    }
+}
+
+/// Converts a byte offset into the char index egui cursors use.
+pub fn char_index(text: &str, byte: usize) -> usize {
+    text[..byte].chars().count()
+}
+
+/// Converts an egui char index back into a byte offset.
+pub fn byte_index(text: &str, char_idx: usize) -> usize {
+    text.char_indices().nth(char_idx).map_or(text.len(), |(b, _)| b)
+}
+
+/// Byte range to underline for an error Rhai reports at `line` and `column` (both 1-based): the
+/// word or single character there, empty at the end of a line. `None` if the text no longer
+/// has that line.
+pub fn error_range(text: &str, line: usize, column: usize) -> Option<Range<usize>> {
+    let line_start = if line <= 1 { 0 } else { text.match_indices('\n').nth(line - 2)?.0 + 1 };
+    let line_text = text[line_start..].split('\n').next().unwrap_or("");
+    let start = line_start + byte_index(line_text, column.saturating_sub(1));
+    let rest = &text[start..line_start + line_text.len()];
+    let is_word = |c: char| c.is_alphanumeric() || c == '_';
+    let len = match rest.chars().next() {
+        None => 0,
+        Some(c) if is_word(c) => rest.find(|c| !is_word(c)).unwrap_or(rest.len()),
+        Some(c) => c.len_utf8(),
+    };
+    Some(start..start + len)
+}
+
+/// Completion popup for the registered SDF constructors (typed as a bare word) and
+/// methods (typed after a `.`). Accepting inserts the call with placeholder arguments selected.
+#[derive(Default)]
+pub struct Autocomplete {
+    active: bool,
+    /// Candidates as `(name, placeholder args)`, filtered by the typed prefix.
+    items: Vec<(&'static str, &'static str)>,
+    selected: usize,
+    /// Byte range of the partially typed word that an accepted item replaces.
+    word: Range<usize>,
+    popup_pos: egui::Pos2,
+}
+
+impl Autocomplete {
+    /// Handles navigation keys before the `TextEdit` gets to see them.
+    pub fn handle_keys(&mut self, ctx: &egui::Context, text: &mut String, editor_id: egui::Id) {
+        if !self.active || self.items.is_empty() {
+            return;
+        }
+        let (up, down, accept, cancel) = ctx.input_mut(|i| (
+            i.consume_key(egui::Modifiers::NONE, egui::Key::ArrowUp),
+            i.consume_key(egui::Modifiers::NONE, egui::Key::ArrowDown),
+            i.consume_key(egui::Modifiers::NONE, egui::Key::Enter) || i.consume_key(egui::Modifiers::NONE, egui::Key::Tab),
+            i.consume_key(egui::Modifiers::NONE, egui::Key::Escape),
+        ));
+        let count = self.items.len();
+        if up { self.selected = (self.selected + count - 1) % count; }
+        if down { self.selected = (self.selected + 1) % count; }
+        if accept { self.accept(ctx, text, editor_id, self.selected); }
+        if cancel { self.active = false; }
+    }
+
+    fn accept(&mut self, ctx: &egui::Context, text: &mut String, editor_id: egui::Id, index: usize) {
+        let (name, args) = self.items[index];
+        text.replace_range(self.word.clone(), &format!("{name}({args})"));
+
+        // Select the placeholder arguments so typing overwrites them
+        let args_start = char_index(text, self.word.start + name.len() + 1);
+        select_chars(ctx, editor_id, args_start, args_start + args.chars().count());
+        self.active = false;
+    }
+
+    /// Recomputes candidates from the text around the cursor after the editor has been drawn.
+    pub fn update(&mut self, output: &egui::text_edit::TextEditOutput, text: &str) {
+        let cursor = match output.cursor_range {
+            Some(range) if output.response.has_focus() && range.primary == range.secondary => range.primary.ccursor,
+            _ => {
+                self.active = false;
+                return;
+            }
+        };
+        let end = byte_index(text, cursor.index);
+        let start = text[..end].rfind(|c: char| !(c.is_ascii_alphanumeric() || c == '_')).map_or(0, |i| i + 1);
+        let prefix = &text[start..end];
+        let after_dot = text[..start].ends_with('.');
+
+        let candidates = if after_dot { RHAI_METHODS } else { RHAI_CONSTRUCTORS };
+        let items: Vec<_> = if after_dot || !prefix.is_empty() {
+            candidates.iter().copied().filter(|(name, _)| name.starts_with(prefix) && *name != prefix).collect()
+        } else {
+            Vec::new()
+        };
+
+        // Only pop up while typing; moving the cursor around should not summon it
+        if output.response.changed() {
+            self.active = true;
+        }
+        if items != self.items {
+            self.selected = 0;
+        }
+        self.items = items;
+        self.word = start..end;
+        self.popup_pos = output.galley.pos_from_ccursor(cursor).translate(output.galley_pos.to_vec2()).left_bottom();
+        if self.items.is_empty() {
+            self.active = false;
+        }
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context, text: &mut String, editor_id: egui::Id) {
+        if !self.active || self.items.is_empty() {
+            return;
+        }
+        let mut clicked = None;
+        egui::Area::new(egui::Id::new("autocomplete_popup"))
+            .order(egui::Order::Foreground)
+            .fixed_pos(self.popup_pos)
+            .show(ctx, |ui| {
+                egui::Frame::popup(ui.style()).show(ui, |ui| {
+                    for (idx, (name, args)) in self.items.iter().enumerate() {
+                        let label = egui::RichText::new(format!("{name}({args})")).monospace();
+                        if ui.selectable_label(idx == self.selected, label).clicked() {
+                            clicked = Some(idx);
+                        }
+                    }
+                });
+            });
+        if let Some(idx) = clicked {
+            self.accept(ctx, text, editor_id, idx);
+        }
+    }
+}
+
+/// Selects the character range `start..end` in the editor and focuses it.
+fn select_chars(ctx: &egui::Context, editor_id: egui::Id, start: usize, end: usize) {
+    if let Some(mut state) = egui::TextEdit::load_state(ctx, editor_id) {
+        let range = egui::text::CCursorRange::two(egui::text::CCursor::new(start), egui::text::CCursor::new(end));
+        state.cursor.set_char_range(Some(range));
+        state.store(ctx, editor_id);
+    }
+    ctx.memory_mut(|m| m.request_focus(editor_id));
+}
+
+/// Inserts `name(args)` at the editor's cursor, replacing any selection (at the end of the
+/// code if the editor has no cursor yet), and selects `args` like an accepted completion.
+fn insert_call(ctx: &egui::Context, text: &mut String, editor_id: egui::Id, name: &str, args: &str) {
+    let cursor = egui::TextEdit::load_state(ctx, editor_id).and_then(|s| s.cursor.char_range());
+    let (start, end) = match cursor {
+        Some(range) => {
+            let (a, b) = (range.primary.index, range.secondary.index);
+            (byte_index(text, a.min(b)), byte_index(text, a.max(b)))
+        }
+        None => (text.len(), text.len()),
+    };
+    text.replace_range(start..end, &format!("{name}({args})"));
+    let args_start = char_index(text, start) + name.chars().count() + 1;
+    select_chars(ctx, editor_id, args_start, args_start + args.chars().count());
+}
+
+/// Buttons inserting each registered shape and operation at the cursor with placeholder
+/// arguments, for building scenes without knowing the API by heart.
+pub fn palette_ui(ui: &mut egui::Ui, text: &mut String, editor_id: egui::Id) {
+    ui.label("Shapes:");
+    ui.horizontal_wrapped(|ui| {
+        for (name, args) in RHAI_CONSTRUCTORS {
+            if ui.small_button(*name).on_hover_text(format!("{name}({args})")).clicked() {
+                insert_call(ui.ctx(), text, editor_id, name, args);
+            }
+        }
+    });
+    ui.label("Operations (applied to the shape before the cursor):");
+    ui.horizontal_wrapped(|ui| {
+        for (name, args) in RHAI_METHODS {
+            if ui.small_button(format!(".{name}")).on_hover_text(format!(".{name}({args})")).clicked() {
+                insert_call(ui.ctx(), text, editor_id, &format!(".{name}"), args);
+            }
+        }
+    });
+}
+
+const RHAI_KEYWORDS: &[&str] = &[
+    "let", "const", "fn", "if", "else", "for", "in", "while", "loop", "do", "until",
+    "break", "continue", "return", "switch", "true", "false", "this", "import", "export", "as",
+];
+
+#[derive(Clone, Copy, PartialEq)]
+enum TokenKind {
+    Plain,
+    Keyword,
+    Builtin,
+    Number,
+    String,
+    Comment,
+}
+
+impl TokenKind {
+    fn color(self, ui: &egui::Ui) -> egui::Color32 {
+        match self {
+            TokenKind::Plain => ui.visuals().text_color(),
+            TokenKind::Keyword => egui::Color32::from_rgb(198, 120, 221),
+            TokenKind::Builtin => egui::Color32::from_rgb(97, 175, 239),
+            TokenKind::Number => egui::Color32::from_rgb(209, 154, 102),
+            TokenKind::String => egui::Color32::from_rgb(152, 195, 121),
+            TokenKind::Comment => egui::Color32::from_rgb(110, 118, 129),
+        }
+    }
+}
+
+/// Splits Rhai source into colored byte ranges. This is a lexer for coloring only; it does
+/// not need to agree with Rhai on malformed input.
+fn tokenize(text: &str) -> Vec<(Range<usize>, TokenKind)> {
+    let bytes = text.as_bytes();
+    let mut tokens: Vec<(Range<usize>, TokenKind)> = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        let start = i;
+        let c = bytes[i];
+        let kind = if text[i..].starts_with("//") {
+            i = text[i..].find('\n').map_or(bytes.len(), |n| i + n);
+            TokenKind::Comment
+        } else if text[i..].starts_with("/*") {
+            i = text[i + 2..].find("*/").map_or(bytes.len(), |n| i + 2 + n + 2);
+            TokenKind::Comment
+        } else if c == b'"' || c == b'`' {
+            i += 1;
+            while i < bytes.len() && bytes[i] != c {
+                i += if bytes[i] == b'\\' { 2 } else { 1 };
+            }
+            i = (i + 1).min(bytes.len());
+            TokenKind::String
+        } else if c.is_ascii_digit() {
+            while i < bytes.len() && (bytes[i].is_ascii_alphanumeric() || bytes[i] == b'.' || bytes[i] == b'_') {
+                i += 1;
+            }
+            TokenKind::Number
+        } else if c.is_ascii_alphabetic() || c == b'_' {
+            while i < bytes.len() && (bytes[i].is_ascii_alphanumeric() || bytes[i] == b'_') {
+                i += 1;
+            }
+            let word = &text[start..i];
+            if RHAI_KEYWORDS.contains(&word) {
+                TokenKind::Keyword
+            } else if RHAI_CONSTRUCTORS.iter().chain(RHAI_METHODS).any(|(name, _)| *name == word) {
+                TokenKind::Builtin
+            } else {
+                TokenKind::Plain
+            }
+        } else {
+            // Advance a whole char so multi-byte text (e.g. Chinese comments outside `//`) stays valid
+            i += text[i..].chars().next().map_or(1, char::len_utf8);
+            TokenKind::Plain
+        };
+        match tokens.last_mut() {
+            // Merge runs of plain text so the layout job stays small
+            Some((last, TokenKind::Plain)) if kind == TokenKind::Plain => last.end = i,
+            _ => tokens.push((start..i, kind)),
+        }
+    }
+    tokens
+}
+
+/// Builds the syntax-colored editor layout, painting a background behind `highlights`
+/// (sorted, non-overlapping byte ranges; the one at index `current` is drawn brighter) and
+/// underlining the `error` range in red.
+pub fn layout_code(ui: &egui::Ui, text: &str, highlights: &[Range<usize>], current: Option<usize>, error: Option<Range<usize>>) -> LayoutJob {
+    let font_id = egui::TextStyle::Monospace.resolve(ui.style());
+    let mut job = LayoutJob::default();
+
+    for (range, kind) in tokenize(text) {
+        let format = TextFormat::simple(font_id.clone(), kind.color(ui));
+        // Split the token wherever a highlight starts or ends inside it
+        let mut pos = range.start;
+        while pos < range.end {
+            let idx = highlights.partition_point(|h| h.end <= pos);
+            let (end, background) = match highlights.get(idx) {
+                Some(h) if h.start <= pos => {
+                    let bg = if Some(idx) == current { egui::Color32::from_rgb(180, 120, 0) } else { egui::Color32::from_rgb(90, 70, 20) };
+                    (h.end.min(range.end), bg)
+                }
+                Some(h) => (h.start.min(range.end), egui::Color32::TRANSPARENT),
+                None => (range.end, egui::Color32::TRANSPARENT),
+            };
+            // ...and wherever the error range starts or ends
+            let (end, underline) = match &error {
+                Some(e) if e.start <= pos && pos < e.end => (end.min(e.end), egui::Stroke::new(1.5, egui::Color32::RED)),
+                Some(e) if pos < e.start => (end.min(e.start), egui::Stroke::NONE),
+                _ => (end, egui::Stroke::NONE),
+            };
+            job.append(&text[pos..end], 0.0, TextFormat { background, underline, ..format.clone() });
+            pos = end;
+        }
+    }
+    if job.sections.is_empty() {
+        // Keep the font so an empty editor still has a correctly sized cursor row
+        job.append("", 0.0, TextFormat::simple(font_id, ui.visuals().text_color()));
+    }
+    job
+}
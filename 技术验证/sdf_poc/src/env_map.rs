@@ -0,0 +1,69 @@
+use std::path::Path;
+
+/// Equirectangular HDR environment, decoded on the CPU with a full box-filtered mip chain.
+/// Texels are packed as `Rgb9e5Ufloat`, which keeps HDR range and stays filterable.
+pub struct EnvMap {
+    pub width: u32,
+    pub height: u32,
+    /// Mip levels from full resolution down to 1x1, each `max(1, w >> l) * max(1, h >> l)` texels.
+    pub mips: Vec<Vec<u32>>,
+}
+
+impl EnvMap {
+    /// Decodes `path` and builds its mip chain. Levels wider or taller than `max_size` (the GPU's
+    /// `max_texture_dimension_2d`) are dropped, so a huge HDRI is uploaded at the largest size
+    /// that fits rather than failing validation.
+    pub fn load(path: impl AsRef<Path>, max_size: u32) -> Result<Self, String> {
+        let path = path.as_ref();
+        let image = image::open(path).map_err(|e| format!("Failed to load {}: {}", path.display(), e))?.into_rgb32f();
+        let (width, height) = image.dimensions();
+        let mut level: Vec<[f32; 3]> = image.pixels().map(|p| p.0).collect();
+        let (mut w, mut h) = (width, height);
+
+        let mut mips = vec![level.iter().map(|&c| pack_rgb9e5(c)).collect()];
+        while w > 1 || h > 1 {
+            let (nw, nh) = ((w / 2).max(1), (h / 2).max(1));
+            let mut next = Vec::with_capacity((nw * nh) as usize);
+            for y in 0..nh {
+                for x in 0..nw {
+                    let mut sum = [0.0f32; 3];
+                    for (dx, dy) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+                        let sx = (2 * x + dx).min(w - 1);
+                        let sy = (2 * y + dy).min(h - 1);
+                        let c = level[(sy * w + sx) as usize];
+                        for i in 0..3 { sum[i] += c[i] * 0.25; }
+                    }
+                    next.push(sum);
+                }
+            }
+            mips.push(next.iter().map(|&c| pack_rgb9e5(c)).collect());
+            level = next;
+            (w, h) = (nw, nh);
+        }
+
+        let skip = (0..mips.len()).find(|&l| (width >> l).max(1) <= max_size && (height >> l).max(1) <= max_size).unwrap_or(mips.len() - 1);
+        if skip > 0 {
+            log::warn!("{}: {}x{} is over this GPU's texture limit of {}; using it at 1/{} size", path.display(), width, height, max_size, 1 << skip);
+            mips.drain(..skip);
+        }
+        Ok(Self { width: (width >> skip).max(1), height: (height >> skip).max(1), mips })
+    }
+}
+
+/// Packs a linear RGB color into the shared-exponent `RGB9E5` layout used by `Rgb9e5Ufloat`.
+fn pack_rgb9e5(c: [f32; 3]) -> u32 {
+    const MAX: f32 = 65408.0; // (2^9 - 1) / 2^9 * 2^16
+    let [r, g, b] = c.map(|v| if v.is_finite() { v.clamp(0.0, MAX) } else { 0.0 });
+    let max_c = r.max(g).max(b);
+    if max_c <= 0.0 {
+        return 0;
+    }
+    let mut exp = (max_c.log2().floor() as i32).max(-16) + 1 + 15;
+    let mut scale = 2f32.powi(exp - 15 - 9);
+    if (max_c / scale).round() as u32 == 512 {
+        exp += 1;
+        scale *= 2.0;
+    }
+    let pack = |v: f32| ((v / scale).round() as u32).min(511);
+    pack(r) | (pack(g) << 9) | (pack(b) << 18) | ((exp as u32) << 27)
+}
@@ -0,0 +1,84 @@
+use eframe::egui;
+use glam::Vec3;
+
+/// Pointer distance (in points) within which an axis handle can be grabbed.
+const GRAB_RADIUS: f32 = 10.0;
+
+const AXIS_COLORS: [egui::Color32; 3] = [
+    egui::Color32::from_rgb(230, 70, 70),
+    egui::Color32::from_rgb(90, 200, 90),
+    egui::Color32::from_rgb(80, 130, 240),
+];
+
+/// Screen-space segment of one gizmo axis; dragging its full length moves `world_len` units.
+pub struct AxisHandle {
+    from: egui::Pos2,
+    to: egui::Pos2,
+    world_len: f32,
+}
+
+struct Drag {
+    axis: usize,
+    start_offset: [f32; 3],
+    along: f32,
+}
+
+/// Three-arrow move gizmo for the selected `translate` node.
+#[derive(Default)]
+pub struct TranslateGizmo {
+    drag: Option<Drag>,
+}
+
+impl TranslateGizmo {
+    /// Builds the handles for the local axes `axes` (world directions) at `pivot`, or `None`
+    /// if any part is behind the camera.
+    pub fn handles(pivot: Vec3, axes: [Vec3; 3], size: f32, project: impl Fn(Vec3) -> Option<egui::Pos2>) -> Option<[AxisHandle; 3]> {
+        let from = project(pivot)?;
+        let handle = |axis: Vec3| Some(AxisHandle { from, to: project(pivot + axis * size)?, world_len: size });
+        Some([handle(axes[0])?, handle(axes[1])?, handle(axes[2])?])
+    }
+
+    pub fn paint(&self, painter: &egui::Painter, handles: &[AxisHandle; 3]) {
+        for (axis, h) in handles.iter().enumerate() {
+            let active = self.drag.as_ref().is_some_and(|d| d.axis == axis);
+            let color = if active { egui::Color32::YELLOW } else { AXIS_COLORS[axis] };
+            painter.line_segment([h.from, h.to], egui::Stroke::new(3.0, color));
+            painter.circle_filled(h.to, 5.0, color);
+        }
+        painter.circle_filled(handles[0].from, 4.0, egui::Color32::WHITE);
+    }
+
+    /// Handles grabbing and dragging an axis. Returns the new offset while a drag is active.
+    pub fn interact(&mut self, response: &egui::Response, handles: &[AxisHandle; 3], offset: [f32; 3]) -> Option<[f32; 3]> {
+        if response.drag_started_by(egui::PointerButton::Primary) {
+            let origin = response.ctx.input(|i| i.pointer.press_origin())?;
+            self.drag = handles.iter().enumerate()
+                .map(|(axis, h)| (axis, distance_to_segment(origin, h.from, h.to)))
+                .filter(|(_, dist)| *dist < GRAB_RADIUS)
+                .min_by(|a, b| a.1.total_cmp(&b.1))
+                .map(|(axis, _)| Drag { axis, start_offset: offset, along: 0.0 });
+        }
+        if response.drag_stopped() {
+            self.drag = None;
+        }
+
+        let drag = self.drag.as_mut()?;
+        if !response.dragged_by(egui::PointerButton::Primary) {
+            return None;
+        }
+        let h = &handles[drag.axis];
+        let dir = h.to - h.from;
+        if dir.length_sq() > 1.0 {
+            drag.along += response.drag_delta().dot(dir) / dir.length_sq() * h.world_len;
+        }
+        let mut new_offset = drag.start_offset;
+        new_offset[drag.axis] += drag.along;
+        Some(new_offset)
+    }
+}
+
+fn distance_to_segment(p: egui::Pos2, a: egui::Pos2, b: egui::Pos2) -> f32 {
+    let ab = b - a;
+    let t = if ab.length_sq() > 0.0 { ((p - a).dot(ab) / ab.length_sq()).clamp(0.0, 1.0) } else { 0.0 };
+    p.distance(a + ab * t)
+}
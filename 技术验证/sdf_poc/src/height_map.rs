@@ -0,0 +1,56 @@
+use glam::Vec2;
+use std::fmt;
+use std::path::Path;
+
+/// Grayscale image driving `SdfOp::DisplaceMap`, decoded once when the script runs so the CPU
+/// evaluator and the shader sample the same texels. Texture coordinates wrap (repeat) on both
+/// axes, on the CPU and the GPU alike: a seamless image tiles cleanly, any other shows seams
+/// every `1 / scale` units.
+pub struct HeightMap {
+    /// Path as written in the script; maps with the same path share one texture layer.
+    pub path: String,
+    pub width: u32,
+    pub height: u32,
+    /// Row-major luminance, one byte per texel.
+    pub texels: Vec<u8>,
+}
+
+impl fmt::Debug for HeightMap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "HeightMap({}, {}x{})", self.path, self.width, self.height)
+    }
+}
+
+impl HeightMap {
+    pub fn load(path: &str) -> Result<Self, String> {
+        let image = image::open(Path::new(path)).map_err(|e| format!("Failed to load {}: {}", path, e))?.into_luma8();
+        let (width, height) = image.dimensions();
+        Ok(Self { path: path.to_string(), width, height, texels: image.into_raw() })
+    }
+
+    /// Bilinear sample in [0, 1] with repeat wrapping, matching a linear `Repeat` sampler.
+    pub fn sample(&self, uv: Vec2) -> f32 {
+        let (w, h) = (self.width as i64, self.height as i64);
+        let x = uv.x * w as f32 - 0.5;
+        let y = uv.y * h as f32 - 0.5;
+        let (x0, y0) = (x.floor(), y.floor());
+        let (fx, fy) = (x - x0, y - y0);
+        let texel = |dx: i64, dy: i64| {
+            let tx = (x0 as i64 + dx).rem_euclid(w);
+            let ty = (y0 as i64 + dy).rem_euclid(h);
+            self.texels[(ty * w + tx) as usize] as f32 / 255.0
+        };
+        let top = texel(0, 0) + (texel(1, 0) - texel(0, 0)) * fx;
+        let bottom = texel(0, 1) + (texel(1, 1) - texel(0, 1)) * fx;
+        top + (bottom - top) * fy
+    }
+
+    /// Texels resampled to `width`x`height`, for uploading into a shared texture array layer.
+    pub fn resized(&self, width: u32, height: u32) -> Vec<u8> {
+        if (width, height) == (self.width, self.height) {
+            return self.texels.clone();
+        }
+        let image = image::GrayImage::from_raw(self.width, self.height, self.texels.clone()).expect("texel count matches size");
+        image::imageops::resize(&image, width, height, image::imageops::FilterType::Triangle).into_raw()
+    }
+}
@@ -1,19 +1,59 @@
+mod batch_check;
+mod editor;
+mod env_map;
+mod gizmo;
+mod height_map;
+mod mesh_volume;
 mod sdf_widget;
 mod sdf_ast;
+mod sdf_bounds;
+mod sdf_check;
+mod sdf_eval;
+mod sdf_export;
+mod sdf_optimize;
+mod sdf_stats;
+mod reference;
+mod scene;
+mod settings;
+mod svg_path;
+mod web_export;
 mod wgsl_gen;
 
-use eframe::{egui, wgpu};
+use eframe::egui;
 use std::sync::Arc;
-use sdf_widget::{SdfRenderResources, sdf_view, CameraUniformData};
-use rhai::{Engine, Scope};
-use sdf_ast::{SdfNode, register_rhai_types};
+use sdf_widget::{SdfRenderResources, sdf_view, CameraUniformData, RenderParams};
+use rhai::{Dynamic, Engine, Scope};
+use sdf_ast::{SdfNode, SdfOp, register_rhai_types};
+use sdf_eval::{MarchEnd, MarchTrace};
 use wgsl_gen::WgslGenerator;
-use glam::{Vec3, Mat3}; 
+use editor::{Autocomplete, CodeHistory, FindReplace};
+use reference::ReferenceImage;
+use scene::Scene;
+use settings::{scene_settings_path, AaPattern, SdfSettings};
+use env_map::EnvMap;
+use gizmo::TranslateGizmo;
+use sdf_export::ExportJob;
+use sdf_stats::SceneStats;
+use glam::Vec3;
+use std::borrow::Cow;
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+
+/// Distance from the eye to the image plane, in units of half the viewport height: a vertical
+/// field of view of `2 * atan(1 / FOCAL_LENGTH)`, about 58°. The horizontal one follows the
+/// viewport's aspect ratio. Baked into the shader as `FOCAL_LENGTH` by `SdfApp::compile_shader`.
+const FOCAL_LENGTH: f32 = 1.8;
 
 struct Camera {
     pos: Vec3,
     yaw: f32,   
     pitch: f32, 
+    /// World Z is up instead of Y. Yaw and pitch are always measured in a Y-up frame, which
+    /// `to_world` rotates so that its Y lands on world Z.
+    z_up: bool,
+    /// Orbit center: while set, middle-drag swings the camera around it, keeping it in view,
+    /// instead of turning in place. Panning and moving carry it along.
+    pivot: Option<Vec3>,
 }
 
 impl Default for Camera {
@@ -31,12 +71,96 @@ impl Default for Camera {
             pos,
             yaw,
             pitch,
+            z_up: false,
+            pivot: None,
         }
     }
 }
 
 impl Camera {
-    fn update(&mut self, ui: &mut egui::Ui, response: &egui::Response) {
+    /// Rotates a vector from the camera's Y-up frame into world space.
+    fn to_world(&self, v: Vec3) -> Vec3 {
+        if self.z_up { Vec3::new(v.x, -v.z, v.y) } else { v }
+    }
+
+    fn to_local(&self, v: Vec3) -> Vec3 {
+        if self.z_up { Vec3::new(v.x, v.z, -v.y) } else { v }
+    }
+
+    /// Switches the up axis, keeping the position and view direction.
+    fn set_z_up(&mut self, z_up: bool) {
+        let (front, _, _) = self.basis();
+        self.z_up = z_up;
+        self.look_at(self.pos, self.pos + front);
+    }
+
+    /// Places the camera at `pos` facing `target`. Looking straight up or down is clamped to the
+    /// same pitch limit as mouse look.
+    fn look_at(&mut self, pos: Vec3, target: Vec3) {
+        self.pos = pos;
+        let dir = self.to_local(target - pos).normalize_or_zero();
+        if dir == Vec3::ZERO {
+            return;
+        }
+        self.yaw = dir.z.atan2(dir.x);
+        self.pitch = dir.y.asin().clamp(-1.5, 1.5);
+    }
+
+    /// Turns to face `pivot` and orbits around it from now on.
+    fn orbit_around(&mut self, pivot: Vec3) {
+        self.look_at(self.pos, pivot);
+        self.pivot = Some(pivot);
+    }
+
+    /// Orthonormal `(front, right, up)` basis, matching the one the shader builds rays from.
+    fn basis(&self) -> (Vec3, Vec3, Vec3) {
+        let front = self.to_world(Vec3::new(
+            self.yaw.cos() * self.pitch.cos(),
+            self.pitch.sin(),
+            self.yaw.sin() * self.pitch.cos()
+        )).normalize();
+
+        let global_up = self.to_world(Vec3::Y);
+        let right = front.cross(global_up).normalize();
+        let up = right.cross(front).normalize();
+        (front, right, up)
+    }
+
+    fn uniform_data(&self) -> CameraUniformData {
+        let (front, right, up) = self.basis();
+        CameraUniformData {
+            pos: self.pos.into(),
+            front: front.into(),
+            right: right.into(),
+            up: up.into(),
+        }
+    }
+
+    /// Direction of the (pinhole) ray through `pos` in the viewport `rect`.
+    fn ray_dir(&self, rect: egui::Rect, pos: egui::Pos2) -> Vec3 {
+        let (front, right, up) = self.basis();
+        let aspect = rect.width() / rect.height();
+        let uv = (pos - rect.min) / rect.size() * 2.0 - egui::vec2(1.0, 1.0);
+        (uv.x * aspect * right - uv.y * up + FOCAL_LENGTH * front).normalize()
+    }
+
+    /// Screen position of the world point `p`, or `None` if it is behind the camera.
+    fn project(&self, rect: egui::Rect, p: Vec3) -> Option<egui::Pos2> {
+        let (front, right, up) = self.basis();
+        let d = p - self.pos;
+        let z = d.dot(front);
+        if z <= 1e-3 {
+            return None;
+        }
+        let aspect = rect.width() / rect.height();
+        let uv = egui::vec2(d.dot(right) * FOCAL_LENGTH / z / aspect, -d.dot(up) * FOCAL_LENGTH / z);
+        Some(rect.min + (uv + egui::vec2(1.0, 1.0)) * 0.5 * rect.size())
+    }
+
+    /// Applies mouse/keyboard navigation. Returns whether the camera moved this frame.
+    /// `pan_speed` is the distance moved per pixel of right-drag.
+    fn update(&mut self, ui: &mut egui::Ui, response: &egui::Response, pan_speed: f32) -> bool {
+        let mut moved = false;
         let dt = ui.input(|i| i.stable_dt).min(0.1);
         
         if response.dragged_by(egui::PointerButton::Middle) {
@@ -46,12 +170,27 @@ impl Camera {
             self.yaw += delta.x * sensitivity;
             self.pitch -= delta.y * sensitivity; // 修复：鼠标向下移动(delta.y > 0)时视角向下看
             self.pitch = self.pitch.clamp(-1.5, 1.5);
+            if let Some(pivot) = self.pivot {
+                // Same distance from the pivot, on the far side of the new view direction
+                let distance = self.pos.distance(pivot);
+                let (front, _, _) = self.basis();
+                self.pos = pivot - front * distance;
+            }
+            moved = true;
+        }
+
+        // Pan: the scene follows the cursor
+        if response.dragged_by(egui::PointerButton::Secondary) {
+            let delta = response.drag_delta();
+            let (_, right, up) = self.basis();
+            self.shift((up * delta.y - right * delta.x) * pan_speed);
+            moved = true;
         }
 
         // Standard movement
-        let forward = Vec3::new(self.yaw.cos(), 0.0, self.yaw.sin()).normalize();
-        let right = Vec3::new(-self.yaw.sin(), 0.0, self.yaw.cos()).normalize();
-        let up = Vec3::new(0.0, 1.0, 0.0);
+        let forward = self.to_world(Vec3::new(self.yaw.cos(), 0.0, self.yaw.sin())).normalize();
+        let right = self.to_world(Vec3::new(-self.yaw.sin(), 0.0, self.yaw.cos())).normalize();
+        let up = self.to_world(Vec3::Y);
         let speed = 4.0 * dt; 
         
         if response.hovered() || response.dragged() {
@@ -65,27 +204,284 @@ impl Camera {
                 if i.key_down(egui::Key::Q) { move_dir -= up; }    // Q: 下降
                 
                 if move_dir.length_squared() > 0.0 {
-                    self.pos += move_dir.normalize() * speed;
+                    self.shift(move_dir.normalize() * speed);
+                    moved = true;
                 }
             });
         }
+        moved
+    }
+
+    /// Moves the camera, and the pivot with it so the orbit keeps its center in view.
+    fn shift(&mut self, delta: Vec3) {
+        self.pos += delta;
+        if let Some(pivot) = &mut self.pivot {
+            *pivot += delta;
+        }
     }
 }
 
-struct SdfApp {
-    sdf_resources: Option<Arc<SdfRenderResources>>,
-    rhai_engine: Engine,
-    code_text: String,
-    compiler_error: Option<String>,
-    camera: Camera,
+/// Rolling window of recent frame times for the status bar.
+#[derive(Default)]
+struct FrameStats {
+    samples: VecDeque<f32>,
+    last: f32,
 }
 
-impl SdfApp {
-    fn new(cc: &eframe::CreationContext<'_>) -> Self {
-        let mut engine = Engine::new();
-        register_rhai_types(&mut engine);
+impl FrameStats {
+    const WINDOW: usize = 120;
+
+    fn record(&mut self, ctx: &egui::Context) {
+        let (stable, last) = ctx.input(|i| (i.stable_dt, i.unstable_dt));
+        self.last = last;
+        self.samples.push_back(stable);
+        if self.samples.len() > Self::WINDOW {
+            self.samples.pop_front();
+        }
+    }
+
+    fn average(&self) -> f32 {
+        if self.samples.is_empty() { 0.0 } else { self.samples.iter().sum::<f32>() / self.samples.len() as f32 }
+    }
+}
+
+/// Sample count for the "Auto" SSAA mode: halves it while frames run over the budget and
+/// doubles it when they come in well under, waiting a full stats window between steps so each
+/// level is measured on its own. With vsync, frames never beat the refresh interval, so a
+/// target at the display rate can only step down.
+struct AutoSsaa {
+    samples: u32,
+    cooldown: usize,
+}
 
-        let default_code = r#"
+impl Default for AutoSsaa {
+    fn default() -> Self {
+        Self { samples: 4, cooldown: FrameStats::WINDOW }
+    }
+}
+
+impl AutoSsaa {
+    fn update(&mut self, stats: &FrameStats, target_fps: f32) {
+        if self.cooldown > 0 {
+            self.cooldown -= 1;
+            return;
+        }
+        let (budget, avg) = (1.0 / target_fps, stats.average());
+        self.samples = if avg > budget * 1.1 && self.samples > 1 {
+            self.samples / 2
+        } else if avg < budget * 0.6 && self.samples < 4 {
+            self.samples * 2
+        } else {
+            return;
+        };
+        self.cooldown = FrameStats::WINDOW;
+    }
+}
+
+/// Two-point distance measurement. Clicks are resolved with a CPU raymarch against the
+/// compiled scene (animations at `t = 0`).
+#[derive(Default)]
+struct Measurement {
+    enabled: bool,
+    points: Vec<Vec3>,
+}
+
+impl Measurement {
+    fn click(&mut self, scene: &SdfNode, camera: &Camera, settings: &SdfSettings, rect: egui::Rect, pos: egui::Pos2) {
+        let rd = camera.ray_dir(rect, pos);
+        if let Some(t) = scene.raycast(camera.pos, rd, settings.march_near, settings.march_far) {
+            if self.points.len() == 2 {
+                self.points.clear();
+            }
+            self.points.push(camera.pos + rd * t);
+        }
+    }
+
+    fn distance(&self) -> Option<f32> {
+        match self.points[..] {
+            [a, b] => Some(a.distance(b)),
+            _ => None,
+        }
+    }
+
+    fn paint(&self, painter: &egui::Painter, camera: &Camera, rect: egui::Rect) {
+        let color = egui::Color32::from_rgb(255, 200, 0);
+        let screen: Vec<_> = self.points.iter().map(|p| camera.project(rect, *p)).collect();
+        for pos in screen.iter().flatten() {
+            painter.circle_filled(*pos, 4.0, color);
+        }
+        if let ([Some(a), Some(b)], Some(dist)) = (&screen[..], self.distance()) {
+            painter.line_segment([*a, *b], egui::Stroke::new(2.0, color));
+            let label = format!("{:.3}", dist);
+            painter.text(a.lerp(*b, 0.5), egui::Align2::CENTER_BOTTOM, label, egui::FontId::proportional(16.0), color);
+        }
+    }
+}
+
+/// Ray march trace of one clicked pixel, replayed on the CPU the way the shader marches it, for
+/// chasing overstepping and rays that run out of steps. It traces the tree the shader is built
+/// from (`SdfApp::shader_scene`).
+#[derive(Default)]
+struct MarchDebug {
+    enabled: bool,
+    /// Ray origin, direction and the steps taken along it.
+    ray: Option<(Vec3, Vec3, MarchTrace)>,
+}
+
+impl MarchDebug {
+    fn click(&mut self, scene: &SdfNode, camera: &Camera, settings: &SdfSettings, rect: egui::Rect, pos: egui::Pos2) {
+        let rd = camera.ray_dir(rect, pos);
+        let scene = SdfApp::shader_scene(scene, settings);
+        let trace = scene.march_trace(camera.pos, rd, settings.march_near, settings.march_far, settings.march_method.relaxation());
+        self.ray = Some((camera.pos, rd, trace));
+    }
+
+    /// Marks every sample point; they line up on the clicked pixel until the camera moves.
+    fn paint(&self, painter: &egui::Painter, camera: &Camera, rect: egui::Rect) {
+        let Some((ro, rd, trace)) = &self.ray else { return };
+        for step in &trace.steps {
+            let color = if step.retreat { egui::Color32::RED } else { egui::Color32::from_rgb(0, 200, 255) };
+            if let Some(pos) = camera.project(rect, *ro + *rd * step.t) {
+                painter.circle_stroke(pos, 3.0, egui::Stroke::new(1.0, color));
+            }
+        }
+    }
+
+    fn window(&mut self, ctx: &egui::Context) {
+        egui::Window::new("Ray March Trace").open(&mut self.enabled).default_width(380.0).show(ctx, |ui| {
+            let Some((ro, rd, trace)) = &self.ray else {
+                ui.label("Click a pixel in the view to trace its ray.");
+                return;
+            };
+            let end = match trace.end {
+                MarchEnd::Hit => "hit the surface",
+                MarchEnd::Miss => "missed (past the far distance)",
+                MarchEnd::StepLimit => "ran out of steps (drawn as a miss)",
+            };
+            let retreats = trace.steps.iter().filter(|s| s.retreat).count();
+            ui.label(format!("{} steps, {} retreats: {}", trace.steps.len(), retreats, end));
+            ui.small("Distances are exact. Away from large parts the shader uses the distance to their bounding box, which is never larger, so it may take more steps there.");
+            ui.separator();
+            egui::ScrollArea::vertical().max_height(360.0).show(ui, |ui| {
+                egui::Grid::new("march_trace").num_columns(4).striped(true).show(ui, |ui| {
+                    for header in ["#", "t", "distance", "position"] {
+                        ui.strong(header);
+                    }
+                    ui.end_row();
+                    for (i, step) in trace.steps.iter().enumerate() {
+                        let p = *ro + *rd * step.t;
+                        ui.label(i.to_string());
+                        ui.label(format!("{:.4}", step.t));
+                        let dist = ui.label(format!("{:+.5}", step.dist));
+                        if step.retreat {
+                            dist.on_hover_text("Relaxed step overshot; marching resumes from the previous sample's sphere");
+                        }
+                        ui.label(format!("[{:.3}, {:.3}, {:.3}]", p.x, p.y, p.z));
+                        ui.end_row();
+                    }
+                });
+            });
+        });
+    }
+}
+
+/// Distance range covered by the slice view legend; the color ramp is within 5% of saturated
+/// beyond it.
+const SLICE_LEGEND_RANGE: f32 = 1.0;
+
+/// Color of signed distance `d` in the slice view, as `render_slice` in the shader computes it
+/// (without the contour bands).
+fn slice_color(d: f32) -> egui::Color32 {
+    let side = if d < 0.0 { Vec3::new(1.0, 0.25, 0.2) } else { Vec3::new(0.2, 0.4, 1.0) };
+    let c = Vec3::ONE.lerp(side, 1.0 - (-3.0 * d.abs()).exp());
+    egui::Rgba::from_rgb(c.x, c.y, c.z).into()
+}
+
+/// Wireframe of every primitive's bounding box (see `SdfNode::primitive_bounds`). Unbounded
+/// boxes, e.g. of repeated or infinite primitives, are skipped, as are edges that reach behind
+/// the camera.
+fn paint_primitive_bounds(painter: &egui::Painter, camera: &Camera, rect: egui::Rect, scene: &SdfNode) {
+    let stroke = egui::Stroke::new(1.0, egui::Color32::from_rgba_unmultiplied(120, 220, 120, 160));
+    for aabb in scene.primitive_bounds().iter().filter(|b| b.is_finite() && !b.is_empty()) {
+        let corners = aabb.corners().map(|c| camera.project(rect, c));
+        // Corner `i` has bit 0, 1, 2 set for the max X, Y, Z side; edges join corners one bit apart
+        for i in 0..8 {
+            for bit in [1, 2, 4] {
+                if i & bit == 0 {
+                    if let (Some(a), Some(b)) = (corners[i], corners[i | bit]) {
+                        painter.line_segment([a, b], stroke);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Color bar in the bottom-right corner of `rect` labelling the slice view's distance colors.
+/// The slice view is the only view with a numeric color ramp: there is no step-count or depth
+/// debug view to label, and the material-ID view's colors are arbitrary.
+fn paint_slice_legend(painter: &egui::Painter, rect: egui::Rect) {
+    let bar = egui::Rect::from_min_size(rect.right_bottom() - egui::vec2(70.0, 190.0), egui::vec2(14.0, 160.0));
+    let backdrop = egui::Rect::from_min_max(bar.left_top() - egui::vec2(16.0, 20.0), bar.right_bottom() + egui::vec2(40.0, 20.0));
+    painter.rect_filled(backdrop, 4.0, egui::Color32::from_black_alpha(160));
+    const STEPS: usize = 64;
+    for i in 0..STEPS {
+        let d = SLICE_LEGEND_RANGE * (1.0 - 2.0 * (i as f32 + 0.5) / STEPS as f32);
+        let y0 = bar.top() + bar.height() * i as f32 / STEPS as f32;
+        let strip = egui::Rect::from_x_y_ranges(bar.x_range(), y0..=y0 + bar.height() / STEPS as f32 + 0.5);
+        painter.rect_filled(strip, 0.0, slice_color(d));
+    }
+    let font = egui::FontId::proportional(11.0);
+    for tick in [-1.0, -0.5, 0.0, 0.5, 1.0] {
+        let y = bar.center().y - tick * bar.height() * 0.5;
+        let label = format!("{:+.1}", tick * SLICE_LEGEND_RANGE);
+        painter.line_segment([egui::pos2(bar.right(), y), egui::pos2(bar.right() + 4.0, y)], egui::Stroke::new(1.0, egui::Color32::WHITE));
+        painter.text(egui::pos2(bar.right() + 7.0, y), egui::Align2::LEFT_CENTER, label, font.clone(), egui::Color32::WHITE);
+    }
+    painter.text(bar.center_top() - egui::vec2(0.0, 4.0), egui::Align2::CENTER_BOTTOM, "outside", font.clone(), egui::Color32::WHITE);
+    painter.text(bar.center_bottom() + egui::vec2(0.0, 4.0), egui::Align2::CENTER_TOP, "inside", font, egui::Color32::WHITE);
+}
+
+/// Puts an RGBA image on the clipboard. Where the clipboard cannot take images, saves it as a
+/// PNG in the temp directory instead. Returns the message for the status bar.
+fn copy_image(size: [u32; 2], rgba: Vec<u8>) -> String {
+    let image = arboard::ImageData { width: size[0] as usize, height: size[1] as usize, bytes: rgba.into() };
+    let error = match arboard::Clipboard::new().and_then(|mut clipboard| clipboard.set_image(image.clone())) {
+        Ok(()) => return format!("Copied {}x{} image", size[0], size[1]),
+        Err(e) => e,
+    };
+    let path = std::env::temp_dir().join("sdf_view.png");
+    match image::save_buffer(&path, &image.bytes, size[0], size[1], image::ExtendedColorType::Rgba8) {
+        Ok(()) => format!("Clipboard unavailable ({error}); saved {}", path.display()),
+        Err(e) => format!("Copy failed: {error}; saving {} also failed: {e}", path.display()),
+    }
+}
+
+const SHADER_TEMPLATE: &str = include_str!("shader_template.wgsl");
+/// Source location of the template, read instead of the embedded copy in template dev mode.
+const TEMPLATE_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/src/shader_template.wgsl");
+
+/// A high-quality still shown instead of the live shader until the camera moves, the view is
+/// resized or a render setting changes.
+struct FrozenFrame {
+    texture_id: egui::TextureId,
+    _texture: eframe::wgpu::Texture,
+    /// View size in points and the parameters it was rendered with.
+    view_size: egui::Vec2,
+    params: RenderParams,
+}
+
+/// Supersampling factor for frozen frames, on top of the shader's own 4x SSAA (always used for
+/// frozen frames, whatever the live sample count, unless the scene is over `ssaa_budget`).
+const FREEZE_SCALE: f32 = 2.0;
+
+/// Rough per-pixel work (estimated `map()` instructions x 128 march steps x samples) above which
+/// a frame risks running into the driver's timeout and losing the device. Integrated and software
+/// adapters get a quarter of it.
+const PIXEL_COST_BUDGET: usize = 2_000_000;
+
+/// The built-in demo, shown at startup when no script is given and by "Reset to Default".
+const DEFAULT_SCENE: &str = r#"
 // Colors and Mirroring demo
 let body = box(1.0, 0.2, 0.5).color(0.8, 0.8, 0.8);
 let wheel = torus(0.4, 0.1).rotate_x(90.0).color(0.2, 0.2, 0.2);
@@ -95,82 +491,914 @@ let wheels = wheel.translate(1.0, 0.0, 0.6).mirror_x().mirror_z();
 
 body.union(wheels)
 "#;
-        
-        let initial_shader = Self::compile_shader(&engine, default_code);
-        let sdf_resources = match initial_shader {
-            Ok(wgsl) => SdfRenderResources::new(cc, &wgsl).map(Arc::new),
-            Err(e) => {
-                println!("Initial compile error: {}", e);
-                None
+
+/// Key or mouse input and what it does, for the shortcut overlay (H or ?). Keep in sync with
+/// the input handling in `Camera::update`, `SdfApp::update` and the editor.
+const SHORTCUTS: &[(&str, &str)] = &[
+    ("Middle drag", "Rotate look, or orbit the pivot"),
+    ("Double-click", "Orbit around the surface point clicked (empty space: back to free look)"),
+    ("Right drag", "Pan"),
+    ("W / A / S / D", "Move horizontally"),
+    ("Q / E", "Move down / up"),
+    ("Left click", "Select the translate under the cursor (Measure: pick points, Trace Ray: trace a pixel)"),
+    ("Left drag on gizmo arrow", "Move the selection"),
+    ("Arrows / PgUp / PgDn", "Nudge the selection"),
+    ("Hold Alt", "Move or nudge without snapping"),
+    ("Ctrl+Enter", "Compile & run"),
+    ("Ctrl+Z", "Undo code"),
+    ("Ctrl+Y / Ctrl+Shift+Z", "Redo code"),
+    ("Ctrl+F", "Find & replace"),
+    ("Tab / Enter", "Accept completion (Up / Down to choose, Esc to dismiss)"),
+    ("F11", "Hide / show the editor"),
+    ("H / ?", "Show / hide this list"),
+];
+
+/// A failed compile, with where Rhai located the problem (1-based line and column) so the
+/// editor can mark it.
+#[derive(Debug)]
+struct ScriptError {
+    message: String,
+    position: Option<(usize, usize)>,
+}
+
+impl From<String> for ScriptError {
+    fn from(message: String) -> Self { Self { message, position: None } }
+}
+
+struct SdfApp {
+    sdf_resources: Option<Arc<SdfRenderResources>>,
+    rhai_engine: Engine,
+    code_text: String,
+    history: CodeHistory,
+    last_snapshot_time: f64,
+    find: FindReplace,
+    autocomplete: Autocomplete,
+    compiler_error: Option<ScriptError>,
+    camera: Camera,
+    camera_moving: bool,
+    /// Hidden with F11 to give the viewport the whole window.
+    show_editor: bool,
+    /// Shortcut overlay, toggled with H or ? while no text field has focus.
+    show_shortcuts: bool,
+    /// "Reset to Default" was clicked and waits for confirmation.
+    confirm_reset: bool,
+    measurement: Measurement,
+    march_debug: MarchDebug,
+    /// Pose typed into the "Camera" panel, applied on request.
+    camera_input: [[f32; 3]; 2],
+    /// Object index and path (see `SdfNode::pick_path`) within that object to the `translate`
+    /// node picked in the viewport.
+    selection: Option<(usize, Vec<usize>)>,
+    gizmo: TranslateGizmo,
+    /// Surface point under the mouse and the field's value there, found by the CPU raymarcher
+    /// while hovering the viewport, for the status bar. `None` while the camera is inside a solid,
+    /// where the shader draws the far side of the shell and a first-hit readout would not match.
+    cursor_probe: Option<(Vec3, f32)>,
+    /// Hover position, view rect, camera position and direction `cursor_probe` was found for; it
+    /// is only marched again when one of them changes or the shader is rebuilt.
+    cursor_probe_key: Option<(egui::Pos2, egui::Rect, Vec3, Vec3)>,
+    /// Viewport size in points as last drawn, recorded in exported shaders' headers.
+    view_size: egui::Vec2,
+    frozen: Option<FrozenFrame>,
+    freeze_requested: bool,
+    /// "Copy Image" was clicked; the view is rendered and copied during the next viewport pass.
+    copy_requested: bool,
+    /// Outcome of the last "Copy Image", shown in the status bar.
+    copy_status: Option<String>,
+    settings: SdfSettings,
+    env_map: Option<Arc<EnvMap>>,
+    env_status: Option<String>,
+    reference: ReferenceImage,
+    frame_stats: FrameStats,
+    auto_ssaa: AutoSsaa,
+    objects: Scene,
+    /// Union of the visible objects, as compiled into the shader.
+    scene: Option<SdfNode>,
+    /// Thin-wall and coincident-face warnings from `SdfNode::thin_result_warnings`, refreshed
+    /// when the script is compiled.
+    scene_warnings: Vec<String>,
+    /// `stats()` of `scene`, refreshed whenever the shader is rebuilt; the SSAA budget reads it
+    /// every frame.
+    scene_stats: Option<SceneStats>,
+    /// The adapter is an integrated GPU or a software renderer; see `PIXEL_COST_BUDGET`.
+    low_power_gpu: bool,
+    /// The adapter renders on the CPU (lavapipe, llvmpipe, WARP). Every extra sample multiplies
+    /// an already slow frame, so supersampling is kept at 1x (see `ssaa_budget`) and the editor
+    /// says why the view is slow.
+    software_renderer: bool,
+    export_path: String,
+    export_count: u32,
+    export_bounds: f32,
+    export_resolution: u32,
+    export_status: Option<String>,
+    export_job: Option<ExportJob>,
+    /// Why the last "Align to Ground" did nothing, if it did not.
+    ground_status: Option<String>,
+    /// The script or project folder opened at startup, next to which the look is saved.
+    script_path: Option<PathBuf>,
+    /// Render settings saved with the opened scene, until the user applies or declines them.
+    pending_look: Option<rhai::Map>,
+    /// Result of the last save or load of the scene's render settings.
+    look_status: Option<String>,
+}
+
+impl SdfApp {
+    /// `script` is a `.rhai` file, or a project folder containing `main.rhai`, to open instead of
+    /// the built-in demo.
+    fn new(cc: &eframe::CreationContext<'_>, script: Option<PathBuf>) -> Self {
+        let mut engine = Engine::new();
+        register_rhai_types(&mut engine);
+
+        // Problems with the startup script are reported in the editor, over the default scene
+        let mut startup_error = None;
+        let code = match script.as_deref().map(Self::read_script) {
+            Some(Ok(code)) => code,
+            Some(Err(e)) => {
+                startup_error = Some(ScriptError::from(format!("{}; showing the default scene", e)));
+                DEFAULT_SCENE.to_string()
             }
+            None => DEFAULT_SCENE.to_string(),
         };
 
+        // A look saved with the scene is offered once the window is up
+        let mut look_status = None;
+        let pending_look = script.as_deref().map(scene_settings_path).filter(|path| path.is_file()).and_then(|path| {
+            let parsed = std::fs::read_to_string(&path).map_err(|e| e.to_string())
+                .and_then(|json| engine.parse_json(json, true).map_err(|e| e.to_string()));
+            parsed.map_err(|e| look_status = Some(format!("Ignored {}: {}", path.display(), e))).ok()
+        });
+
+        let device_type = cc.wgpu_render_state.as_ref().map(|rs| rs.adapter.get_info().device_type);
+        let software_renderer = device_type == Some(eframe::wgpu::DeviceType::Cpu);
+        let mut settings = SdfSettings::default();
+        if software_renderer {
+            settings.ssaa_samples = 1;
+        }
+        let objects = Self::eval_scene(&engine, &code).unwrap_or_else(|e| {
+            startup_error = Some(e);
+            Scene::default()
+        });
+        let initial_scene = objects.combined(settings.explode);
+        let sdf_resources = initial_scene.as_ref()
+            .and_then(|scene| SdfRenderResources::new(cc, &Self::compile_shader(scene, &settings), None, &scene.height_maps(), &scene.mesh_volumes()))
+            .map(Arc::new);
+
         Self {
             sdf_resources,
             rhai_engine: engine,
-            code_text: default_code.to_string(),
-            compiler_error: None,
+            history: CodeHistory::new(&code),
+            code_text: code,
+            last_snapshot_time: 0.0,
+            find: FindReplace::default(),
+            autocomplete: Autocomplete::default(),
+            compiler_error: startup_error,
             camera: Camera::default(),
+            camera_moving: false,
+            show_editor: true,
+            show_shortcuts: false,
+            confirm_reset: false,
+            measurement: Measurement::default(),
+            march_debug: MarchDebug::default(),
+            camera_input: [[5.0, 5.0, 5.0], [0.0; 3]],
+            selection: None,
+            gizmo: TranslateGizmo::default(),
+            cursor_probe: None,
+            cursor_probe_key: None,
+            view_size: egui::vec2(16.0, 9.0),
+            frozen: None,
+            freeze_requested: false,
+            copy_requested: false,
+            copy_status: None,
+            settings,
+            env_map: None,
+            env_status: None,
+            reference: ReferenceImage::default(),
+            frame_stats: FrameStats::default(),
+            auto_ssaa: AutoSsaa::default(),
+            objects,
+            scene_warnings: initial_scene.as_ref().map_or_else(Vec::new, |s| s.thin_result_warnings()),
+            scene_stats: initial_scene.as_ref().map(SdfNode::stats),
+            low_power_gpu: matches!(device_type, Some(eframe::wgpu::DeviceType::IntegratedGpu | eframe::wgpu::DeviceType::Cpu)),
+            software_renderer,
+            scene: initial_scene,
+            export_path: "export.ply".to_string(),
+            export_count: 20000,
+            export_bounds: 5.0,
+            export_resolution: 96,
+            export_status: None,
+            ground_status: None,
+            script_path: script,
+            pending_look,
+            look_status,
+            export_job: None,
         }
     }
 
-    fn compile_shader(engine: &Engine, code: &str) -> Result<String, String> {
+    /// Compiles and runs the editor's code, replacing the scene; errors leave the old one.
+    fn run_script(&mut self, frame: &eframe::Frame) {
+        self.history.push(&self.code_text);
+        match Self::eval_scene(&self.rhai_engine, &self.code_text) {
+            Ok(mut objects) => {
+                self.compiler_error = None;
+                self.ground_status = None;
+                objects.keep_visibility(&self.objects);
+                self.scene = objects.combined(self.settings.explode);
+                self.scene_warnings = self.scene.as_ref().map_or_else(Vec::new, |s| s.thin_result_warnings());
+                self.objects = objects;
+                self.selection = None;
+                self.rebuild_resources(frame);
+            }
+            Err(e) => self.compiler_error = Some(e),
+        }
+    }
+
+    fn read_script(path: &Path) -> Result<String, String> {
+        let file = if path.is_dir() { path.join("main.rhai") } else { path.to_path_buf() };
+        std::fs::read_to_string(&file).map_err(|e| format!("Could not open {}: {}", file.display(), e))
+    }
+
+    /// Runs the script. Objects added with `scene.add(name, shape)` are kept by name; a shape
+    /// the script evaluates to becomes one more object called "main".
+    fn eval_scene(engine: &Engine, code: &str) -> Result<Scene, ScriptError> {
         let mut scope = Scope::new();
-        let result = engine.eval_with_scope::<SdfNode>(&mut scope, code)
-            .map_err(|e| format!("Rhai Error: {}", e))?;
+        scope.push("scene", Scene::default());
+        let value = engine.eval_with_scope::<Dynamic>(&mut scope, code).map_err(|e| ScriptError {
+            message: format!("Rhai Error: {}", e),
+            position: e.position().line().map(|line| (line, e.position().position().unwrap_or(1))),
+        })?;
+        let mut scene = scope.get_value::<Scene>("scene").unwrap_or_default();
+        if value.is::<SdfNode>() {
+            scene.add("main", value.cast::<SdfNode>());
+        } else if !value.is_unit() || scene.objects.is_empty() {
+            // A script that ends in `let shape = ...;` or a statement evaluates to something else;
+            // say so plainly instead of surfacing a bare type-cast error
+            let got = if value.is_unit() { "Unit".to_string() } else { engine.map_type_name(value.type_name()).to_string() };
+            return Err(ScriptError::from(format!(
+                "Script must evaluate to an SdfNode or call scene.add(name, shape); got {} \u{2014} did you forget to return your shape?",
+                got,
+            )));
+        }
+        Ok(scene)
+    }
+
+    /// The tree the shader is generated from: `scene` with the global smoothing applied, optimized.
+    fn shader_scene(scene: &SdfNode, settings: &SdfSettings) -> SdfNode {
+        if settings.global_smooth > 0.0 {
+            scene.with_global_smooth(settings.global_smooth).optimize()
+        } else {
+            scene.optimize()
+        }
+    }
 
+    fn compile_shader(scene: &SdfNode, settings: &SdfSettings) -> String {
         let mut generator = WgslGenerator::new();
-        let map_fn_body = generator.generate(&result);
+        let map_fn_body = generator.generate(&Self::shader_scene(scene, settings));
+        let offsets: Vec<String> = settings.aa_pattern.offsets().iter()
+            .map(|[x, y]| format!("vec2<f32>({x:.4}, {y:.4})"))
+            .collect();
+        let generated = format!(
+            "const FOCAL_LENGTH: f32 = {:.4};\nconst MARCH_NEAR: f32 = {:.4};\nconst MARCH_FAR: f32 = {:.4};\n\
+             const MARCH_RELAXATION: f32 = {:.4};\nfn ssaa_offset(i: u32) -> vec2<f32> {{\n    var offsets = array<vec2<f32>, 4>({});\n    return offsets[i];\n}}\n{}",
+            FOCAL_LENGTH, settings.march_near, settings.march_far, settings.march_method.relaxation(), offsets.join(", "), map_fn_body,
+        );
+
+        Self::shader_template(settings).replace("// {{MAP_FUNCTION_HERE}}", &generated)
+    }
+
+    /// The embedded template, or in template dev mode the copy on disk so helper edits apply on
+    /// the next compile without rebuilding. Falls back to the embedded one if the file is missing.
+    fn shader_template(settings: &SdfSettings) -> Cow<'static, str> {
+        if settings.template_from_disk {
+            match std::fs::read_to_string(TEMPLATE_PATH) {
+                Ok(template) => return Cow::Owned(template),
+                Err(e) => log::warn!("Using embedded shader template, failed to read {}: {}", TEMPLATE_PATH, e),
+            }
+        }
+        Cow::Borrowed(SHADER_TEMPLATE)
+    }
 
-        let template = include_str!("shader_template.wgsl");
-        let full_wgsl = template.replace("// {{MAP_FUNCTION_HERE}}", &map_fn_body);
+    /// Selects the innermost `translate` enclosing the shape under `pos`.
+    fn pick(&mut self, rect: egui::Rect, pos: egui::Pos2) {
+        let Some(scene) = &self.scene else { return };
+        let rd = self.camera.ray_dir(rect, pos);
+        self.selection = scene.raycast(self.camera.pos, rd, self.settings.march_near, self.settings.march_far).and_then(|t| {
+            let hit = self.camera.pos + rd * t;
+            let object = self.objects.object_at(hit, self.settings.explode)?;
+            let node = &self.objects.objects[object].node;
+            let mut path = node.pick_path(hit - self.objects.explode_offsets(self.settings.explode)[object]);
+            while !matches!(node.node_at(&path)?.op, SdfOp::Translate { .. }) {
+                path.pop()?;
+            }
+            Some((object, path))
+        });
+    }
+
+    /// Moves every object by the same amount so the lowest surface of the visible scene rests on
+    /// the ground (y = 0, or z = 0 with Z up), by wrapping each object's root in a `translate`.
+    /// Like nudging, this lives in the compiled scene only. Returns why nothing moved, if so.
+    fn align_to_ground(&mut self, frame: &eframe::Frame) -> Option<String> {
+        let Some(scene) = &self.scene else { return Some("Nothing to align, compile a scene first.".to_string()) };
+        let axis = if self.settings.z_up { 2 } else { 1 };
+        let Some(lowest) = scene.lowest_point(axis) else {
+            return Some("The scene has no lowest point (it is empty or extends forever downwards).".to_string());
+        };
+        if lowest.abs() < 1e-5 {
+            return None;
+        }
+        let mut offset = Vec3::ZERO;
+        offset[axis] = -lowest;
+        for object in &mut self.objects.objects {
+            object.node = object.node.translate(offset.x, offset.y, offset.z);
+        }
+        if let Some((_, path)) = &mut self.selection {
+            path.insert(0, 0);
+        }
+        self.scene = self.objects.combined(self.settings.explode);
+        self.rebuild_resources(frame);
+        None
+    }
+
+    /// The selected object's root node and the path to the selected node inside it.
+    fn selected_node(&self) -> Option<(&SdfNode, &[usize])> {
+        let (object, path) = self.selection.as_ref()?;
+        Some((&self.objects.objects.get(*object)?.node, path))
+    }
+
+    /// Moves the selected `translate` node by `delta`, rounding the moved axes to `snap` if
+    /// given, and regenerates the shader. The change lives in the compiled scene only;
+    /// recompiling the code discards it.
+    fn nudge_selection(&mut self, delta: Vec3, snap: Option<f32>, frame: &eframe::Frame) {
+        let Some(mut offset) = self.selected_offset() else { return };
+        for (value, d) in offset.iter_mut().zip(delta.to_array()) {
+            if d != 0.0 {
+                *value += d;
+                if let Some(step) = snap {
+                    *value = (*value / step).round() * step;
+                }
+            }
+        }
+        self.set_selected_offset(offset, frame);
+    }
+
+    fn set_selected_offset(&mut self, new_offset: [f32; 3], frame: &eframe::Frame) {
+        let Some((object, path)) = &self.selection else { return };
+        let Some(root) = self.objects.objects.get_mut(*object).map(|o| &mut o.node) else { return };
+        if let Some(SdfNode { op: SdfOp::Translate { offset, .. } }) = root.node_at_mut(path) {
+            if *offset != new_offset {
+                *offset = new_offset;
+                self.scene = self.objects.combined(self.settings.explode);
+                self.rebuild_resources(frame);
+            }
+        }
+    }
+
+    /// Draws the move gizmo on the selected node and applies axis drags to its offset.
+    fn selection_gizmo(&mut self, ui: &egui::Ui, response: &egui::Response, frame: &eframe::Frame) {
+        let (Some((scene, path)), Some(offset)) = (self.selected_node(), self.selected_offset()) else { return };
+        let exploded = self.selection.as_ref().map_or(Vec3::ZERO, |(object, _)| self.objects.explode_offsets(self.settings.explode)[*object]);
+        let pivot = scene.to_world(path, Vec3::from(offset), true) + exploded;
+        let axes = [Vec3::X, Vec3::Y, Vec3::Z].map(|axis| scene.to_world(path, axis, false));
+        let size = 0.15 * (pivot - self.camera.pos).length();
+        let rect = response.rect;
+        let Some(handles) = TranslateGizmo::handles(pivot, axes, size, |p| self.camera.project(rect, p)) else { return };
+
+        self.gizmo.paint(ui.painter(), &handles);
+        if let Some(mut new_offset) = self.gizmo.interact(response, &handles, offset) {
+            let bypass_snap = ui.input(|i| i.modifiers.alt);
+            if self.settings.snap && !bypass_snap {
+                let step = self.settings.snap_increment;
+                for (value, old) in new_offset.iter_mut().zip(offset) {
+                    if *value != old {
+                        *value = (*value / step).round() * step;
+                    }
+                }
+            }
+            self.set_selected_offset(new_offset, frame);
+        }
+    }
+
+    fn selected_offset(&self) -> Option<[f32; 3]> {
+        let (root, path) = self.selected_node()?;
+        match root.node_at(path)?.op {
+            SdfOp::Translate { offset, .. } => Some(offset),
+            _ => None,
+        }
+    }
+
+    fn unfreeze(&mut self, frame: &eframe::Frame) {
+        if let (Some(frozen), Some(rs)) = (self.frozen.take(), frame.wgpu_render_state()) {
+            rs.renderer.write().free_texture(&frozen.texture_id);
+        }
+    }
 
-        Ok(full_wgsl)
+    /// Samples per pixel for live rendering: the fixed setting, or the auto controller's choice
+    /// (which never goes over `ssaa_budget`).
+    fn ssaa_samples(&self) -> u32 {
+        if self.settings.auto_ssaa { self.auto_ssaa.samples.min(self.ssaa_budget()) } else { self.settings.ssaa_samples }
+    }
+
+    /// Highest sample count (4, 2 or 1) that keeps the current scene within `PIXEL_COST_BUDGET`;
+    /// always 1 on a software renderer.
+    fn ssaa_budget(&self) -> u32 {
+        if self.software_renderer {
+            return 1;
+        }
+        let Some(stats) = &self.scene_stats else { return 4 };
+        let budget = if self.low_power_gpu { PIXEL_COST_BUDGET / 4 } else { PIXEL_COST_BUDGET };
+        let cost = stats.estimated_instructions.max(1) * 128;
+        [4, 2].into_iter().find(|samples| cost * *samples as usize <= budget).unwrap_or(1)
+    }
+
+    fn render_params(&self) -> RenderParams {
+        RenderParams {
+            env_intensity: self.settings.hdri_intensity,
+            focus_distance: self.settings.focus_distance,
+            aperture: self.settings.aperture,
+            background: self.settings.background,
+            slice_plane: if self.settings.slice_view {
+                let n = self.settings.slice_axis.normal();
+                [n[0], n[1], n[2], self.settings.slice_offset]
+            } else {
+                [0.0; 4]
+            },
+            inside_tint: self.settings.tint_inside.then_some(self.settings.inside_tint),
+            ssaa_samples: self.ssaa_samples(),
+            show_ids: self.settings.show_ids,
+            z_up: self.settings.z_up,
+            light_dir: if self.settings.headlight {
+                let (front, _, _) = self.camera.basis();
+                self.camera.to_local(-front).into()
+            } else {
+                Vec3::from(self.settings.light_dir).normalize_or(Vec3::Y).into()
+            },
+            ambient: (Vec3::from(self.settings.ambient_color) * self.settings.ambient_intensity).into(),
+            gamma_correct: self.settings.gamma_correct,
+            outline: self.settings.outlines.then(|| {
+                [1.0 - self.settings.outline_crease_deg.to_radians().cos(), self.settings.outline_depth]
+            }),
+        }
+    }
+
+    /// Comment block for the top of an exported shader, recording the camera, projection and
+    /// settings it was exported with, so the image can be reproduced outside the app. The
+    /// projection is baked in (`FOCAL_LENGTH`); the rest arrives through the uniforms.
+    fn shader_header(&self, params: &RenderParams) -> String {
+        let (front, right, up) = self.camera.basis();
+        let v = |v: Vec3| format!("[{:.4}, {:.4}, {:.4}]", v.x, v.y, v.z);
+        let on_off = |on: bool| if on { "on" } else { "off" };
+        let s = &self.settings;
+        let aspect = self.view_size.x / self.view_size.y.max(1.0);
+        let vertical_fov = 2.0 * (1.0 / FOCAL_LENGTH).atan();
+        let horizontal_fov = 2.0 * (aspect / FOCAL_LENGTH).atan();
+        let aa_pattern = match s.aa_pattern {
+            AaPattern::Grid => "grid",
+            AaPattern::RotatedGrid => "rotated grid",
+            AaPattern::Halton => "Halton",
+        };
+        let lines = [
+            "Exported from the SDF POC. Camera and settings at export time:".to_string(),
+            format!("camera: position {}, front {}, right {}, up {}", v(self.camera.pos), v(front), v(right), v(up)),
+            format!("camera: yaw {:.2}°, pitch {:.2}°, {} up", self.camera.yaw.to_degrees(), self.camera.pitch.to_degrees(), if s.z_up { "Z" } else { "Y" }),
+            format!(
+                "projection: pinhole, FOCAL_LENGTH {FOCAL_LENGTH} (vertical FOV {:.2}°); viewport {:.0}x{:.0}, aspect {:.4} (horizontal FOV {:.2}°)",
+                vertical_fov.to_degrees(), self.view_size.x, self.view_size.y, aspect, horizontal_fov.to_degrees(),
+            ),
+            "projection: the horizontal FOV follows the aspect of the viewport drawn into (rect_data)".to_string(),
+            format!("SSAA: {} samples, {} pattern", params.ssaa_samples, aa_pattern),
+            format!(
+                "march: near {}, far {}, relaxation {}, global smoothing {}",
+                s.march_near, s.march_far, s.march_method.relaxation(), s.global_smooth,
+            ),
+            format!(
+                "light: {} {}, ambient {}, background {}, environment intensity {} (no environment map exported)",
+                if s.headlight { "headlight" } else { "direction" }, v(params.light_dir.into()), v(params.ambient.into()),
+                v(params.background.into()), params.env_intensity,
+            ),
+            format!(
+                "depth of field: focus {}, aperture {}; sRGB output {}; outlines {}; color by primitive {}; slice view {}",
+                params.focus_distance, params.aperture, on_off(params.gamma_correct), on_off(params.outline.is_some()),
+                on_off(params.show_ids), on_off(s.slice_view),
+            ),
+        ];
+        lines.iter().map(|line| format!("// {line}\n")).collect::<String>() + "\n"
+    }
+
+    /// Writes the current scene, camera and shading settings as a standalone WebGPU page next to
+    /// the export path.
+    fn export_web_viewer(&self, scene: &SdfNode) -> String {
+        let path = Path::new(&self.export_path).with_extension("html");
+        let params = self.render_params();
+        let wgsl = format!("{}{}", self.shader_header(&params), Self::compile_shader(scene, &self.settings));
+        let uniforms = sdf_widget::uniform_floats(&self.camera.uniform_data(), &params);
+        match web_export::export_web_viewer(&path, &wgsl, &uniforms, &scene.height_maps(), &scene.mesh_volumes(), scene.is_animated()) {
+            Ok(()) => format!("Wrote web viewer to {}", path.display()),
+            Err(e) => format!("Export failed: {}", e),
+        }
+    }
+
+    /// Recreates the GPU resources for the current scene, its height maps and the environment map.
+    fn rebuild_resources(&mut self, frame: &eframe::Frame) {
+        self.unfreeze(frame);
+        self.cursor_probe_key = None;
+        self.scene_stats = self.scene.as_ref().map(SdfNode::stats);
+        let Some(rs) = frame.wgpu_render_state() else { return };
+        let Some(scene) = &self.scene else {
+            // Every object is hidden
+            self.sdf_resources = None;
+            return;
+        };
+        let wgsl = Self::compile_shader(scene, &self.settings);
+        if let Some(new_res) = SdfRenderResources::from_wgpu_state(rs, &wgsl, self.env_map.as_deref(), &scene.height_maps(), &scene.mesh_volumes()) {
+            self.sdf_resources = Some(Arc::new(new_res));
+        } else {
+            self.compiler_error = Some(ScriptError::from("Failed to create WGPU resources".to_string()));
+        }
+    }
+
+    /// Writes the look settings next to the opened script (see `scene_settings_path`).
+    fn save_scene_look(&mut self) {
+        let Some(script) = &self.script_path else { return };
+        let path = scene_settings_path(script);
+        self.look_status = Some(match std::fs::write(&path, self.settings.look_json()) {
+            Ok(()) => format!("Saved to {}", path.display()),
+            Err(e) => format!("Could not write {}: {}", path.display(), e),
+        });
+    }
+
+    /// Switches to the look saved with the scene and rebuilds what it affects.
+    fn apply_scene_look(&mut self, look: &rhai::Map, frame: &eframe::Frame) {
+        if let Err(e) = self.settings.apply_look(look) {
+            self.look_status = Some(format!("The scene's settings were not applied: {}", e));
+            return;
+        }
+        if self.software_renderer {
+            self.settings.ssaa_samples = 1;
+            self.settings.auto_ssaa = false;
+        }
+        self.camera.set_z_up(self.settings.z_up);
+        self.look_status = Some("Applied the scene's settings".to_string());
+        self.rebuild_resources(frame);
+        if !self.settings.hdri_path.trim().is_empty() {
+            self.load_env_map(frame);
+        }
+    }
+
+    fn load_env_map(&mut self, frame: &eframe::Frame) {
+        let path = self.settings.hdri_path.trim();
+        if path.is_empty() {
+            self.env_map = None;
+            self.env_status = None;
+        } else {
+            let max_size = frame.wgpu_render_state().map_or(u32::MAX, |rs| rs.device.limits().max_texture_dimension_2d);
+            match EnvMap::load(path, max_size) {
+                Ok(env) => {
+                    self.env_status = Some(format!("Loaded {} ({}x{})", path, env.width, env.height));
+                    self.env_map = Some(Arc::new(env));
+                }
+                Err(e) => {
+                    self.env_status = Some(e);
+                    return;
+                }
+            }
+        }
+        self.rebuild_resources(frame);
     }
 }
 
 impl eframe::App for SdfApp {
     fn update(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
-        ctx.request_repaint(); 
+        self.frame_stats.record(ctx);
+        if self.settings.auto_ssaa {
+            self.auto_ssaa.update(&self.frame_stats, self.settings.target_fps);
+        }
+        self.reference.handle_dropped_files(ctx);
+        if let Some(job) = &mut self.export_job {
+            match job.poll() {
+                Some(result) => {
+                    self.export_status = Some(result.unwrap_or_else(|e| e));
+                    self.export_job = None;
+                }
+                // Keep polling while the worker runs, even with the Export section collapsed
+                None => ctx.request_repaint_after(std::time::Duration::from_millis(100)),
+            }
+        }
+
+        // Tab is left to the code editor and autocomplete, so only F11 toggles the panel
+        if ctx.input_mut(|i| i.consume_key(egui::Modifiers::NONE, egui::Key::F11)) {
+            self.show_editor = !self.show_editor;
+        }
 
-        egui::SidePanel::left("editor_panel").resizable(true).default_width(400.0).show(ctx, |ui| {
+        if ctx.input_mut(|i| i.consume_key(egui::Modifiers::COMMAND, egui::Key::F)) {
+            self.find.open = !self.find.open;
+        }
+
+        // Plain letters belong to the code editor while it has focus
+        if !ctx.wants_keyboard_input()
+            && ctx.input_mut(|i| i.consume_key(egui::Modifiers::NONE, egui::Key::H) || i.key_pressed(egui::Key::Questionmark))
+        {
+            self.show_shortcuts = !self.show_shortcuts;
+        }
+
+        // Handle undo/redo before the TextEdit sees the keys, so its own limited undo stays out of the way
+        let (redo, undo) = ctx.input_mut(|i| {
+            let redo = i.consume_key(egui::Modifiers::COMMAND | egui::Modifiers::SHIFT, egui::Key::Z)
+                || i.consume_key(egui::Modifiers::COMMAND, egui::Key::Y);
+            (redo, i.consume_key(egui::Modifiers::COMMAND, egui::Key::Z))
+        });
+        if undo {
+            if let Some(code) = self.history.undo(&self.code_text) { self.code_text = code; }
+        } else if redo {
+            if let Some(code) = self.history.redo() { self.code_text = code; }
+        }
+
+        // Arrow keys nudge the selected node unless a text field has focus
+        if self.selection.is_some() && !ctx.wants_keyboard_input() {
+            let step = self.settings.nudge_step;
+            let bypass_snap = ctx.input(|i| i.modifiers.alt);
+            let snap = (self.settings.snap && !bypass_snap).then_some(self.settings.snap_increment);
+            let delta = ctx.input(|i| {
+                let mut d = Vec3::ZERO;
+                if i.key_pressed(egui::Key::ArrowRight) { d.x += step; }
+                if i.key_pressed(egui::Key::ArrowLeft) { d.x -= step; }
+                if i.key_pressed(egui::Key::ArrowDown) { d.z += step; }
+                if i.key_pressed(egui::Key::ArrowUp) { d.z -= step; }
+                if i.key_pressed(egui::Key::PageUp) { d.y += step; }
+                if i.key_pressed(egui::Key::PageDown) { d.y -= step; }
+                // Keys map to the Y-up directions above; PgUp / PgDn follow the up axis
+                self.camera.to_world(d)
+            });
+            if delta != Vec3::ZERO {
+                self.nudge_selection(delta, snap, frame);
+            }
+        }
+
+        let now = ctx.input(|i| i.time);
+        if now - self.last_snapshot_time >= 2.0 {
+            self.history.push(&self.code_text);
+            self.last_snapshot_time = now;
+        }
+
+        egui::SidePanel::left("editor_panel").resizable(true).default_width(400.0).show_animated(ctx, self.show_editor, |ui| {
             ui.heading("Rhai SDF Editor");
-            ui.label("Controls:");
-            ui.label("- Drag Middle Mouse: Rotate Look");
-            ui.label("- W/A/S/D: Move Horizontal");
-            ui.label("- Q/E: Move Down/Up");
+            ui.horizontal(|ui| {
+                ui.label("Middle drag to look, right drag to pan, W/A/S/D/Q/E to move.");
+                ui.toggle_value(&mut self.show_shortcuts, "All shortcuts (H)");
+            });
             ui.separator();
             
-            if ui.button("Compile & Run (Ctrl+Enter)").clicked() || 
-               (ui.input(|i| i.key_pressed(egui::Key::Enter) && i.modifiers.command)) 
-            {
-                match Self::compile_shader(&self.rhai_engine, &self.code_text) {
-                    Ok(wgsl) => {
-                        self.compiler_error = None;
-                        if let Some(rs) = frame.wgpu_render_state() {
-                            if let Some(new_res) = SdfRenderResources::from_wgpu_state(&rs, &wgsl) {
-                                self.sdf_resources = Some(Arc::new(new_res));
-                            } else {
-                                self.compiler_error = Some("Failed to create WGPU resources".to_string());
-                            }
-                        }
-                    }
-                    Err(e) => self.compiler_error = Some(e),
+            let compile = ui.horizontal(|ui| {
+                if ui.button("Reset to Default").on_hover_text("Replace the code with the built-in demo scene").clicked() {
+                    self.confirm_reset = true;
                 }
+                ui.button("Compile & Run (Ctrl+Enter)").clicked()
+            }).inner;
+            if compile || ui.input(|i| i.key_pressed(egui::Key::Enter) && i.modifiers.command) {
+                self.run_script(frame);
             }
 
             if let Some(err) = &self.compiler_error {
-                ui.colored_label(egui::Color32::RED, err);
+                ui.colored_label(egui::Color32::RED, &err.message);
+            }
+            if self.software_renderer {
+                ui.colored_label(egui::Color32::YELLOW, "No GPU found: rendering on the CPU, which is slow. Supersampling is off; \
+                    a smaller window or fewer objects keeps the view responsive");
+            }
+            if let Some(scene) = self.scene.as_ref().filter(|s| s.step_factor() < 1.0) {
+                ui.colored_label(egui::Color32::YELLOW, format!(
+                    "Scene has approximate distances: marching at step factor {:.2}, so thin parts may show holes and rendering is slower",
+                    scene.step_factor(),
+                ));
+            }
+            for warning in &self.scene_warnings {
+                ui.colored_label(egui::Color32::YELLOW, warning);
+            }
+
+            ui.collapsing(format!("Objects ({})", self.objects.objects.len()), |ui| {
+                let mut changed = false;
+                for object in &mut self.objects.objects {
+                    changed |= ui.checkbox(&mut object.visible, &object.name).changed();
+                }
+                let explode = ui.add(egui::Slider::new(&mut self.settings.explode, 0.0..=2.0).text("Explode"))
+                    .on_hover_text("Move each object away from the center of the visible objects, for exploded views of assemblies");
+                // Each change recompiles the shader, so a drag only applies once it is released
+                changed |= explode.drag_stopped() || (explode.changed() && !explode.dragged());
+                if ui.button("Align to Ground")
+                    .on_hover_text("Move all objects up or down so the lowest visible surface sits on the ground plane. Recompiling the code undoes this")
+                    .clicked()
+                {
+                    self.ground_status = self.align_to_ground(frame);
+                }
+                if let Some(status) = &self.ground_status {
+                    ui.label(status);
+                }
+                if changed {
+                    if self.selection.as_ref().is_some_and(|(object, _)| !self.objects.objects[*object].visible) {
+                        self.selection = None;
+                    }
+                    self.scene = self.objects.combined(self.settings.explode);
+                    self.rebuild_resources(frame);
+                }
+            });
+
+            ui.collapsing("Statistics", |ui| {
+                let Some(stats) = self.scene_stats else {
+                    ui.label("No visible objects.");
+                    return;
+                };
+                egui::Grid::new("scene_stats").num_columns(2).show(ui, |ui| {
+                    ui.label("Primitives:");
+                    ui.label(stats.primitives.to_string());
+                    ui.end_row();
+                    ui.label("Boolean ops:");
+                    ui.label(stats.booleans.to_string());
+                    ui.end_row();
+                    ui.label("Max tree depth:");
+                    ui.label(stats.max_depth.to_string());
+                    ui.end_row();
+                    ui.label("Est. instructions / map():").on_hover_text(
+                        "Rough guess. Transforms are inlined into every primitive below them, so deep transform chains over many primitives grow this fastest",
+                    );
+                    ui.label(format!("~{}", stats.estimated_instructions));
+                    ui.end_row();
+                    ui.label("Non-exact ops:");
+                    ui.label(if stats.non_exact { "yes (shorter march steps)" } else { "no" });
+                    ui.end_row();
+                });
+            });
+
+            ui.collapsing("Camera", |ui| {
+                for (label, value) in ["Position:", "Look at:"].into_iter().zip(&mut self.camera_input) {
+                    ui.horizontal(|ui| {
+                        ui.label(label);
+                        for v in value.iter_mut() {
+                            ui.add(egui::DragValue::new(v).speed(0.05).max_decimals(3));
+                        }
+                    });
+                }
+                ui.horizontal(|ui| {
+                    if ui.button("Apply").clicked() {
+                        let [pos, target] = self.camera_input.map(Vec3::from);
+                        self.camera.look_at(pos, target);
+                        if self.camera.pivot.is_some() {
+                            self.camera.pivot = Some(target);
+                        }
+                        self.unfreeze(frame);
+                    }
+                    if ui.button("Use current").on_hover_text("Fill in the current position and the orbit pivot, or a point 5 units ahead").clicked() {
+                        let (front, _, _) = self.camera.basis();
+                        let target = self.camera.pivot.unwrap_or(self.camera.pos + front * 5.0);
+                        self.camera_input = [self.camera.pos.into(), target.into()];
+                    }
+                });
+            });
+
+            ui.collapsing("Settings", |ui| {
+                // Options baked into the generated shader
+                let baked = |s: &SdfSettings| (s.march_near, s.march_far, s.aa_pattern, s.march_method, s.global_smooth);
+                let before = baked(&self.settings);
+                if self.settings.ui(ui, if self.software_renderer { 1 } else { 4 }) {
+                    self.load_env_map(frame);
+                } else if before != baked(&self.settings) {
+                    self.rebuild_resources(frame);
+                }
+                if self.camera.z_up != self.settings.z_up {
+                    self.camera.set_z_up(self.settings.z_up);
+                    self.unfreeze(frame);
+                }
+                if let Some(status) = &self.env_status {
+                    ui.label(status);
+                }
+                let save = ui.add_enabled(self.script_path.is_some(), egui::Button::new("Save with Scene"))
+                    .on_hover_text("Write the sampling, lighting, environment, lens and output settings next to the script, \
+                        to be offered when the scene is opened")
+                    .on_disabled_hover_text("Open a script or project folder to save settings with it");
+                if save.clicked() {
+                    self.save_scene_look();
+                }
+                if let Some(status) = &self.look_status {
+                    ui.label(status);
+                }
+            });
+
+            ui.collapsing("Reference Image", |ui| {
+                self.reference.ui(ui);
+            });
+
+            ui.collapsing("Export", |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Path:");
+                    ui.text_edit_singleline(&mut self.export_path);
+                });
+                ui.add(egui::DragValue::new(&mut self.export_count).range(1..=1_000_000).prefix("Points: "));
+                ui.add(egui::DragValue::new(&mut self.export_bounds).range(0.1..=100.0).speed(0.1).prefix("Bounds: "));
+                ui.add(egui::DragValue::new(&mut self.export_resolution).range(8..=512).prefix("Resolution: "));
+                let idle = self.export_job.is_none();
+                let point_cloud = ui.add_enabled(idle, egui::Button::new("Export Point Cloud (PLY)")).clicked();
+                let mesh = ui.add_enabled(idle, egui::Button::new("Export Mesh (Dual Contouring, OBJ/PLY/glTF)")).clicked();
+                if ui.button("Export Web Viewer (HTML)").on_hover_text("Standalone WebGPU page with orbit controls, saved as .html").clicked() {
+                    self.export_status = Some(match &self.scene {
+                        Some(scene) => self.export_web_viewer(scene),
+                        None => "Nothing to export, compile a scene first.".to_string(),
+                    });
+                }
+                if point_cloud || mesh {
+                    match self.scene.clone() {
+                        Some(scene) => {
+                            let (path, count, bounds, resolution) = (self.export_path.clone(), self.export_count, self.export_bounds, self.export_resolution);
+                            self.export_status = None;
+                            self.export_job = Some(ExportJob::spawn(move |progress| {
+                                let result = if point_cloud {
+                                    scene.export_point_cloud_ply(count, bounds, &path, progress).map(|n| format!("Wrote {} points to {}", n, path))
+                                } else {
+                                    scene.export_mesh_dual_contour(resolution, bounds, &path, progress).map(|n| format!("Wrote {} triangles to {}", n, path))
+                                };
+                                result.map_err(|e| match e.kind() {
+                                    std::io::ErrorKind::Interrupted => "Export cancelled.".to_string(),
+                                    _ => format!("Export failed: {}", e),
+                                })
+                            }));
+                        }
+                        None => self.export_status = Some("Nothing to export, compile a scene first.".to_string()),
+                    }
+                }
+                if let Some(job) = &self.export_job {
+                    ui.horizontal(|ui| {
+                        ui.add(egui::ProgressBar::new(job.progress).show_percentage().desired_width(200.0));
+                        if ui.button("Cancel").clicked() {
+                            job.cancel();
+                        }
+                    });
+                }
+                if let Some(status) = &self.export_status {
+                    ui.label(status);
+                }
+            });
+
+            let editor_id = egui::Id::new("code_editor");
+            ui.collapsing("Insert", |ui| {
+                editor::palette_ui(ui, &mut self.code_text, editor_id);
+            });
+
+            if self.find.open {
+                self.find.ui(ui, &mut self.code_text);
             }
 
+            self.autocomplete.handle_keys(ui.ctx(), &mut self.code_text, editor_id);
+
             egui::ScrollArea::vertical().show(ui, |ui| {
-                ui.add(
+                let find = &self.find;
+                // The error stays marked at its line and column until the next compile, even if the code is edited
+                let error_pos = self.compiler_error.as_ref().and_then(|e| e.position);
+                let mut layouter = |ui: &egui::Ui, text: &str, wrap_width: f32| {
+                    let matches = if find.open { find.matches(text) } else { Vec::new() };
+                    let error = error_pos.and_then(|(line, column)| editor::error_range(text, line, column));
+                    let mut job = editor::layout_code(ui, text, &matches, Some(find.current), error);
+                    job.wrap.max_width = wrap_width;
+                    ui.fonts(|f| f.layout_job(job))
+                };
+                // Leave a gutter left of the text for the error marker
+                const GUTTER: f32 = 10.0;
+                let mut output = ui.horizontal_top(|ui| {
+                    ui.spacing_mut().item_spacing.x = 0.0;
+                    ui.add_space(GUTTER);
                     egui::TextEdit::multiline(&mut self.code_text)
+                        .id(editor_id)
                         .code_editor()
                         .desired_width(f32::INFINITY)
                         .desired_rows(30)
-                );
+                        .layouter(&mut layouter)
+                        .show(ui)
+                }).inner;
+
+                let error = self.compiler_error.as_ref().zip(error_pos)
+                    .and_then(|(e, (line, column))| Some((e, editor::error_range(&self.code_text, line, column)?)));
+                if let Some((err, range)) = error {
+                    let cursor = egui::text::CCursor::new(editor::char_index(&self.code_text, range.start));
+                    let row = output.galley.pos_from_ccursor(cursor).translate(output.galley_pos.to_vec2());
+                    let marker = egui::Rect::from_center_size(
+                        egui::pos2(output.response.rect.left() - GUTTER * 0.5, row.center().y),
+                        egui::vec2(GUTTER * 0.6, row.height()),
+                    );
+                    ui.painter().rect_filled(marker, 2.0, egui::Color32::RED);
+                    ui.interact(marker, editor_id.with("error_marker"), egui::Sense::hover()).on_hover_text(&err.message);
+                }
+
+                // Select the current match and bring it into view
+                if self.find.jump_pending {
+                    self.find.jump_pending = false;
+                    if let Some(range) = self.find.matches(&self.code_text).get(self.find.current) {
+                        let start = egui::text::CCursor::new(editor::char_index(&self.code_text, range.start));
+                        let end = egui::text::CCursor::new(editor::char_index(&self.code_text, range.end));
+                        output.state.cursor.set_char_range(Some(egui::text::CCursorRange::two(start, end)));
+                        output.state.clone().store(ui.ctx(), output.response.id);
+                        let rect = output.galley.pos_from_ccursor(start).translate(output.galley_pos.to_vec2());
+                        ui.scroll_to_rect(rect, Some(egui::Align::Center));
+                    }
+                }
+
+                self.autocomplete.update(&output, &self.code_text);
             });
+            self.autocomplete.show(ui.ctx(), &mut self.code_text, editor_id);
         });
 
         egui::TopBottomPanel::bottom("status_bar").show(ctx, |ui| {
@@ -178,44 +1406,315 @@ impl eframe::App for SdfApp {
                 ui.label(format!("Camera Pos: [{:.2}, {:.2}, {:.2}]", self.camera.pos.x, self.camera.pos.y, self.camera.pos.z));
                 ui.separator();
                 ui.label(format!("Yaw: {:.1}°, Pitch: {:.1}°", self.camera.yaw.to_degrees(), self.camera.pitch.to_degrees()));
+                ui.separator();
+                let avg = self.frame_stats.average();
+                let fps = if avg > 0.0 { 1.0 / avg } else { 0.0 };
+                ui.label(format!("Frame: {:.1} ms (avg {:.1} ms, {:.0} FPS)", self.frame_stats.last * 1000.0, avg * 1000.0, fps));
+                ui.separator();
+                ui.label(format!("SSAA: {}x{}", self.ssaa_samples(), if self.settings.auto_ssaa { " (auto)" } else { "" }));
+                let budget = self.ssaa_budget();
+                if self.ssaa_samples() > budget {
+                    ui.colored_label(egui::Color32::YELLOW, "⚠ heavy").on_hover_text(format!(
+                        "This scene at {}x SSAA may take long enough per frame for the GPU driver to reset{}. {}x is estimated to be safe.",
+                        self.ssaa_samples(),
+                        if self.low_power_gpu { " (integrated GPU)" } else { "" },
+                        budget,
+                    ));
+                    if ui.small_button(format!("Cap to {budget}x")).clicked() {
+                        self.settings.ssaa_samples = budget;
+                    }
+                }
+                ui.separator();
+                let mut frozen = self.frozen.is_some();
+                if ui.toggle_value(&mut frozen, "Freeze")
+                    .on_hover_text("Render one supersampled still and stop redrawing until the camera moves")
+                    .changed()
+                {
+                    if frozen { self.freeze_requested = true; } else { self.unfreeze(frame); }
+                }
+                if ui.button("Copy Image").on_hover_text("Render the view at its on-screen resolution and copy it to the clipboard").clicked() {
+                    self.copy_requested = true;
+                }
+                if let Some(status) = &self.copy_status {
+                    ui.label(status);
+                }
+                ui.separator();
+                if let (Some(offset), Some((object, _))) = (self.selected_offset(), &self.selection) {
+                    let name = &self.objects.objects[*object].name;
+                    ui.label(format!("Selected translate in {}: [{:.3}, {:.3}, {:.3}]", name, offset[0], offset[1], offset[2]));
+                    if ui.small_button("x").on_hover_text("Clear selection").clicked() {
+                        self.selection = None;
+                    }
+                    ui.separator();
+                }
+                if self.settings.snap {
+                    ui.label(format!("Snap: {}", self.settings.snap_increment));
+                } else {
+                    ui.label("Snap: off");
+                }
+                ui.separator();
+                if ui.toggle_value(&mut self.measurement.enabled, "Measure").changed() {
+                    self.measurement.points.clear();
+                    self.march_debug.enabled = false;
+                }
+                if ui.toggle_value(&mut self.march_debug.enabled, "Trace Ray")
+                    .on_hover_text("Click a pixel to list the ray march steps taken through it")
+                    .changed()
+                {
+                    self.measurement.enabled = false;
+                }
+                if self.measurement.enabled {
+                    match self.measurement.distance() {
+                        Some(dist) => ui.label(format!("Distance: {:.4}", dist)),
+                        None => ui.label(format!("Click surface point {} of 2", self.measurement.points.len() + 1)),
+                    };
+                }
+                if let Some(pivot) = self.camera.pivot {
+                    ui.separator();
+                    ui.label(format!("Orbit: [{:.3}, {:.3}, {:.3}]", pivot.x, pivot.y, pivot.z))
+                        .on_hover_text("Middle drag orbits around this point; double-click another surface to move it");
+                    if ui.small_button("Free look").clicked() {
+                        self.camera.pivot = None;
+                    }
+                }
+                if let Some((p, dist)) = self.cursor_probe {
+                    ui.separator();
+                    // The CPU evaluator holds animations at t = 0, so the point can be off the moving surface
+                    let animated = self.scene.as_ref().is_some_and(|s| s.is_animated());
+                    ui.label(format!("Cursor: [{:.3}, {:.3}, {:.3}] d = {:+.5}{}", p.x, p.y, p.z, dist, if animated { " (at t = 0)" } else { "" }))
+                        .on_hover_text(if animated {
+                            "Surface point under the mouse and the signed distance there, for the scene at t = 0; \
+                                animated parts may have moved since"
+                        } else {
+                            "Surface point under the mouse and the signed distance the CPU evaluator gives there"
+                        });
+                }
             });
         });
 
         egui::CentralPanel::default().show(ctx, |ui| {
             if let Some(resources) = &self.sdf_resources.clone() {
                 egui::Frame::canvas(ui.style()).show(ui, |ui| {
-                    
-                    let front = Vec3::new(
-                        self.camera.yaw.cos() * self.camera.pitch.cos(),
-                        self.camera.pitch.sin(),
-                        self.camera.yaw.sin() * self.camera.pitch.cos()
-                    ).normalize();
-
-                    let global_up = Vec3::new(0.0, 1.0, 0.0);
-                    let right = front.cross(global_up).normalize();
-                    let up = right.cross(front).normalize();
-
-                    let cam_data = CameraUniformData {
-                        pos: self.camera.pos.into(),
-                        front: front.into(),
-                        right: right.into(),
-                        up: up.into(),
+                    let cam_data = self.camera.uniform_data();
+                    let params = self.render_params();
+                    if std::mem::take(&mut self.copy_requested) {
+                        if let Some(rs) = frame.wgpu_render_state() {
+                            let px = ui.available_size() * ui.ctx().pixels_per_point();
+                            let size = [px.x.max(1.0) as u32, px.y.max(1.0) as u32];
+                            self.copy_status = Some(match resources.render_to_rgba(&rs.device, &rs.queue, size, &cam_data, &params) {
+                                Some(rgba) => copy_image(size, rgba),
+                                None => "Copy failed: could not read back the frame".to_string(),
+                            });
+                        }
+                    }
+                    if std::mem::take(&mut self.reference.compare_requested) {
+                        if let (Some(rs), Some(size)) = (frame.wgpu_render_state(), self.reference.size()) {
+                            let max = rs.device.limits().max_texture_dimension_2d;
+                            let rgba = if size[0].max(size[1]) > max {
+                                Err(format!("the image is larger than the GPU's {max} pixel limit"))
+                            } else {
+                                resources.render_to_rgba(&rs.device, &rs.queue, size, &cam_data, &params)
+                                    .ok_or_else(|| "could not read back the frame".to_string())
+                            };
+                            self.reference.compare(ui.ctx(), rgba);
+                        }
+                    }
+                    let frozen_params = RenderParams { ssaa_samples: self.ssaa_budget(), ..params };
+                    if self.frozen.as_ref().is_some_and(|f| f.view_size != ui.available_size() || f.params != frozen_params) {
+                        self.unfreeze(frame);
+                    }
+                    if std::mem::take(&mut self.freeze_requested) {
+                        if let Some(rs) = frame.wgpu_render_state() {
+                            // Scale both axes alike so a view too large for the GPU keeps its aspect
+                            let max = rs.device.limits().max_texture_dimension_2d as f32;
+                            let px = ui.available_size() * ui.ctx().pixels_per_point() * FREEZE_SCALE;
+                            let px = px * (max / px.max_elem()).min(1.0);
+                            let size = [px.x.max(1.0) as u32, px.y.max(1.0) as u32];
+                            let texture = resources.render_to_texture(&rs.device, &rs.queue, size, &cam_data, &frozen_params);
+                            let view = texture.create_view(&Default::default());
+                            let texture_id = rs.renderer.write().register_native_texture(&rs.device, &view, eframe::wgpu::FilterMode::Linear);
+                            self.frozen = Some(FrozenFrame { texture_id, _texture: texture, view_size: ui.available_size(), params: frozen_params });
+                        }
+                    }
+
+                    let response = match &self.frozen {
+                        Some(frozen) => {
+                            let image = egui::Image::new((frozen.texture_id, ui.available_size())).sense(egui::Sense::click_and_drag());
+                            ui.add(image)
+                        }
+                        None => sdf_view(ui, resources, cam_data, params),
                     };
-                    
-                    let response = sdf_view(ui, resources, cam_data);
-                    self.camera.update(ui, &response);
+                    self.view_size = response.rect.size();
+                    self.camera_moving = self.camera.update(ui, &response, self.settings.pan_speed);
+                    if self.camera_moving {
+                        self.unfreeze(frame);
+                    }
+
+                    // The status bar is already drawn, so a changed reading shows on the next frame
+                    let probe_key = response.hover_pos().map(|pos| (pos, response.rect, self.camera.pos, self.camera.basis().0));
+                    if probe_key != self.cursor_probe_key {
+                        self.cursor_probe_key = probe_key;
+                        let probe = probe_key.zip(self.scene.as_ref()).and_then(|((pos, ..), scene)| {
+                            let rd = self.camera.ray_dir(response.rect, pos);
+                            if scene.distance(self.camera.pos + rd * self.settings.march_near) < 0.0 {
+                                return None;
+                            }
+                            let t = scene.raycast(self.camera.pos, rd, self.settings.march_near, self.settings.march_far)?;
+                            let hit = self.camera.pos + rd * t;
+                            Some((hit, scene.distance(hit)))
+                        });
+                        if probe != self.cursor_probe {
+                            self.cursor_probe = probe;
+                            ui.ctx().request_repaint();
+                        }
+                    }
+
+                    self.reference.paint(&ui.painter_at(response.rect), response.rect);
+                    if let (true, Some(scene)) = (self.settings.show_bounds, &self.scene) {
+                        paint_primitive_bounds(&ui.painter_at(response.rect), &self.camera, response.rect, scene);
+                    }
+                    if self.settings.slice_view && self.settings.slice_legend {
+                        paint_slice_legend(&ui.painter_at(response.rect), response.rect);
+                    }
+
+                    let orbit_click = !self.measurement.enabled && !self.march_debug.enabled && response.double_clicked();
+                    if let (true, Some(pos), Some(scene)) = (orbit_click, response.interact_pointer_pos(), &self.scene) {
+                        let rd = self.camera.ray_dir(response.rect, pos);
+                        match scene.raycast(self.camera.pos, rd, self.settings.march_near, self.settings.march_far) {
+                            Some(t) => self.camera.orbit_around(self.camera.pos + rd * t),
+                            None => self.camera.pivot = None,
+                        }
+                        self.unfreeze(frame);
+                    }
+                    if let Some(pivot) = self.camera.pivot.and_then(|p| self.camera.project(response.rect, p)) {
+                        ui.painter_at(response.rect).circle_stroke(pivot, 4.0, egui::Stroke::new(1.5, egui::Color32::WHITE));
+                    }
+
+                    if let (true, Some(pos)) = (response.clicked_by(egui::PointerButton::Primary), response.interact_pointer_pos()) {
+                        if self.measurement.enabled {
+                            if let Some(scene) = &self.scene {
+                                self.measurement.click(scene, &self.camera, &self.settings, response.rect, pos);
+                            }
+                        } else if self.march_debug.enabled {
+                            if let Some(scene) = &self.scene {
+                                self.march_debug.click(scene, &self.camera, &self.settings, response.rect, pos);
+                            }
+                        } else {
+                            self.pick(response.rect, pos);
+                        }
+                    }
+                    if self.measurement.enabled {
+                        self.measurement.paint(ui.painter(), &self.camera, response.rect);
+                    } else if self.march_debug.enabled {
+                        self.march_debug.paint(ui.painter(), &self.camera, response.rect);
+                    } else {
+                        self.selection_gizmo(ui, &response, frame);
+                    }
                 });
             } else {
                 ui.centered_and_justified(|ui| {
-                    ui.label("No Shader Compiled.");
+                    if self.objects.objects.is_empty() {
+                        ui.label("No Shader Compiled.");
+                    } else {
+                        ui.label("All objects are hidden.");
+                    }
                 });
             }
         });
+
+        if self.march_debug.enabled {
+            self.march_debug.window(ctx);
+        }
+
+        egui::Window::new("Shortcuts")
+            .open(&mut self.show_shortcuts)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                egui::Grid::new("shortcuts").num_columns(2).striped(true).spacing([16.0, 4.0]).show(ui, |ui| {
+                    for (keys, action) in SHORTCUTS {
+                        ui.strong(*keys);
+                        ui.label(*action);
+                        ui.end_row();
+                    }
+                });
+            });
+
+        if let Some(look) = &self.pending_look {
+            let mut choice = None;
+            egui::Window::new("Scene Render Settings")
+                .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+                .collapsible(false)
+                .resizable(false)
+                .show(ctx, |ui| {
+                    ui.label("This scene was saved with its own sampling, lighting and output settings.");
+                    ui.label("Apply them to render it as it was shared, or keep your current settings?");
+                    ui.horizontal(|ui| {
+                        if ui.button("Apply").clicked() {
+                            choice = Some(true);
+                        }
+                        if ui.button("Keep Mine").clicked() {
+                            choice = Some(false);
+                        }
+                    });
+                });
+            if let Some(apply) = choice {
+                let look = look.clone();
+                self.pending_look = None;
+                if apply {
+                    self.apply_scene_look(&look, frame);
+                }
+            }
+        }
+
+        if self.confirm_reset {
+            egui::Window::new("Reset to Default Scene")
+                .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+                .collapsible(false)
+                .resizable(false)
+                .show(ctx, |ui| {
+                    ui.label("Replace the code in the editor with the built-in demo scene and run it?");
+                    ui.label("Nothing is written to disk, and Ctrl+Z brings the current code back.");
+                    ui.horizontal(|ui| {
+                        if ui.button("Reset").clicked() {
+                            self.history.push(&self.code_text);
+                            self.code_text = DEFAULT_SCENE.to_string();
+                            self.run_script(frame);
+                            self.confirm_reset = false;
+                        }
+                        if ui.button("Cancel").clicked() {
+                            self.confirm_reset = false;
+                        }
+                    });
+                });
+        }
+
+        // egui repaints on input events by itself; continuous repaint is only needed for
+        // held-key movement and time-animated shaders, and never while a frame is frozen. A
+        // static scene with pausing off is redrawn at `idle_fps` rather than the display rate.
+        let animated = self.scene.as_ref().is_some_and(|s| s.is_animated());
+        if self.camera_moving || (self.frozen.is_none() && animated) {
+            ctx.request_repaint();
+        } else if self.frozen.is_none() && !self.settings.pause_when_idle {
+            ctx.request_repaint_after(std::time::Duration::from_secs_f32(1.0 / self.settings.idle_fps.max(1.0)));
+        }
     }
 }
 
 fn main() -> eframe::Result<()> {
     env_logger::init();
+    // Usage: sdf_poc [scene.rhai | project folder]
+    //        sdf_poc --check <folder>    (check every script in the folder and exit)
+    let mut args = std::env::args_os().skip(1);
+    let script = args.next().map(PathBuf::from);
+    if script.as_deref() == Some(Path::new("--check")) {
+        let Some(folder) = args.next() else {
+            eprintln!("Usage: sdf_poc --check <folder>");
+            std::process::exit(2);
+        };
+        std::process::exit(if batch_check::check_scenes(Path::new(&folder)) { 0 } else { 1 });
+    }
     let options = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default().with_inner_size([1280.0, 720.0]),
         ..Default::default()
@@ -223,6 +1722,6 @@ fn main() -> eframe::Result<()> {
     eframe::run_native(
         "SDF Rhai Modeler",
         options,
-        Box::new(|cc| Ok(Box::new(SdfApp::new(cc)))),
+        Box::new(|cc| Ok(Box::new(SdfApp::new(cc, script)))),
     )
 }
\ No newline at end of file
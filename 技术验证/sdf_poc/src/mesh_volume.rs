@@ -0,0 +1,360 @@
+use glam::{IVec3, Vec3};
+use std::fmt;
+use std::sync::{Arc, Mutex, Weak};
+use std::time::SystemTime;
+
+/// Default grid resolution of `mesh(path)`, in cells along the mesh's longest side.
+pub const DEFAULT_MESH_RESOLUTION: i64 = 64;
+/// Upper bound on `mesh(path, resolution)`. Baking time and memory grow with the cube of the
+/// resolution: 256 is 67 MB of distances per mesh and several seconds of baking.
+pub const MAX_MESH_RESOLUTION: i64 = 256;
+/// Empty cells around the mesh on every side, so the grid box encloses the surface with room
+/// for the trilinear filter and the outside estimate (see `MeshVolume::sample`).
+const PADDING: i32 = 2;
+
+/// Bakes still in use, keyed by path, resolution and the file's modification time, so running
+/// a script again reuses its meshes instead of baking them on the UI thread each time. Weak, so
+/// a bake is freed with the last scene using it; an edited file has a new time and is rebaked.
+static BAKES: Mutex<Vec<(BakeKey, Weak<MeshVolume>)>> = Mutex::new(Vec::new());
+
+type BakeKey = (String, u32, Option<SystemTime>);
+
+/// A triangle mesh baked into a grid of signed distances for `SdfOp::MeshVolume`, done once when
+/// the script runs. The shader reads the same values from a 3D texture and filters them the
+/// same way (trilinear, by hand), so the CPU evaluator and the GPU agree.
+///
+/// The grid resolution trades detail for memory and load time: features thinner than about two
+/// cells (`cell`) are lost or rounded, and sharp edges are bevelled by about one cell. The sign
+/// comes from counting surface crossings along grid rows, so the mesh must be closed
+/// (watertight); holes show up as streaks of wrong sign.
+pub struct MeshVolume {
+    /// Path as written in the script.
+    pub path: String,
+    /// Grid points per axis.
+    pub size: [u32; 3],
+    /// Position of grid point (0, 0, 0).
+    pub origin: Vec3,
+    /// Spacing between grid points.
+    pub cell: f32,
+    /// Signed distances, x fastest, then y, then z.
+    pub values: Vec<f32>,
+}
+
+impl fmt::Debug for MeshVolume {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "MeshVolume({}, {}x{}x{})", self.path, self.size[0], self.size[1], self.size[2])
+    }
+}
+
+impl MeshVolume {
+    /// Loads an OBJ or STL (binary or ASCII, chosen by extension) file and bakes it with
+    /// `resolution` cells along its longest side.
+    fn load(path: &str, resolution: u32) -> Result<Self, String> {
+        let bytes = std::fs::read(path).map_err(|e| format!("Failed to load {}: {}", path, e))?;
+        let triangles = if path.to_ascii_lowercase().ends_with(".stl") { parse_stl(&bytes) } else { parse_obj(&bytes) }
+            .map_err(|e| format!("{}: {}", path, e))?;
+        if triangles.is_empty() {
+            return Err(format!("{}: no triangles", path));
+        }
+        Ok(Self::bake(path, &triangles, resolution.max(2)))
+    }
+
+    /// `load`, or the bake of an earlier call with the same file and resolution if a scene still
+    /// holds it. Shared bakes share one slab of the texture (see `SdfNode::mesh_volumes`).
+    pub fn load_shared(path: &str, resolution: u32) -> Result<Arc<Self>, String> {
+        let modified = std::fs::metadata(path).and_then(|m| m.modified()).ok();
+        let mut bakes = BAKES.lock().unwrap_or_else(|e| e.into_inner());
+        bakes.retain(|(_, bake)| bake.strong_count() > 0);
+        let key = (path.to_string(), resolution, modified);
+        let cached = bakes.iter().find(|(k, _)| *k == key).and_then(|(_, bake)| bake.upgrade());
+        if let Some(volume) = cached {
+            return Ok(volume);
+        }
+        let volume = Arc::new(Self::load(path, resolution)?);
+        bakes.push((key, Arc::downgrade(&volume)));
+        Ok(volume)
+    }
+
+    /// Upper corner of the grid.
+    pub fn max(&self) -> Vec3 {
+        self.origin + (Vec3::new(self.size[0] as f32, self.size[1] as f32, self.size[2] as f32) - 1.0) * self.cell
+    }
+
+    fn value(&self, i: IVec3) -> f32 {
+        let [nx, ny, _] = self.size.map(|n| n as usize);
+        self.values[i.x as usize + nx * (i.y as usize + ny * i.z as usize)]
+    }
+
+    /// Trilinear sample at `p`, matching `sd_mesh_volume` in the shader. Outside the grid the
+    /// distance is to the grid box shrunk by the padding, which the surface lies within: a
+    /// lower bound, so marching towards the mesh never overshoots.
+    pub fn sample(&self, p: Vec3) -> f32 {
+        let g = (p - self.origin) / self.cell;
+        let top = Vec3::new(self.size[0] as f32, self.size[1] as f32, self.size[2] as f32) - 1.0;
+        if g.cmplt(Vec3::ZERO).any() || g.cmpgt(top).any() {
+            let half = top * self.cell * 0.5;
+            let q = (p - self.origin - half).abs() - (half - PADDING as f32 * self.cell);
+            return q.max(Vec3::ZERO).length() + q.max_element().min(0.0);
+        }
+        let i = g.floor().as_ivec3().min(IVec3::from(self.size.map(|n| n as i32)) - 2);
+        let f = g - i.as_vec3();
+        let lerp = |a: f32, b: f32, t: f32| a + (b - a) * t;
+        let v = |dx, dy, dz| self.value(i + IVec3::new(dx, dy, dz));
+        let x00 = lerp(v(0, 0, 0), v(1, 0, 0), f.x);
+        let x10 = lerp(v(0, 1, 0), v(1, 1, 0), f.x);
+        let x01 = lerp(v(0, 0, 1), v(1, 0, 1), f.x);
+        let x11 = lerp(v(0, 1, 1), v(1, 1, 1), f.x);
+        lerp(lerp(x00, x10, f.y), lerp(x01, x11, f.y), f.z)
+    }
+
+    /// Signed distance grid over `triangles` (after Bridson's `makelevelset3`): exact distances
+    /// to the nearby triangles around the surface, spread outwards by sweeping each point's
+    /// closest triangle to its neighbors, then signed by the parity of surface crossings along
+    /// each X row.
+    fn bake(path: &str, triangles: &[[Vec3; 3]], resolution: u32) -> Self {
+        let (lo, hi) = triangles.iter().flatten().fold((Vec3::INFINITY, Vec3::NEG_INFINITY), |(lo, hi), v| (lo.min(*v), hi.max(*v)));
+        let cell = (hi - lo).max_element().max(1e-6) / resolution as f32;
+        let origin = lo - cell * PADDING as f32;
+        let n = (((hi - lo) / cell).ceil().as_ivec3() + 1 + 2 * PADDING).max(IVec3::splat(2));
+        let index = |i: IVec3| (i.x + n.x * (i.y + n.y * i.z)) as usize;
+        let count = (n.x * n.y * n.z) as usize;
+        let to_grid = |v: Vec3| (v - origin) / cell;
+
+        let mut dist = vec![f32::INFINITY; count];
+        let mut closest = vec![usize::MAX; count];
+        let mut crossings = vec![0u32; count];
+        for (t, tri) in triangles.iter().enumerate() {
+            let g = tri.map(to_grid);
+            let (g_lo, g_hi) = (g[0].min(g[1]).min(g[2]), g[0].max(g[1]).max(g[2]));
+
+            // Exact distances within a cell of the triangle
+            let i0 = (g_lo.floor().as_ivec3() - 1).max(IVec3::ZERO);
+            let i1 = (g_hi.ceil().as_ivec3() + 1).min(n - 1);
+            for z in i0.z..=i1.z {
+                for y in i0.y..=i1.y {
+                    for x in i0.x..=i1.x {
+                        let i = IVec3::new(x, y, z);
+                        let d = point_triangle_distance(origin + i.as_vec3() * cell, tri);
+                        if d < dist[index(i)] {
+                            dist[index(i)] = d;
+                            closest[index(i)] = t;
+                        }
+                    }
+                }
+            }
+
+            // Rows along X (through grid points (y, z)) that the triangle crosses, counted at
+            // the first grid point past the crossing
+            let (y0, y1) = (g_lo.y.ceil().max(0.0) as i32, (g_hi.y.floor() as i32).min(n.y - 1));
+            let (z0, z1) = (g_lo.z.ceil().max(0.0) as i32, (g_hi.z.floor() as i32).min(n.z - 1));
+            for z in z0..=z1 {
+                for y in y0..=y1 {
+                    let Some(x) = row_crossing(y as f32, z as f32, &g) else { continue };
+                    let x = x.ceil().max(0.0) as i32;
+                    if x < n.x {
+                        crossings[index(IVec3::new(x, y, z))] += 1;
+                    }
+                }
+            }
+        }
+
+        // Two rounds of sweeps in all eight diagonal directions
+        for _ in 0..2 {
+            for dir in 0..8 {
+                let d = IVec3::new(if dir & 1 == 0 { 1 } else { -1 }, if dir & 2 == 0 { 1 } else { -1 }, if dir & 4 == 0 { 1 } else { -1 });
+                let range = |step: i32, len: i32| -> Vec<i32> { if step > 0 { (1..len).collect() } else { (0..len - 1).rev().collect() } };
+                for z in range(d.z, n.z) {
+                    for y in range(d.y, n.y) {
+                        for x in range(d.x, n.x) {
+                            let i = IVec3::new(x, y, z);
+                            let p = origin + i.as_vec3() * cell;
+                            for offset in [IVec3::new(d.x, 0, 0), IVec3::new(0, d.y, 0), IVec3::new(0, 0, d.z),
+                                           IVec3::new(d.x, d.y, 0), IVec3::new(d.x, 0, d.z), IVec3::new(0, d.y, d.z), d] {
+                                let t = closest[index(i - offset)];
+                                if t != usize::MAX && t != closest[index(i)] {
+                                    let dt = point_triangle_distance(p, &triangles[t]);
+                                    if dt < dist[index(i)] {
+                                        dist[index(i)] = dt;
+                                        closest[index(i)] = t;
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        // Odd crossings so far along the row: inside
+        for z in 0..n.z {
+            for y in 0..n.y {
+                let mut total = 0;
+                for x in 0..n.x {
+                    let i = index(IVec3::new(x, y, z));
+                    total += crossings[i];
+                    if total % 2 == 1 {
+                        dist[i] = -dist[i];
+                    }
+                }
+            }
+        }
+
+        Self { path: path.to_string(), size: n.to_array().map(|v| v as u32), origin, cell, values: dist }
+    }
+}
+
+/// Stacking of volumes along Z in one 3D texture: each volume's first slice, and the texture
+/// size. Used both to upload the texture and to address it from the shader.
+pub fn atlas_layout(volumes: &[Arc<MeshVolume>]) -> (Vec<u32>, [u32; 3]) {
+    let mut offsets = Vec::with_capacity(volumes.len());
+    let mut size = [1, 1, 0];
+    for v in volumes {
+        offsets.push(size[2]);
+        size = [size[0].max(v.size[0]), size[1].max(v.size[1]), size[2] + v.size[2]];
+    }
+    (offsets, [size[0], size[1], size[2].max(1)])
+}
+
+/// Texel data for `atlas_layout`'s texture, x fastest; texels no volume covers are left at 0.
+pub fn atlas_data(volumes: &[Arc<MeshVolume>]) -> Vec<f32> {
+    let (offsets, [w, h, d]) = atlas_layout(volumes);
+    let mut data = vec![0.0; (w * h * d) as usize];
+    for (v, z0) in volumes.iter().zip(offsets) {
+        let [nx, ny, nz] = v.size;
+        for z in 0..nz {
+            for y in 0..ny {
+                let src = ((z * ny + y) * nx) as usize;
+                let dst = (((z0 + z) * h + y) * w) as usize;
+                data[dst..dst + nx as usize].copy_from_slice(&v.values[src..src + nx as usize]);
+            }
+        }
+    }
+    data
+}
+
+/// X coordinate where the line parallel to X through `(y, z)` meets the triangle, if it does.
+/// Points on a shared edge go to exactly one of the two triangles, using Bridson's consistent
+/// tie-breaking on the 2D orientation signs.
+fn row_crossing(y: f32, z: f32, g: &[Vec3; 3]) -> Option<f32> {
+    // Sign and twice the signed area of the triangle (point, a, b) in the YZ plane
+    let orient = |a: Vec3, b: Vec3| -> (i32, f64) {
+        let (ay, az, by, bz) = ((a.y - y) as f64, (a.z - z) as f64, (b.y - y) as f64, (b.z - z) as f64);
+        let area = ay * bz - az * by;
+        let sign = if area != 0.0 {
+            area.signum() as i32
+        } else if bz != az {
+            if bz > az { 1 } else { -1 }
+        } else if ay != by {
+            if ay > by { 1 } else { -1 }
+        } else {
+            0
+        };
+        (sign, area)
+    };
+    let ((sa, a), (sb, b), (sc, c)) = (orient(g[1], g[2]), orient(g[2], g[0]), orient(g[0], g[1]));
+    if sa == 0 || sb != sa || sc != sa {
+        return None;
+    }
+    let sum = a + b + c;
+    if sum == 0.0 {
+        return None;
+    }
+    Some(((a * g[0].x as f64 + b * g[1].x as f64 + c * g[2].x as f64) / sum) as f32)
+}
+
+/// Distance from `p` to the closest point of the triangle (Ericson, "Real-Time Collision
+/// Detection", 5.1.5).
+fn point_triangle_distance(p: Vec3, [a, b, c]: &[Vec3; 3]) -> f32 {
+    let (ab, ac, ap) = (*b - *a, *c - *a, p - *a);
+    let (d1, d2) = (ab.dot(ap), ac.dot(ap));
+    if d1 <= 0.0 && d2 <= 0.0 {
+        return p.distance(*a);
+    }
+    let bp = p - *b;
+    let (d3, d4) = (ab.dot(bp), ac.dot(bp));
+    if d3 >= 0.0 && d4 <= d3 {
+        return p.distance(*b);
+    }
+    let vc = d1 * d4 - d3 * d2;
+    if vc <= 0.0 && d1 >= 0.0 && d3 <= 0.0 {
+        return p.distance(*a + ab * (d1 / (d1 - d3)));
+    }
+    let cp = p - *c;
+    let (d5, d6) = (ab.dot(cp), ac.dot(cp));
+    if d6 >= 0.0 && d5 <= d6 {
+        return p.distance(*c);
+    }
+    let vb = d5 * d2 - d1 * d6;
+    if vb <= 0.0 && d2 >= 0.0 && d6 <= 0.0 {
+        return p.distance(*a + ac * (d2 / (d2 - d6)));
+    }
+    let va = d3 * d6 - d5 * d4;
+    if va <= 0.0 && d4 - d3 >= 0.0 && d5 - d6 >= 0.0 {
+        return p.distance(*b + (*c - *b) * ((d4 - d3) / ((d4 - d3) + (d5 - d6))));
+    }
+    let denom = 1.0 / (va + vb + vc);
+    p.distance(*a + ab * (vb * denom) + ac * (vc * denom))
+}
+
+/// Triangles of an OBJ file: `v` positions and `f` faces (fan-triangulated), ignoring texture
+/// coordinates, normals, groups and materials.
+fn parse_obj(bytes: &[u8]) -> Result<Vec<[Vec3; 3]>, String> {
+    let text = String::from_utf8_lossy(bytes);
+    let mut vertices = Vec::new();
+    let mut triangles = Vec::new();
+    for (line_no, line) in text.lines().enumerate() {
+        let mut parts = line.split_whitespace();
+        let err = |what: &str| format!("line {}: {}", line_no + 1, what);
+        match parts.next() {
+            Some("v") => {
+                let coords: Vec<f32> = parts.take(3).map(|s| s.parse().map_err(|_| err("bad vertex"))).collect::<Result<_, _>>()?;
+                let [x, y, z] = coords[..] else { return Err(err("vertex needs three coordinates")) };
+                vertices.push(Vec3::new(x, y, z));
+            }
+            Some("f") => {
+                // `v`, `v/vt`, `v//vn` or `v/vt/vn`; negative indices count back from the last vertex
+                let face: Vec<Vec3> = parts.map(|s| {
+                    let index: i64 = s.split('/').next().unwrap_or("").parse().map_err(|_| err("bad face index"))?;
+                    let i = if index < 0 { vertices.len() as i64 + index } else { index - 1 };
+                    vertices.get(i as usize).copied().ok_or_else(|| err("face index out of range"))
+                }).collect::<Result<_, _>>()?;
+                for k in 2..face.len() {
+                    triangles.push([face[0], face[k - 1], face[k]]);
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(triangles)
+}
+
+/// Triangles of a binary or ASCII STL file. A binary file may also start with "solid", so the
+/// size implied by its triangle count decides.
+fn parse_stl(bytes: &[u8]) -> Result<Vec<[Vec3; 3]>, String> {
+    if bytes.len() >= 84 {
+        let count = u32::from_le_bytes([bytes[80], bytes[81], bytes[82], bytes[83]]) as usize;
+        if bytes.len() == 84 + count * 50 {
+            let float = |at: usize| f32::from_le_bytes([bytes[at], bytes[at + 1], bytes[at + 2], bytes[at + 3]]);
+            let vertex = |at: usize| Vec3::new(float(at), float(at + 4), float(at + 8));
+            // Each record: normal, three vertices, attribute byte count
+            return Ok((0..count).map(|t| {
+                let at = 84 + t * 50 + 12;
+                [vertex(at), vertex(at + 12), vertex(at + 24)]
+            }).collect());
+        }
+    }
+    let text = String::from_utf8_lossy(bytes);
+    let mut corners = Vec::new();
+    for line in text.lines() {
+        let mut parts = line.split_whitespace();
+        if parts.next() == Some("vertex") {
+            let coords: Vec<f32> = parts.take(3).map(|s| s.parse().map_err(|_| "bad vertex".to_string())).collect::<Result<_, _>>()?;
+            let [x, y, z] = coords[..] else { return Err("vertex needs three coordinates".to_string()) };
+            corners.push(Vec3::new(x, y, z));
+        }
+    }
+    if corners.len() % 3 != 0 {
+        return Err("vertex count is not a multiple of three".to_string());
+    }
+    Ok(corners.chunks(3).map(|c| [c[0], c[1], c[2]]).collect())
+}
@@ -0,0 +1,213 @@
+use eframe::egui;
+use std::path::{Path, PathBuf};
+
+/// A 2D image drawn over the viewport to line a model up with concept art, or compared with a
+/// render of the current view to check that a change left the output alone.
+/// Purely a UI overlay: it never reaches the shader.
+pub struct ReferenceImage {
+    pub path: String,
+    pub opacity: f32,
+    pub visible: bool,
+    /// "Compare" was clicked; the view is rendered at the image's size during the next viewport
+    /// pass and handed to `compare`.
+    pub compare_requested: bool,
+    /// Mean error, in 8-bit levels, up to which a comparison passes.
+    pub tolerance: f32,
+    texture: Option<egui::TextureHandle>,
+    pixels: Option<image::RgbaImage>,
+    diff: Option<ImageDiff>,
+    status: Option<String>,
+}
+
+/// Result of the last `compare`, shown instead of the image while it is kept.
+struct ImageDiff {
+    /// Per-pixel error as a heat map: black where the render matches, through red and yellow to
+    /// white at `HEAT_MAP_RANGE` levels or more.
+    texture: egui::TextureHandle,
+    /// Absolute difference averaged over the RGB channels of all pixels, in 8-bit levels.
+    mean_error: f32,
+    max_error: u8,
+    /// Fraction of pixels with any channel off by more than one level (rounding noise).
+    changed: f32,
+}
+
+/// Error, in 8-bit levels, shown as white in the diff heat map.
+const HEAT_MAP_RANGE: f32 = 64.0;
+
+impl Default for ReferenceImage {
+    fn default() -> Self {
+        Self {
+            path: String::new(),
+            opacity: 0.5,
+            visible: true,
+            compare_requested: false,
+            tolerance: 1.0,
+            texture: None,
+            pixels: None,
+            diff: None,
+            status: None,
+        }
+    }
+}
+
+impl ReferenceImage {
+    pub fn load(&mut self, ctx: &egui::Context, path: impl AsRef<Path>) {
+        let path = path.as_ref();
+        self.path = path.display().to_string();
+        match image::open(path) {
+            Ok(image) => {
+                let image = image.into_rgba8();
+                self.status = Some(format!("Loaded {} ({}x{})", self.path, image.width(), image.height()));
+                self.set_image(ctx, image);
+            }
+            Err(e) => self.status = Some(format!("Failed to load {}: {}", self.path, e)),
+        }
+    }
+
+    /// Takes the image on the clipboard, e.g. a screenshot or one copied from a browser.
+    pub fn paste(&mut self, ctx: &egui::Context) {
+        let pasted = arboard::Clipboard::new().and_then(|mut clipboard| clipboard.get_image()).map_err(|e| e.to_string())
+            .and_then(|data| {
+                image::RgbaImage::from_raw(data.width as u32, data.height as u32, data.bytes.into_owned())
+                    .ok_or_else(|| "the clipboard image has the wrong size".to_string())
+            });
+        match pasted {
+            Ok(image) => {
+                self.path.clear();
+                self.status = Some(format!("Pasted a {}x{} image", image.width(), image.height()));
+                self.set_image(ctx, image);
+            }
+            Err(e) => self.status = Some(format!("Nothing pasted: {}", e)),
+        }
+    }
+
+    fn set_image(&mut self, ctx: &egui::Context, image: image::RgbaImage) {
+        let size = [image.width() as usize, image.height() as usize];
+        let color = egui::ColorImage::from_rgba_unmultiplied(size, image.as_raw());
+        self.texture = Some(ctx.load_texture("reference_image", color, egui::TextureOptions::LINEAR));
+        self.pixels = Some(image);
+        self.diff = None;
+        self.visible = true;
+    }
+
+    /// Loads the first image file dropped onto the window, if any.
+    pub fn handle_dropped_files(&mut self, ctx: &egui::Context) {
+        let dropped = ctx.input(|i| i.raw.dropped_files.iter().find_map(|f| f.path.clone()));
+        if let Some(path) = dropped {
+            self.load(ctx, path);
+        }
+    }
+
+    pub fn ui(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label("Image:");
+            ui.add(egui::TextEdit::singleline(&mut self.path).hint_text("path/to/concept.png").desired_width(180.0));
+            if ui.button("Load").on_hover_text("You can also drop an image file onto the window").clicked() {
+                if self.path.trim().is_empty() {
+                    self.texture = None;
+                    self.pixels = None;
+                    self.diff = None;
+                    self.status = None;
+                } else {
+                    let path = PathBuf::from(self.path.trim());
+                    self.load(ui.ctx(), path);
+                }
+            }
+            if ui.button("Paste").on_hover_text("Use the image on the clipboard").clicked() {
+                self.paste(ui.ctx());
+            }
+        });
+        ui.add_enabled_ui(self.texture.is_some(), |ui| {
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut self.visible, "Show");
+                ui.add(egui::Slider::new(&mut self.opacity, 0.0..=1.0).text("Opacity"));
+            });
+        });
+        if let Some(status) = &self.status {
+            ui.label(status);
+        }
+
+        ui.add_enabled_ui(self.pixels.is_some(), |ui| {
+            ui.horizontal(|ui| {
+                if ui.button("Compare").on_hover_text("Render the current view at the image's size and show where they differ").clicked() {
+                    self.compare_requested = true;
+                }
+                ui.add(egui::DragValue::new(&mut self.tolerance).range(0.0..=255.0).speed(0.1).prefix("Tolerance: "))
+                    .on_hover_text("Largest mean difference, in 8-bit levels, that still passes");
+                if self.diff.is_some() && ui.button("Clear").clicked() {
+                    self.diff = None;
+                }
+            });
+        });
+        if let Some(diff) = &self.diff {
+            let (verdict, color) = if diff.mean_error <= self.tolerance {
+                ("PASS", egui::Color32::GREEN)
+            } else {
+                ("FAIL", egui::Color32::RED)
+            };
+            ui.horizontal(|ui| {
+                ui.colored_label(color, verdict);
+                ui.label(format!(
+                    "mean error {:.3}, max {}, {:.2}% of pixels changed",
+                    diff.mean_error, diff.max_error, diff.changed * 100.0,
+                ));
+            });
+            ui.label("The heat map shows the view when Compare was clicked; compare again after moving the camera.");
+        }
+    }
+
+    /// Size in pixels of the loaded image, which `compare` expects the render to have.
+    pub fn size(&self) -> Option<[u32; 2]> {
+        self.pixels.as_ref().map(|p| [p.width(), p.height()])
+    }
+
+    /// Diffs `rendered` (tightly packed RGBA8 at `size()`, as `render_to_rgba` returns it) against
+    /// the image, or reports why the render failed. Alpha is ignored.
+    pub fn compare(&mut self, ctx: &egui::Context, rendered: Result<Vec<u8>, String>) {
+        let Some(reference) = &self.pixels else { return };
+        let expected = reference.as_raw().len();
+        let rendered = rendered.and_then(|rgba| if rgba.len() == expected { Ok(rgba) } else { Err("the render has the wrong size".to_string()) });
+        let rendered = match rendered {
+            Ok(rgba) => rgba,
+            Err(e) => {
+                self.status = Some(format!("Compare failed: {}", e));
+                return;
+            }
+        };
+        let (mut total, mut max_error, mut changed) = (0u64, 0u8, 0usize);
+        let mut heat = Vec::with_capacity(rendered.len());
+        for (a, b) in reference.as_raw().chunks(4).zip(rendered.chunks(4)) {
+            let errors = [0, 1, 2].map(|i| a[i].abs_diff(b[i]));
+            let sum: u32 = errors.iter().map(|e| *e as u32).sum();
+            total += sum as u64;
+            let max = errors.into_iter().max().unwrap_or(0);
+            max_error = max_error.max(max);
+            changed += (max > 1) as usize;
+            let t = (sum as f32 / 3.0 / HEAT_MAP_RANGE).min(1.0) * 3.0;
+            heat.extend([t, t - 1.0, t - 2.0].map(|c| (c.clamp(0.0, 1.0) * 255.0) as u8));
+            heat.push(255);
+        }
+        let pixels = (rendered.len() / 4).max(1);
+        let size = [reference.width() as usize, reference.height() as usize];
+        let image = egui::ColorImage::from_rgba_unmultiplied(size, &heat);
+        self.diff = Some(ImageDiff {
+            texture: ctx.load_texture("reference_diff", image, egui::TextureOptions::NEAREST),
+            mean_error: total as f32 / (pixels * 3) as f32,
+            max_error,
+            changed: changed as f32 / pixels as f32,
+        });
+        self.visible = true;
+    }
+
+    /// Draws the image, or the heat map of the last comparison, centered in `rect` and scaled to
+    /// fit while keeping its aspect ratio.
+    pub fn paint(&self, painter: &egui::Painter, rect: egui::Rect) {
+        let texture = self.diff.as_ref().map(|d| &d.texture).or(self.texture.as_ref());
+        let Some(texture) = texture.filter(|_| self.visible) else { return };
+        let size = texture.size_vec2();
+        let scale = (rect.width() / size.x).min(rect.height() / size.y);
+        let image_rect = egui::Rect::from_center_size(rect.center(), size * scale);
+        let uv = egui::Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(1.0, 1.0));
+        painter.image(texture.id(), image_rect, uv, egui::Color32::WHITE.gamma_multiply(self.opacity));
+    }
+}
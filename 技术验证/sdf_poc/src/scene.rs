@@ -0,0 +1,77 @@
+use glam::Vec3;
+use rhai::{CustomType, TypeBuilder};
+use crate::sdf_ast::SdfNode;
+
+/// A named part of the scene, registered from a script with `scene.add(name, shape)`.
+#[derive(Clone, Debug)]
+pub struct SceneObject {
+    pub name: String,
+    pub node: SdfNode,
+    /// Hidden objects are left out of the generated shader entirely.
+    pub visible: bool,
+}
+
+/// The objects making up a project, rendered as a top-level union of the visible ones.
+/// Scripts see it as the `scene` variable.
+#[derive(Clone, Debug, Default)]
+pub struct Scene {
+    pub objects: Vec<SceneObject>,
+}
+
+impl Scene {
+    pub fn add(&mut self, name: &str, node: SdfNode) {
+        self.objects.push(SceneObject { name: name.to_string(), node, visible: true });
+    }
+
+    /// Carries hidden flags over from `previous` by name, so recompiling keeps parts hidden.
+    pub fn keep_visibility(&mut self, previous: &Scene) {
+        for object in &mut self.objects {
+            if let Some(old) = previous.objects.iter().find(|o| o.name == object.name) {
+                object.visible = old.visible;
+            }
+        }
+    }
+
+    /// Union of the visible objects, each moved by its `explode_offsets` entry, or `None` if
+    /// there are none.
+    pub fn combined(&self, explode: f32) -> Option<SdfNode> {
+        self.objects.iter()
+            .zip(self.explode_offsets(explode))
+            .filter(|(o, _)| o.visible)
+            .map(|(o, offset)| if offset == Vec3::ZERO { o.node.clone() } else { o.node.clone().translate(offset.x, offset.y, offset.z) })
+            .reduce(|mut acc, node| acc.union(node))
+    }
+
+    /// Per-object offsets of the exploded view: each visible object moves away from the mean of
+    /// the visible objects' centers by `factor` times its distance from it. The center of an
+    /// object is that of its bounds, with unbounded axes counting as 0. Hidden objects stay put.
+    pub fn explode_offsets(&self, factor: f32) -> Vec<Vec3> {
+        let centers: Vec<Vec3> = self.objects.iter().map(|o| {
+            let b = o.node.bounds();
+            Vec3::from(((b.min + b.max) * 0.5).to_array().map(|c| if c.is_finite() { c } else { 0.0 }))
+        }).collect();
+        let visible = self.objects.iter().filter(|o| o.visible).count();
+        if factor == 0.0 || visible == 0 {
+            return vec![Vec3::ZERO; self.objects.len()];
+        }
+        let center = centers.iter().zip(&self.objects).filter(|(_, o)| o.visible).map(|(c, _)| *c).sum::<Vec3>() / visible as f32;
+        centers.iter().zip(&self.objects).map(|(c, o)| if o.visible { (*c - center) * factor } else { Vec3::ZERO }).collect()
+    }
+
+    /// Index of the visible object whose surface is closest to `p`, with the objects exploded
+    /// by `explode`.
+    pub fn object_at(&self, p: Vec3, explode: f32) -> Option<usize> {
+        let offsets = self.explode_offsets(explode);
+        self.objects.iter().enumerate()
+            .filter(|(_, o)| o.visible)
+            .min_by(|(i, a), (j, b)| a.node.distance(p - offsets[*i]).total_cmp(&b.node.distance(p - offsets[*j])))
+            .map(|(i, _)| i)
+    }
+}
+
+impl CustomType for Scene {
+    fn build(mut builder: TypeBuilder<Self>) {
+        builder.with_name("Scene")
+            .with_fn("add", Scene::add);
+    }
+}
@@ -1,75 +1,671 @@
-use rhai::{Engine, CustomType, TypeBuilder};
+use rhai::{Array, Dynamic, Engine, CustomType, EvalAltResult, TypeBuilder};
+use std::sync::Arc;
+use crate::height_map::HeightMap;
+use crate::mesh_volume::{MeshVolume, DEFAULT_MESH_RESOLUTION, MAX_MESH_RESOLUTION};
+use crate::scene::Scene;
+use crate::svg_path::parse_svg_path;
+
+/// Smooth-min formula of a smooth boolean (`smooth > 0`).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SmoothKind {
+    // Quadratic polynomial: a compact fillet about `k` wide
+    Poly,
+    // Exponential (log-sum-exp): a softer blend reaching up to `k` further out. Associative, so
+    // a chain of unions blends evenly whatever the order.
+    Exp,
+}
 
 #[derive(Clone, Debug)]
 pub enum SdfOp {
     Sphere { radius: f32 },
     Box { size: [f32; 3] },
     Cylinder { radius: f32, height: f32 },
-    Torus { major_radius: f32, minor_radius: f32 },
+    // Ring around `axis`, which is snapped to X, Y or Z (the ring lies in the XZ plane for Y)
+    Torus { major_radius: f32, minor_radius: f32, axis: [f32; 3] },
+    // Ramp: a box (half extents) cut diagonally so the top rises towards +X; `rotate_y` to face it elsewhere
+    Wedge { size: [f32; 3] },
+    // Superellipsoid; approximate distance, see `step_factor`
+    SuperEllipsoid { radii: [f32; 3], e1: f32, e2: f32 },
+    // Mandelbulb fractal (distance estimator, approximate and slow); fits in a radius-2 ball
+    Mandelbulb { power: f32, iterations: u32 },
+    // Rounded rod: every point within `radius` of the segment from `a` to `b`
+    Capsule { a: [f32; 3], b: [f32; 3], radius: f32 },
+    // Regular tetrahedron, one vertex up; `size` is the circumradius
+    Tetrahedron { size: f32 },
+    // Bowl: sphere shell cut at `cut_height` (opening towards +Y), wall half-thickness `thickness`
+    CutHollowSphere { radius: f32, cut_height: f32, thickness: f32 },
+    // Coil spring along Y, centered, `pitch * turns` tall. Approximate (non-exact) distance.
+    Helix { radius: f32, pitch: f32, thickness: f32, turns: f32 },
+    // Closed 2D polygon from an SVG path in the XY plane, extending infinitely along Z (an exact
+    // prism). There is no `extrude` yet: `intersect` with a box to give it depth. `segments` is
+    // the flattened path, parsed once at construction.
+    SvgProfile { path: String, segments: Vec<[f32; 4]> },
+    // Unbounded cylinder around the unit `axis` through the origin, e.g. for drilling through a part
+    InfiniteCylinder { radius: f32, axis: [f32; 3] },
+    // Unbounded cone with its apex at the origin, opening along the unit `axis`; `angle_deg` is
+    // the half-angle between the axis and the surface
+    InfiniteCone { angle_deg: f32, axis: [f32; 3] },
+    // Closed triangle mesh (OBJ/STL) baked into a signed distance grid when the script runs and
+    // sampled trilinearly; see `MeshVolume` for the resolution tradeoffs
+    MeshVolume { volume: Arc<MeshVolume> },
     
-    Union { a: Box<SdfNode>, b: Box<SdfNode>, smooth: f32 },
-    Subtract { a: Box<SdfNode>, b: Box<SdfNode>, smooth: f32 },
-    Intersect { a: Box<SdfNode>, b: Box<SdfNode>, smooth: f32 },
+    Union { a: Box<SdfNode>, b: Box<SdfNode>, smooth: f32, kind: SmoothKind },
+    Subtract { a: Box<SdfNode>, b: Box<SdfNode>, smooth: f32, kind: SmoothKind },
+    Intersect { a: Box<SdfNode>, b: Box<SdfNode>, smooth: f32, kind: SmoothKind },
+    // Hard `min` / `max` of `a` and `b` that take every material attribute from the operand they
+    // pick. Unlike a crisp union or intersect they never blend: `smooth` rejects them and the
+    // global blend radius skips them.
+    Nearest { a: Box<SdfNode>, b: Box<SdfNode> },
+    Farthest { a: Box<SdfNode>, b: Box<SdfNode> },
+    // Engraves a channel into `target` along the surface of `cutter` (iq's groove): material
+    // within `width` of the cutter's surface is removed down to `depth` below the target's
+    // surface. The channel walls use the target's inside color.
+    Groove { target: Box<SdfNode>, cutter: Box<SdfNode>, depth: f32, width: f32 },
+    // Tube of `radius` along the curve where the surfaces of `a` and `b` meet (iq's pipe)
+    Pipe { a: Box<SdfNode>, b: Box<SdfNode>, radius: f32 },
+    // Exponential smooth union of all `children` at once. Chained `smooth_union` calls blend
+    // differently depending on the pairing order; this log-sum-exp is symmetric in its operands.
+    // A `k` of 0 is a hard union; constructors turn radii under `MIN_SMOOTH_GROUP_K` into 0.
+    SmoothGroup { children: Vec<SdfNode>, k: f32 },
     
     Translate { target: Box<SdfNode>, offset: [f32; 3] },
     Rotate { target: Box<SdfNode>, axis: [f32; 3], angle_deg: f32 },
+    // Rotation by the unit quaternion `rotation` (x, y, z, w), then a move by `offset`. Not
+    // scriptable: `SdfNode::optimize` builds it from chains of static translates and rotates.
+    Transform { target: Box<SdfNode>, rotation: [f32; 4], offset: [f32; 3] },
+    // Uniform scale by `factor` about the origin: the target is sampled at `p / factor` and its
+    // distance multiplied by `factor`, so the result stays exact.
+    Scale { target: Box<SdfNode>, factor: f32 },
     Mirror { target: Box<SdfNode>, axis: [f32; 3] },
+    // Keeps the side of the plane `dot(p, normal) = offset` that `normal` points to and reflects
+    // it onto the other side; `normal` is unit length
+    MirrorPlane { target: Box<SdfNode>, normal: [f32; 3], offset: f32 },
+    // Infinite domain repetition; a spacing of 0 leaves that axis unrepeated
+    Repeat { target: Box<SdfNode>, spacing: [f32; 3] },
+    // `count` copies unioned, copy `i` rotated by `i * rotate_step_deg` (about X, then Y, then Z)
+    // and then moved by `i * offset`. Unlike `Repeat` each copy may differ, so every copy is
+    // evaluated; the target is emitted once and called in a loop.
+    ArrayLinear { target: Box<SdfNode>, count: u32, offset: [f32; 3], rotate_step_deg: [f32; 3] },
+    // Offsets the sample point by a 3D value-noise vector (unlike a displacement, which perturbs
+    // the distance). Stretches space, so the distance is non-exact; see `step_factor`.
+    Warp { target: Box<SdfNode>, amplitude: f32, frequency: f32 },
+    // Adds `amplitude * (h - 0.5)` to the distance, `h` being the height map sampled triplanarly
+    // (projected along X, Y and Z, blended by the target's normal) at `p * scale`. The map repeats,
+    // so `scale` is tiles per unit. Non-exact like `Warp`; see `step_factor`. The blend normal
+    // takes four more evaluations of the target (on the CPU as in the shader), so a displacement
+    // nested inside another multiplies the cost of everything below it by five; nest sparingly.
+    DisplaceMap { target: Box<SdfNode>, texture: Arc<HeightMap>, amplitude: f32, scale: f32 },
+    // Hollows the solid into a wall of `thickness` on each side of its surface (`abs(d) - thickness`).
+    // It always acts on the 3D field (there is no revolve or extrude, and `SvgProfile` is already an
+    // infinite prism): a cylinder becomes a closed can rather than an open pipe, so cut the caps off
+    // with `intersect` for that. On an `SvgProfile` it gives an open-ended wall along Z.
+    Annular { target: Box<SdfNode>, thickness: f32 },
+    // Multiplies the texture coordinates of everything below it, per axis: a `DisplaceMap` in the
+    // target samples at `p * scale * this`, so a larger value tiles its map more densely without
+    // resizing the geometry. Nested scales multiply. `DisplaceMap` is the only texture user so far.
+    UvScale { target: Box<SdfNode>, scale: [f32; 3] },
+    // `count` nested shells, `spacing / 4` thick: the surface and its inward offsets every
+    // `spacing` (the onion trick repeated). Only visible where something cuts into it. Each shell
+    // is a separate surface, so shells much thinner than the marcher's hit epsilon break up.
+    OnionRings { target: Box<SdfNode>, count: u32, spacing: f32 },
     
     // Attribute
     Color { target: Box<SdfNode>, color: [f32; 3] },
+    // Color of surfaces revealed when this shape is cut by `subtract`. Apply after `color`,
+    // which resets it to the outer color.
+    InsideColor { target: Box<SdfNode>, color: [f32; 3] },
+    // Glossy varnish layer over the base material (car paint, lacquer): `strength` in [0, 1],
+    // `roughness` of the coat's own highlight in [0, 1]. Shading only; the CPU side ignores it.
+    Clearcoat { target: Box<SdfNode>, strength: f32, roughness: f32 },
+    // Translucent medium (fog, tinted glass) filling the target instead of a surface: what is
+    // seen through it is tinted towards `absorption_color` (white absorbs nothing), faster for a
+    // higher `density` per unit of depth. It only absorbs, it does not glow or scatter light.
+    // Surfaces and booleans treat it as empty space; only the transforms above it apply.
+    Volume { target: Box<SdfNode>, density: f32, absorption_color: [f32; 3] },
+
+    // Animation: `expr` is baked into the shader and evaluated against the time uniform
+    Animated { target: Box<SdfNode>, channel: AnimChannel, expr: TimeExpr },
 }
 
+/// What an `SdfOp::Animated` node drives with its time expression.
+#[derive(Clone, Copy, Debug)]
+pub enum AnimChannel {
+    /// Offset along `dir` by `expr(t)` units.
+    Translate { dir: [f32; 3] },
+    /// Rotation about a principal axis by `expr(t)` degrees.
+    Rotate { axis: [f32; 3] },
+    /// Grows the surface outward by `expr(t)` units (negative shrinks).
+    Inflate,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Wave { Sin, Cos, Saw, Linear }
+
+/// `base + amp * wave(freq * t)`, with `t` in seconds. `Sin`/`Cos` take `freq` in Hz,
+/// `Saw` ramps 0..1 once per period and `Linear` is just `freq * t`.
+#[derive(Clone, Copy, Debug)]
+pub struct TimeExpr {
+    pub wave: Wave,
+    pub freq: f32,
+    pub amp: f32,
+    pub base: f32,
+}
+
+impl TimeExpr {
+    pub fn parse(wave: &str, freq: f32, amp: f32) -> Result<Self, Box<EvalAltResult>> {
+        let wave = match wave {
+            "sin" => Wave::Sin,
+            "cos" => Wave::Cos,
+            "saw" => Wave::Saw,
+            "linear" => Wave::Linear,
+            other => return Err(format!("unknown wave '{other}' (expected sin, cos, saw or linear)").into()),
+        };
+        Ok(Self { wave, freq, amp, base: 0.0 })
+    }
+
+    pub fn offset(&mut self, base: f32) -> TimeExpr { Self { base: self.base + base, ..*self } }
+}
+
+impl CustomType for TimeExpr {
+    fn build(mut builder: TypeBuilder<Self>) {
+        builder.with_name("TimeExpr")
+            .with_fn("offset", TimeExpr::offset);
+    }
+}
+
+/// Normalizes a script-supplied axis, falling back to +Y for a zero vector.
+fn unit_axis(x: f32, y: f32, z: f32) -> [f32; 3] {
+    let len = (x * x + y * y + z * z).sqrt();
+    if len > 1e-6 { [x / len, y / len, z / len] } else { [0.0, 1.0, 0.0] }
+}
+
+/// The coordinate axis (positive unit vector) closest in direction to `(x, y, z)`; Y if zero.
+fn coordinate_axis(x: f32, y: f32, z: f32) -> [f32; 3] {
+    let (ax, ay, az) = (x.abs(), y.abs(), z.abs());
+    if ax > ay && ax >= az { [1.0, 0.0, 0.0] } else if az > ay && az > ax { [0.0, 0.0, 1.0] } else { [0.0, 1.0, 0.0] }
+}
+
+/// RGB for hue `h` in degrees (wrapping), saturation `s` and value `v` in [0, 1].
+fn hsv_to_rgb(h: f32, s: f32, v: f32) -> [f32; 3] {
+    let (s, v) = (s.clamp(0.0, 1.0), v.clamp(0.0, 1.0));
+    let h = h.rem_euclid(360.0) / 60.0;
+    let c = v * s;
+    let x = c * (1.0 - (h % 2.0 - 1.0).abs());
+    let [r, g, b] = match h as u32 {
+        0 => [c, x, 0.0],
+        1 => [x, c, 0.0],
+        2 => [0.0, c, x],
+        3 => [0.0, x, c],
+        4 => [x, 0.0, c],
+        _ => [c, 0.0, x],
+    };
+    [r + v - c, g + v - c, b + v - c]
+}
+
+/// `k` for a `smooth_group`: radii under `MIN_SMOOTH_GROUP_K` (and negative ones) become 0.
+pub(crate) fn smooth_group_k(k: f32) -> f32 {
+    if k >= MIN_SMOOTH_GROUP_K { k } else { 0.0 }
+}
+
+/// `hsv(h, s, v)` for scripts: the color as an `[r, g, b]` array, for `color(rgb)`.
+fn hsv(h: f32, s: f32, v: f32) -> Array {
+    hsv_to_rgb(h, s, v).iter().map(|c| Dynamic::from_float(*c)).collect()
+}
+
+/// Upper bound on fractal iterations, to keep the shader's per-pixel cost sane.
+pub const MAX_FRACTAL_ITERATIONS: i64 = 16;
+/// Upper bound on `onion_rings` shells.
+pub const MAX_ONION_RINGS: i64 = 64;
+/// Upper bound on `array` copies: each one evaluates the whole target.
+pub const MAX_ARRAY_COUNT: i64 = 64;
+/// Smallest `smooth_group` radius; smaller ones become a hard union. The shader divides by the
+/// radius and writes it with four decimals, so anything smaller would print as zero.
+pub const MIN_SMOOTH_GROUP_K: f32 = 1e-4;
+
 #[derive(Clone, Debug)]
 pub struct SdfNode {
     pub op: SdfOp,
 }
 
 impl SdfNode {
+    /// Direct child nodes, in evaluation order.
+    pub fn children(&self) -> Vec<&SdfNode> {
+        match &self.op {
+            SdfOp::Sphere { .. } | SdfOp::Box { .. } | SdfOp::Cylinder { .. } | SdfOp::Torus { .. }
+            | SdfOp::Wedge { .. } | SdfOp::SuperEllipsoid { .. } | SdfOp::Mandelbulb { .. } | SdfOp::Tetrahedron { .. }
+            | SdfOp::CutHollowSphere { .. } | SdfOp::Helix { .. } | SdfOp::SvgProfile { .. } | SdfOp::Capsule { .. }
+            | SdfOp::InfiniteCylinder { .. } | SdfOp::InfiniteCone { .. } | SdfOp::MeshVolume { .. } => Vec::new(),
+            SdfOp::Union { a, b, .. } | SdfOp::Subtract { a, b, .. } | SdfOp::Intersect { a, b, .. } | SdfOp::Pipe { a, b, .. }
+            | SdfOp::Nearest { a, b } | SdfOp::Farthest { a, b }
+            | SdfOp::Groove { target: a, cutter: b, .. } => vec![a, b],
+            SdfOp::SmoothGroup { children, .. } => children.iter().collect(),
+            SdfOp::Translate { target, .. } | SdfOp::Rotate { target, .. } | SdfOp::Transform { target, .. } | SdfOp::Scale { target, .. } | SdfOp::Mirror { target, .. } | SdfOp::MirrorPlane { target, .. } | SdfOp::Repeat { target, .. }
+            | SdfOp::ArrayLinear { target, .. } | SdfOp::Warp { target, .. } | SdfOp::DisplaceMap { target, .. } | SdfOp::Annular { target, .. } | SdfOp::OnionRings { target, .. } | SdfOp::Color { target, .. } | SdfOp::InsideColor { target, .. }
+            | SdfOp::Clearcoat { target, .. } | SdfOp::Volume { target, .. } | SdfOp::Animated { target, .. } | SdfOp::UvScale { target, .. } => vec![target],
+        }
+    }
+
+    /// Mutable counterpart of `children`, in the same order.
+    pub fn children_mut(&mut self) -> Vec<&mut SdfNode> {
+        match &mut self.op {
+            SdfOp::Sphere { .. } | SdfOp::Box { .. } | SdfOp::Cylinder { .. } | SdfOp::Torus { .. }
+            | SdfOp::Wedge { .. } | SdfOp::SuperEllipsoid { .. } | SdfOp::Mandelbulb { .. } | SdfOp::Tetrahedron { .. }
+            | SdfOp::CutHollowSphere { .. } | SdfOp::Helix { .. } | SdfOp::SvgProfile { .. } | SdfOp::Capsule { .. }
+            | SdfOp::InfiniteCylinder { .. } | SdfOp::InfiniteCone { .. } | SdfOp::MeshVolume { .. } => Vec::new(),
+            SdfOp::Union { a, b, .. } | SdfOp::Subtract { a, b, .. } | SdfOp::Intersect { a, b, .. } | SdfOp::Pipe { a, b, .. }
+            | SdfOp::Nearest { a, b } | SdfOp::Farthest { a, b }
+            | SdfOp::Groove { target: a, cutter: b, .. } => vec![a, b],
+            SdfOp::SmoothGroup { children, .. } => children.iter_mut().collect(),
+            SdfOp::Translate { target, .. } | SdfOp::Rotate { target, .. } | SdfOp::Transform { target, .. } | SdfOp::Scale { target, .. } | SdfOp::Mirror { target, .. } | SdfOp::MirrorPlane { target, .. } | SdfOp::Repeat { target, .. }
+            | SdfOp::ArrayLinear { target, .. } | SdfOp::Warp { target, .. } | SdfOp::DisplaceMap { target, .. } | SdfOp::Annular { target, .. } | SdfOp::OnionRings { target, .. } | SdfOp::Color { target, .. } | SdfOp::InsideColor { target, .. }
+            | SdfOp::Clearcoat { target, .. } | SdfOp::Volume { target, .. } | SdfOp::Animated { target, .. } | SdfOp::UvScale { target, .. } => vec![target],
+        }
+    }
+
+    /// Node reached by following child indices (as returned by `pick_path`).
+    pub fn node_at(&self, path: &[usize]) -> Option<&SdfNode> {
+        path.iter().try_fold(self, |node, &i| node.children().get(i).copied())
+    }
+
+    pub fn node_at_mut(&mut self, path: &[usize]) -> Option<&mut SdfNode> {
+        path.iter().try_fold(self, |node, &i| node.children_mut().into_iter().nth(i))
+    }
+
+    /// Whether the generated shader depends on the time uniform.
+    pub fn is_animated(&self) -> bool {
+        matches!(self.op, SdfOp::Animated { .. }) || self.children().iter().any(|c| c.is_animated())
+    }
+
+    /// Distinct height maps used under this node, by path, in the order that assigns their
+    /// texture array layers.
+    pub fn height_maps(&self) -> Vec<Arc<HeightMap>> {
+        let mut maps: Vec<Arc<HeightMap>> = Vec::new();
+        self.collect_height_maps(&mut maps);
+        maps
+    }
+
+    /// Distinct baked meshes used under this node, in the order they are stacked in the
+    /// mesh texture (see `mesh_volume::atlas_layout`).
+    pub fn mesh_volumes(&self) -> Vec<Arc<MeshVolume>> {
+        let mut volumes: Vec<Arc<MeshVolume>> = Vec::new();
+        self.collect_mesh_volumes(&mut volumes);
+        volumes
+    }
+
+    fn collect_mesh_volumes(&self, volumes: &mut Vec<Arc<MeshVolume>>) {
+        if let SdfOp::MeshVolume { volume } = &self.op {
+            // By bake, not path: the same file at two resolutions is two different grids
+            if !volumes.iter().any(|v| Arc::ptr_eq(v, volume)) {
+                volumes.push(volume.clone());
+            }
+        }
+        for child in self.children() {
+            child.collect_mesh_volumes(volumes);
+        }
+    }
+
+    fn collect_height_maps(&self, maps: &mut Vec<Arc<HeightMap>>) {
+        if let SdfOp::DisplaceMap { texture, .. } = &self.op {
+            if !maps.iter().any(|m| m.path == texture.path) {
+                maps.push(texture.clone());
+            }
+        }
+        for child in self.children() {
+            child.collect_height_maps(maps);
+        }
+    }
+
+    /// Fraction of the field value the raymarcher can safely advance by. Exact distances allow 1;
+    /// approximate fields that may overestimate need shorter steps to avoid overshooting.
+    pub fn step_factor(&self) -> f32 {
+        self.step_factor_uv(1.0)
+    }
+
+    /// `step_factor` below `UvScale` nodes whose largest combined scale is `uv`.
+    fn step_factor_uv(&self, uv: f32) -> f32 {
+        let uv = match self.op {
+            SdfOp::UvScale { scale, .. } => uv * scale.into_iter().fold(0.0, f32::max),
+            _ => uv,
+        };
+        let own = match self.op {
+            SdfOp::Helix { .. } => 0.8,
+            SdfOp::SuperEllipsoid { .. } | SdfOp::Mandelbulb { .. } => 0.5,
+            // Value noise changes by at most ~3 per unit per axis, so the warp can stretch
+            // distances by about 1 + 3 * amplitude * frequency
+            SdfOp::Warp { amplitude, frequency, .. } => (1.0 / (1.0 + 3.0 * amplitude * frequency)).max(0.1),
+            // Assumes a reasonably smooth map whose value changes by at most ~4 per tile
+            SdfOp::DisplaceMap { amplitude, scale, .. } => (1.0 / (1.0 + 4.0 * amplitude * scale * uv)).max(0.1),
+            // Trilinear filtering can steepen the field a little between grid points
+            SdfOp::MeshVolume { .. } => 0.9,
+            _ => 1.0,
+        };
+        self.children().iter().map(|c| c.step_factor_uv(uv)).fold(own, f32::min)
+    }
+
     pub fn new_sphere(radius: f32) -> Self { Self { op: SdfOp::Sphere { radius } } }
     pub fn new_box(x: f32, y: f32, z: f32) -> Self { Self { op: SdfOp::Box { size: [x, y, z] } } }
     pub fn new_cylinder(r: f32, h: f32) -> Self { Self { op: SdfOp::Cylinder { radius: r, height: h } } }
-    pub fn new_torus(major: f32, minor: f32) -> Self { Self { op: SdfOp::Torus { major_radius: major, minor_radius: minor } } }
+    pub fn new_torus(major: f32, minor: f32) -> Self { Self::new_torus_axis(major, minor, 0.0, 1.0, 0.0) }
+    /// The shader swizzles the point instead of rotating it, so the axis is snapped to the
+    /// coordinate axis nearest `(x, y, z)`.
+    pub fn new_torus_axis(major: f32, minor: f32, x: f32, y: f32, z: f32) -> Self {
+        Self { op: SdfOp::Torus { major_radius: major, minor_radius: minor, axis: coordinate_axis(x, y, z) } }
+    }
+    pub fn new_wedge(x: f32, y: f32, z: f32) -> Self { Self { op: SdfOp::Wedge { size: [x, y, z] } } }
+    pub fn new_superellipsoid(rx: f32, ry: f32, rz: f32, e1: f32, e2: f32) -> Self {
+        Self { op: SdfOp::SuperEllipsoid { radii: [rx.abs().max(1e-4), ry.abs().max(1e-4), rz.abs().max(1e-4)], e1: e1.clamp(0.1, 4.0), e2: e2.clamp(0.1, 4.0) } }
+    }
+    /// Iterations are capped: each one is a `pow` and trig round in every `map()` call.
+    pub fn new_mandelbulb(power: f32, iterations: i64) -> Self {
+        Self { op: SdfOp::Mandelbulb { power: power.clamp(2.0, 16.0), iterations: iterations.clamp(1, MAX_FRACTAL_ITERATIONS) as u32 } }
+    }
+    pub fn new_capsule(ax: f32, ay: f32, az: f32, bx: f32, by: f32, bz: f32, radius: f32) -> Self {
+        Self { op: SdfOp::Capsule { a: [ax, ay, az], b: [bx, by, bz], radius: radius.abs() } }
+    }
+    pub fn new_tetrahedron(size: f32) -> Self { Self { op: SdfOp::Tetrahedron { size: size.abs().max(1e-4) } } }
+    pub fn new_cut_hollow_sphere(r: f32, h: f32, t: f32) -> Self {
+        let radius = r.abs();
+        Self { op: SdfOp::CutHollowSphere { radius, cut_height: h.clamp(-radius, radius), thickness: t.abs() } }
+    }
+    pub fn new_helix(radius: f32, pitch: f32, thickness: f32, turns: f32) -> Self {
+        Self { op: SdfOp::Helix { radius, pitch: pitch.abs().max(1e-3), thickness: thickness.abs(), turns: turns.max(0.0) } }
+    }
+    pub fn new_inf_cylinder(radius: f32) -> Self { Self::new_inf_cylinder_axis(radius, 0.0, 1.0, 0.0) }
+    pub fn new_inf_cylinder_axis(radius: f32, x: f32, y: f32, z: f32) -> Self {
+        Self { op: SdfOp::InfiniteCylinder { radius: radius.abs(), axis: unit_axis(x, y, z) } }
+    }
+    pub fn new_inf_cone(angle_deg: f32) -> Self { Self::new_inf_cone_axis(angle_deg, 0.0, 1.0, 0.0) }
+    pub fn new_inf_cone_axis(angle_deg: f32, x: f32, y: f32, z: f32) -> Self {
+        Self { op: SdfOp::InfiniteCone { angle_deg: angle_deg.clamp(0.1, 89.9), axis: unit_axis(x, y, z) } }
+    }
+    pub fn new_svg_profile(path: &str) -> Result<Self, Box<EvalAltResult>> { Self::new_svg_profile_scaled(path, 1.0) }
+    pub fn new_svg_profile_scaled(path: &str, scale: f32) -> Result<Self, Box<EvalAltResult>> {
+        let segments = parse_svg_path(path, scale).map_err(|e| format!("svg_profile: {e}"))?;
+        Ok(Self { op: SdfOp::SvgProfile { path: path.to_string(), segments } })
+    }
+
+    pub fn new_mesh(path: &str) -> Result<Self, Box<EvalAltResult>> { Self::new_mesh_resolution(path, DEFAULT_MESH_RESOLUTION) }
+    pub fn new_mesh_resolution(path: &str, resolution: i64) -> Result<Self, Box<EvalAltResult>> {
+        let volume = MeshVolume::load_shared(path, resolution.clamp(8, MAX_MESH_RESOLUTION) as u32).map_err(|e| format!("mesh: {e}"))?;
+        Ok(Self { op: SdfOp::MeshVolume { volume } })
+    }
+
+    /// `smooth_group([a, b, c], k)`; see `SdfOp::SmoothGroup`. `k` below `MIN_SMOOTH_GROUP_K` is a hard union.
+    pub fn new_smooth_group(shapes: Array, k: f32) -> Result<Self, Box<EvalAltResult>> {
+        let children: Option<Vec<SdfNode>> = shapes.into_iter().map(|s| s.try_cast::<SdfNode>()).collect();
+        match children {
+            Some(children) if !children.is_empty() => Ok(Self { op: SdfOp::SmoothGroup { children, k: smooth_group_k(k) } }),
+            Some(_) => Err("smooth_group: needs at least one shape".into()),
+            None => Err("smooth_group: expected an array of shapes".into()),
+        }
+    }
+
+    // Colored constructors: shorthand for `new_*(..).color(r, g, b)`
+    pub fn new_sphere_colored(radius: f32, r: f32, g: f32, b: f32) -> Self { Self::new_sphere(radius).color(r, g, b) }
+    pub fn new_box_colored(x: f32, y: f32, z: f32, r: f32, g: f32, b: f32) -> Self { Self::new_box(x, y, z).color(r, g, b) }
+    pub fn new_cylinder_colored(radius: f32, h: f32, r: f32, g: f32, b: f32) -> Self { Self::new_cylinder(radius, h).color(r, g, b) }
+    pub fn new_torus_colored(major: f32, minor: f32, r: f32, g: f32, b: f32) -> Self { Self::new_torus(major, minor).color(r, g, b) }
+    pub fn new_wedge_colored(x: f32, y: f32, z: f32, r: f32, g: f32, b: f32) -> Self { Self::new_wedge(x, y, z).color(r, g, b) }
+    #[allow(clippy::too_many_arguments)]
+    pub fn new_superellipsoid_colored(rx: f32, ry: f32, rz: f32, e1: f32, e2: f32, r: f32, g: f32, b: f32) -> Self {
+        Self::new_superellipsoid(rx, ry, rz, e1, e2).color(r, g, b)
+    }
+    pub fn new_mandelbulb_colored(power: f32, iterations: i64, r: f32, g: f32, b: f32) -> Self { Self::new_mandelbulb(power, iterations).color(r, g, b) }
+    #[allow(clippy::too_many_arguments)]
+    pub fn new_capsule_colored(ax: f32, ay: f32, az: f32, bx: f32, by: f32, bz: f32, radius: f32, r: f32, g: f32, b: f32) -> Self {
+        Self::new_capsule(ax, ay, az, bx, by, bz, radius).color(r, g, b)
+    }
+    pub fn new_tetrahedron_colored(size: f32, r: f32, g: f32, b: f32) -> Self { Self::new_tetrahedron(size).color(r, g, b) }
+    pub fn new_cut_hollow_sphere_colored(radius: f32, h: f32, t: f32, r: f32, g: f32, b: f32) -> Self { Self::new_cut_hollow_sphere(radius, h, t).color(r, g, b) }
+    pub fn new_helix_colored(radius: f32, pitch: f32, thickness: f32, turns: f32, r: f32, g: f32, b: f32) -> Self {
+        Self::new_helix(radius, pitch, thickness, turns).color(r, g, b)
+    }
+    pub fn new_inf_cylinder_colored(radius: f32, r: f32, g: f32, b: f32) -> Self { Self::new_inf_cylinder(radius).color(r, g, b) }
+    pub fn new_inf_cone_colored(angle_deg: f32, r: f32, g: f32, b: f32) -> Self { Self::new_inf_cone(angle_deg).color(r, g, b) }
+    pub fn new_svg_profile_colored(path: &str, r: f32, g: f32, b: f32) -> Result<Self, Box<EvalAltResult>> { Ok(Self::new_svg_profile(path)?.color(r, g, b)) }
+    pub fn new_mesh_colored(path: &str, r: f32, g: f32, b: f32) -> Result<Self, Box<EvalAltResult>> { Ok(Self::new_mesh(path)?.color(r, g, b)) }
 
-    pub fn union(&mut self, other: SdfNode) -> SdfNode { Self { op: SdfOp::Union { a: Box::new(self.clone()), b: Box::new(other), smooth: 0.0 } } }
-    pub fn smooth_union(&mut self, other: SdfNode, k: f32) -> SdfNode { Self { op: SdfOp::Union { a: Box::new(self.clone()), b: Box::new(other), smooth: k } } }
-    pub fn subtract(&mut self, other: SdfNode) -> SdfNode { Self { op: SdfOp::Subtract { a: Box::new(self.clone()), b: Box::new(other), smooth: 0.0 } } }
-    pub fn smooth_subtract(&mut self, other: SdfNode, k: f32) -> SdfNode { Self { op: SdfOp::Subtract { a: Box::new(self.clone()), b: Box::new(other), smooth: k } } }
+    pub fn union(&mut self, other: SdfNode) -> SdfNode { self.smooth_union(other, 0.0) }
+    pub fn smooth_union(&mut self, other: SdfNode, k: f32) -> SdfNode { Self { op: SdfOp::Union { a: Box::new(self.clone()), b: Box::new(other), smooth: k, kind: SmoothKind::Poly } } }
+    pub fn smooth_union_exp(&mut self, other: SdfNode, k: f32) -> SdfNode { Self { op: SdfOp::Union { a: Box::new(self.clone()), b: Box::new(other), smooth: k, kind: SmoothKind::Exp } } }
+    pub fn subtract(&mut self, other: SdfNode) -> SdfNode { self.smooth_subtract(other, 0.0) }
+    pub fn smooth_subtract(&mut self, other: SdfNode, k: f32) -> SdfNode { Self { op: SdfOp::Subtract { a: Box::new(self.clone()), b: Box::new(other), smooth: k, kind: SmoothKind::Poly } } }
+    pub fn smooth_subtract_exp(&mut self, other: SdfNode, k: f32) -> SdfNode { Self { op: SdfOp::Subtract { a: Box::new(self.clone()), b: Box::new(other), smooth: k, kind: SmoothKind::Exp } } }
+    pub fn intersect(&mut self, other: SdfNode) -> SdfNode { self.smooth_intersect(other, 0.0) }
+    pub fn smooth_intersect(&mut self, other: SdfNode, k: f32) -> SdfNode { Self { op: SdfOp::Intersect { a: Box::new(self.clone()), b: Box::new(other), smooth: k, kind: SmoothKind::Poly } } }
+    pub fn smooth_intersect_exp(&mut self, other: SdfNode, k: f32) -> SdfNode { Self { op: SdfOp::Intersect { a: Box::new(self.clone()), b: Box::new(other), smooth: k, kind: SmoothKind::Exp } } }
+    pub fn blend(&mut self, other: SdfNode, k: f32) -> SdfNode { self.smooth_union(other, k) }
+    pub fn nearest(&mut self, other: SdfNode) -> SdfNode { Self { op: SdfOp::Nearest { a: Box::new(self.clone()), b: Box::new(other) } } }
+    pub fn farthest(&mut self, other: SdfNode) -> SdfNode { Self { op: SdfOp::Farthest { a: Box::new(self.clone()), b: Box::new(other) } } }
+    /// Sets the blend radius of a boolean after the fact, e.g. `a.subtract(b).smooth(0.1)`,
+    /// keeping its smoothing kind. 0 makes it crisp again; a negative radius is an error.
+    pub fn smooth(&mut self, k: f32) -> Result<SdfNode, Box<EvalAltResult>> {
+        if k < 0.0 || k.is_nan() {
+            return Err(format!("smooth: the radius must be 0 or more, got {k}").into());
+        }
+        let mut node = self.clone();
+        match &mut node.op {
+            SdfOp::Union { smooth, .. } | SdfOp::Subtract { smooth, .. } | SdfOp::Intersect { smooth, .. } => *smooth = k,
+            _ => return Err("smooth: only applies to union, subtract or intersect".into()),
+        }
+        Ok(node)
+    }
     
+    pub fn groove(&mut self, cutter: SdfNode, depth: f32, width: f32) -> SdfNode {
+        Self { op: SdfOp::Groove { target: Box::new(self.clone()), cutter: Box::new(cutter), depth: depth.abs(), width: width.abs() } }
+    }
+    pub fn pipe(&mut self, other: SdfNode, radius: f32) -> SdfNode { Self { op: SdfOp::Pipe { a: Box::new(self.clone()), b: Box::new(other), radius: radius.abs() } } }
     pub fn translate(&mut self, x: f32, y: f32, z: f32) -> SdfNode { Self { op: SdfOp::Translate { target: Box::new(self.clone()), offset: [x, y, z] } } }
     pub fn rotate_x(&mut self, deg: f32) -> SdfNode { Self { op: SdfOp::Rotate { target: Box::new(self.clone()), axis: [1.0, 0.0, 0.0], angle_deg: deg } } }
     pub fn rotate_y(&mut self, deg: f32) -> SdfNode { Self { op: SdfOp::Rotate { target: Box::new(self.clone()), axis: [0.0, 1.0, 0.0], angle_deg: deg } } }
     pub fn rotate_z(&mut self, deg: f32) -> SdfNode { Self { op: SdfOp::Rotate { target: Box::new(self.clone()), axis: [0.0, 0.0, 1.0], angle_deg: deg } } }
+    pub fn scale(&mut self, factor: f32) -> SdfNode { Self { op: SdfOp::Scale { target: Box::new(self.clone()), factor: factor.abs().max(1e-4) } } }
     
     pub fn mirror_x(&mut self) -> SdfNode { Self { op: SdfOp::Mirror { target: Box::new(self.clone()), axis: [1.0, 0.0, 0.0] } } }
     pub fn mirror_y(&mut self) -> SdfNode { Self { op: SdfOp::Mirror { target: Box::new(self.clone()), axis: [0.0, 1.0, 0.0] } } }
     pub fn mirror_z(&mut self) -> SdfNode { Self { op: SdfOp::Mirror { target: Box::new(self.clone()), axis: [0.0, 0.0, 1.0] } } }
+    pub fn mirror_plane(&mut self, nx: f32, ny: f32, nz: f32, d: f32) -> SdfNode {
+        // Scaling the plane equation keeps the plane, so d is divided by the normal's length too
+        let len = (nx * nx + ny * ny + nz * nz).sqrt();
+        let offset = if len > 1e-6 { d / len } else { d };
+        Self { op: SdfOp::MirrorPlane { target: Box::new(self.clone()), normal: unit_axis(nx, ny, nz), offset } }
+    }
+    pub fn repeat(&mut self, x: f32, y: f32, z: f32) -> SdfNode { Self { op: SdfOp::Repeat { target: Box::new(self.clone()), spacing: [x.max(0.0), y.max(0.0), z.max(0.0)] } } }
+    pub fn array(&mut self, count: i64, dx: f32, dy: f32, dz: f32) -> SdfNode { self.array_rotated(count, dx, dy, dz, 0.0, 0.0, 0.0) }
+    #[allow(clippy::too_many_arguments)]
+    pub fn array_rotated(&mut self, count: i64, dx: f32, dy: f32, dz: f32, rx: f32, ry: f32, rz: f32) -> SdfNode {
+        Self { op: SdfOp::ArrayLinear { target: Box::new(self.clone()), count: count.clamp(1, MAX_ARRAY_COUNT) as u32, offset: [dx, dy, dz], rotate_step_deg: [rx, ry, rz] } }
+    }
+    pub fn warp(&mut self, amplitude: f32, frequency: f32) -> SdfNode { Self { op: SdfOp::Warp { target: Box::new(self.clone()), amplitude: amplitude.abs(), frequency: frequency.abs() } } }
+    pub fn displace_map(&mut self, path: &str, amplitude: f32, scale: f32) -> Result<SdfNode, Box<EvalAltResult>> {
+        let texture = Arc::new(HeightMap::load(path).map_err(|e| format!("displace_map: {e}"))?);
+        Ok(Self { op: SdfOp::DisplaceMap { target: Box::new(self.clone()), texture, amplitude: amplitude.abs(), scale: scale.abs().max(1e-4) } })
+    }
+    pub fn uv_scale(&mut self, x: f32, y: f32, z: f32) -> SdfNode {
+        Self { op: SdfOp::UvScale { target: Box::new(self.clone()), scale: [x.abs().max(1e-4), y.abs().max(1e-4), z.abs().max(1e-4)] } }
+    }
+    pub fn annular(&mut self, thickness: f32) -> SdfNode { Self { op: SdfOp::Annular { target: Box::new(self.clone()), thickness: thickness.abs() } } }
+    pub fn onion_rings(&mut self, count: i64, spacing: f32) -> SdfNode {
+        Self { op: SdfOp::OnionRings { target: Box::new(self.clone()), count: count.clamp(1, MAX_ONION_RINGS) as u32, spacing: spacing.abs().max(1e-3) } }
+    }
 
     pub fn color(&mut self, r: f32, g: f32, b: f32) -> SdfNode { 
         Self { op: SdfOp::Color { target: Box::new(self.clone()), color: [r, g, b] } } 
     }
+    pub fn color_hsv(&mut self, h: f32, s: f32, v: f32) -> SdfNode {
+        let [r, g, b] = hsv_to_rgb(h, s, v);
+        self.color(r, g, b)
+    }
+    /// `color([r, g, b])`, e.g. with the result of `hsv`.
+    pub fn color_array(&mut self, rgb: Array) -> Result<SdfNode, Box<EvalAltResult>> {
+        let channels: Option<Vec<f32>> = rgb.iter()
+            .map(|c| c.as_float().ok().or_else(|| c.as_int().ok().map(|i| i as f32)))
+            .collect();
+        match channels.as_deref() {
+            Some(&[r, g, b]) => Ok(self.color(r, g, b)),
+            _ => Err("color: expected an array of three numbers [r, g, b]".into()),
+        }
+    }
+    pub fn inside_color(&mut self, r: f32, g: f32, b: f32) -> SdfNode {
+        Self { op: SdfOp::InsideColor { target: Box::new(self.clone()), color: [r, g, b] } }
+    }
+    pub fn clearcoat(&mut self, strength: f32, roughness: f32) -> SdfNode {
+        Self { op: SdfOp::Clearcoat { target: Box::new(self.clone()), strength: strength.clamp(0.0, 1.0), roughness: roughness.clamp(0.0, 1.0) } }
+    }
+    pub fn volume(&mut self, density: f32, r: f32, g: f32, b: f32) -> SdfNode {
+        let absorption_color = [r, g, b].map(|c| c.clamp(0.0, 1.0));
+        Self { op: SdfOp::Volume { target: Box::new(self.clone()), density: density.max(0.0), absorption_color } }
+    }
+
+    fn animated(&self, channel: AnimChannel, expr: TimeExpr) -> SdfNode { Self { op: SdfOp::Animated { target: Box::new(self.clone()), channel, expr } } }
+    pub fn translate_anim(&mut self, x: f32, y: f32, z: f32, expr: TimeExpr) -> SdfNode { self.animated(AnimChannel::Translate { dir: [x, y, z] }, expr) }
+    pub fn rotate_x_anim(&mut self, expr: TimeExpr) -> SdfNode { self.animated(AnimChannel::Rotate { axis: [1.0, 0.0, 0.0] }, expr) }
+    pub fn rotate_y_anim(&mut self, expr: TimeExpr) -> SdfNode { self.animated(AnimChannel::Rotate { axis: [0.0, 1.0, 0.0] }, expr) }
+    pub fn rotate_z_anim(&mut self, expr: TimeExpr) -> SdfNode { self.animated(AnimChannel::Rotate { axis: [0.0, 0.0, 1.0] }, expr) }
+    pub fn inflate_anim(&mut self, expr: TimeExpr) -> SdfNode { self.animated(AnimChannel::Inflate, expr) }
 }
 
 impl CustomType for SdfNode {
     fn build(mut builder: TypeBuilder<Self>) {
         builder.with_name("SdfNode")
             .with_fn("union", SdfNode::union).with_fn("add", SdfNode::union)
-            .with_fn("smooth_union", SdfNode::smooth_union)
+            .with_fn("smooth_union", SdfNode::smooth_union).with_fn("smooth_union_exp", SdfNode::smooth_union_exp)
             .with_fn("subtract", SdfNode::subtract).with_fn("sub", SdfNode::subtract)
+            .with_fn("smooth_subtract", SdfNode::smooth_subtract).with_fn("smooth_subtract_exp", SdfNode::smooth_subtract_exp)
+            .with_fn("intersect", SdfNode::intersect)
+            .with_fn("smooth_intersect", SdfNode::smooth_intersect).with_fn("smooth_intersect_exp", SdfNode::smooth_intersect_exp)
+            .with_fn("blend", SdfNode::blend).with_fn("smooth", SdfNode::smooth)
+            .with_fn("nearest", SdfNode::nearest).with_fn("farthest", SdfNode::farthest)
+            .with_fn("groove", SdfNode::groove)
+            .with_fn("pipe", SdfNode::pipe)
             .with_fn("translate", SdfNode::translate).with_fn("move", SdfNode::translate)
             .with_fn("rotate_x", SdfNode::rotate_x)
             .with_fn("rotate_y", SdfNode::rotate_y)
             .with_fn("rotate_z", SdfNode::rotate_z)
+            .with_fn("scale", SdfNode::scale)
             .with_fn("mirror_x", SdfNode::mirror_x)
             .with_fn("mirror_y", SdfNode::mirror_y)
             .with_fn("mirror_z", SdfNode::mirror_z)
-            .with_fn("color", SdfNode::color);
+            .with_fn("mirror_plane", SdfNode::mirror_plane)
+            .with_fn("repeat", SdfNode::repeat)
+            .with_fn("array", SdfNode::array).with_fn("array", SdfNode::array_rotated)
+            .with_fn("warp", SdfNode::warp)
+            .with_fn("displace_map", SdfNode::displace_map)
+            .with_fn("uv_scale", SdfNode::uv_scale)
+            .with_fn("annular", SdfNode::annular)
+            .with_fn("onion_rings", SdfNode::onion_rings)
+            .with_fn("color", SdfNode::color).with_fn("color", SdfNode::color_array)
+            .with_fn("color_hsv", SdfNode::color_hsv)
+            .with_fn("inside_color", SdfNode::inside_color)
+            .with_fn("clearcoat", SdfNode::clearcoat)
+            .with_fn("volume", SdfNode::volume)
+            .with_fn("translate_anim", SdfNode::translate_anim)
+            .with_fn("rotate_x_anim", SdfNode::rotate_x_anim)
+            .with_fn("rotate_y_anim", SdfNode::rotate_y_anim)
+            .with_fn("rotate_z_anim", SdfNode::rotate_z_anim)
+            .with_fn("inflate_anim", SdfNode::inflate_anim);
     }
 }
 
 pub fn register_rhai_types(engine: &mut Engine) {
     engine.build_type::<SdfNode>();
+    engine.build_type::<TimeExpr>();
+    engine.build_type::<Scene>();
     engine.register_fn("sphere", SdfNode::new_sphere);
     engine.register_fn("box", SdfNode::new_box);
     engine.register_fn("cylinder", SdfNode::new_cylinder);
     engine.register_fn("torus", SdfNode::new_torus);
+    engine.register_fn("torus_axis", SdfNode::new_torus_axis);
+    engine.register_fn("wedge", SdfNode::new_wedge);
+    engine.register_fn("superellipsoid", SdfNode::new_superellipsoid);
+    engine.register_fn("mandelbulb", SdfNode::new_mandelbulb);
+    engine.register_fn("tetrahedron", SdfNode::new_tetrahedron);
+    engine.register_fn("capsule", SdfNode::new_capsule);
+    engine.register_fn("cut_hollow_sphere", SdfNode::new_cut_hollow_sphere);
+    engine.register_fn("helix", SdfNode::new_helix);
+    engine.register_fn("inf_cylinder", SdfNode::new_inf_cylinder);
+    engine.register_fn("inf_cylinder", SdfNode::new_inf_cylinder_axis);
+    engine.register_fn("inf_cone", SdfNode::new_inf_cone);
+    engine.register_fn("inf_cone", SdfNode::new_inf_cone_axis);
+    engine.register_fn("svg_profile", SdfNode::new_svg_profile);
+    engine.register_fn("svg_profile", SdfNode::new_svg_profile_scaled);
+    engine.register_fn("mesh", SdfNode::new_mesh);
+    engine.register_fn("mesh", SdfNode::new_mesh_resolution);
+    engine.register_fn("smooth_group", SdfNode::new_smooth_group);
+    engine.register_fn("sphere_colored", SdfNode::new_sphere_colored);
+    engine.register_fn("box_colored", SdfNode::new_box_colored);
+    engine.register_fn("cylinder_colored", SdfNode::new_cylinder_colored);
+    engine.register_fn("torus_colored", SdfNode::new_torus_colored);
+    engine.register_fn("wedge_colored", SdfNode::new_wedge_colored);
+    engine.register_fn("superellipsoid_colored", SdfNode::new_superellipsoid_colored);
+    engine.register_fn("mandelbulb_colored", SdfNode::new_mandelbulb_colored);
+    engine.register_fn("capsule_colored", SdfNode::new_capsule_colored);
+    engine.register_fn("tetrahedron_colored", SdfNode::new_tetrahedron_colored);
+    engine.register_fn("cut_hollow_sphere_colored", SdfNode::new_cut_hollow_sphere_colored);
+    engine.register_fn("helix_colored", SdfNode::new_helix_colored);
+    engine.register_fn("inf_cylinder_colored", SdfNode::new_inf_cylinder_colored);
+    engine.register_fn("inf_cone_colored", SdfNode::new_inf_cone_colored);
+    engine.register_fn("svg_profile_colored", SdfNode::new_svg_profile_colored);
+    engine.register_fn("mesh_colored", SdfNode::new_mesh_colored);
+    engine.register_fn("animated", TimeExpr::parse);
+    engine.register_fn("hsv", hsv);
 }
+
+/// Global functions registered by `register_rhai_types`, with placeholder arguments.
+/// Keep in sync: the editor uses these for highlighting and completion.
+pub const RHAI_CONSTRUCTORS: &[(&str, &str)] = &[
+    ("sphere", "1.0"),
+    ("box", "1.0, 1.0, 1.0"),
+    ("cylinder", "0.5, 1.0"),
+    ("torus", "1.0, 0.25"),
+    ("torus_axis", "1.0, 0.25, 0.0, 0.0, 1.0"),
+    ("wedge", "1.0, 0.5, 1.0"),
+    ("superellipsoid", "1.0, 1.0, 1.0, 0.3, 0.3"),
+    ("mandelbulb", "8.0, 8"),
+    ("tetrahedron", "1.0"),
+    ("capsule", "0.0, -0.5, 0.0, 0.0, 0.5, 0.0, 0.25"),
+    ("cut_hollow_sphere", "1.0, 0.5, 0.05"),
+    ("helix", "0.5, 0.2, 0.05, 5.0"),
+    ("inf_cylinder", "0.25"),
+    ("inf_cone", "30.0"),
+    ("svg_profile", "\"M 0 0 L 1 0 L 0 -1 Z\""),
+    ("mesh", "\"model.obj\", 64"),
+    ("smooth_group", "[a, b, c], 0.2"),
+    ("sphere_colored", "1.0, 1.0, 1.0, 1.0"),
+    ("box_colored", "1.0, 1.0, 1.0, 1.0, 1.0, 1.0"),
+    ("cylinder_colored", "0.5, 1.0, 1.0, 1.0, 1.0"),
+    ("torus_colored", "1.0, 0.25, 1.0, 1.0, 1.0"),
+    ("wedge_colored", "1.0, 0.5, 1.0, 1.0, 1.0, 1.0"),
+    ("superellipsoid_colored", "1.0, 1.0, 1.0, 0.3, 0.3, 1.0, 1.0, 1.0"),
+    ("mandelbulb_colored", "8.0, 8, 1.0, 1.0, 1.0"),
+    ("capsule_colored", "0.0, -0.5, 0.0, 0.0, 0.5, 0.0, 0.25, 1.0, 1.0, 1.0"),
+    ("tetrahedron_colored", "1.0, 1.0, 1.0, 1.0"),
+    ("cut_hollow_sphere_colored", "1.0, 0.5, 0.05, 1.0, 1.0, 1.0"),
+    ("helix_colored", "0.5, 0.2, 0.05, 5.0, 1.0, 1.0, 1.0"),
+    ("inf_cylinder_colored", "0.25, 1.0, 1.0, 1.0"),
+    ("inf_cone_colored", "30.0, 1.0, 1.0, 1.0"),
+    ("svg_profile_colored", "\"M 0 0 L 1 0 L 0 -1 Z\", 1.0, 1.0, 1.0"),
+    ("mesh_colored", "\"model.obj\", 1.0, 1.0, 1.0"),
+    ("animated", "\"sin\", 1.0, 0.5"),
+    ("hsv", "200.0, 0.8, 0.9"),
+];
+
+/// `SdfNode` methods registered in `CustomType::build`, with placeholder arguments.
+pub const RHAI_METHODS: &[(&str, &str)] = &[
+    ("union", "other"), ("add", "other"), ("smooth_union", "other, 0.2"), ("smooth_union_exp", "other, 0.1"),
+    ("subtract", "other"), ("sub", "other"), ("smooth_subtract", "other, 0.2"), ("smooth_subtract_exp", "other, 0.1"),
+    ("intersect", "other"), ("smooth_intersect", "other, 0.2"), ("smooth_intersect_exp", "other, 0.1"),
+    ("blend", "other, 0.2"), ("smooth", "0.2"), ("nearest", "other"), ("farthest", "other"),
+    ("groove", "cutter, 0.05, 0.02"), ("pipe", "other, 0.05"),
+    ("translate", "0.0, 0.0, 0.0"), ("move", "0.0, 0.0, 0.0"),
+    ("rotate_x", "90.0"), ("rotate_y", "90.0"), ("rotate_z", "90.0"), ("scale", "2.0"),
+    ("mirror_x", ""), ("mirror_y", ""), ("mirror_z", ""), ("mirror_plane", "1.0, 0.0, 0.0, 0.5"),
+    ("repeat", "2.0, 0.0, 2.0"), ("array", "4, 1.0, 0.0, 0.0"), ("warp", "0.2, 2.0"), ("displace_map", "\"height.png\", 0.1, 1.0"), ("uv_scale", "2.0, 2.0, 2.0"), ("annular", "0.05"), ("onion_rings", "4, 0.1"),
+    ("color", "1.0, 1.0, 1.0"), ("color_hsv", "200.0, 0.8, 0.9"), ("inside_color", "1.0, 0.3, 0.3"), ("clearcoat", "1.0, 0.1"), ("volume", "2.0, 0.6, 0.8, 1.0"),
+    ("translate_anim", "0.0, 1.0, 0.0, animated(\"sin\", 1.0, 0.5)"),
+    ("rotate_x_anim", "animated(\"linear\", 45.0, 1.0)"),
+    ("rotate_y_anim", "animated(\"linear\", 45.0, 1.0)"),
+    ("rotate_z_anim", "animated(\"linear\", 45.0, 1.0)"),
+    ("inflate_anim", "animated(\"sin\", 1.0, 0.1)"),
+    ("offset", "0.0"),
+];
@@ -0,0 +1,243 @@
+use glam::{Quat, Vec3};
+use crate::sdf_ast::{AnimChannel, SdfNode, SdfOp, SmoothKind, TimeExpr, Wave};
+
+/// Axis-aligned bounding box. Components may be infinite (e.g. on repeated axes).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Aabb {
+    pub min: Vec3,
+    pub max: Vec3,
+}
+
+impl Aabb {
+    pub const INFINITE: Aabb = Aabb { min: Vec3::NEG_INFINITY, max: Vec3::INFINITY };
+
+    pub fn new(min: Vec3, max: Vec3) -> Self { Self { min, max } }
+    pub fn centered(half_extents: Vec3) -> Self { Self { min: -half_extents, max: half_extents } }
+    pub fn cube(half_size: f32) -> Self { Self::centered(Vec3::splat(half_size)) }
+
+    pub fn union(&self, other: &Aabb) -> Aabb { Aabb::new(self.min.min(other.min), self.max.max(other.max)) }
+    pub fn intersect(&self, other: &Aabb) -> Aabb { Aabb::new(self.min.max(other.min), self.max.min(other.max)) }
+    pub fn expand(&self, amount: f32) -> Aabb { Aabb::new(self.min - Vec3::splat(amount), self.max + Vec3::splat(amount)) }
+    pub fn translate(&self, offset: Vec3) -> Aabb { Aabb::new(self.min + offset, self.max + offset) }
+    pub fn scale(&self, factor: f32) -> Aabb { Aabb::new(self.min * factor, self.max * factor) }
+
+    pub fn is_finite(&self) -> bool { self.min.is_finite() && self.max.is_finite() }
+    pub fn is_empty(&self) -> bool { self.min.cmpgt(self.max).any() }
+    pub fn size(&self) -> Vec3 { self.max - self.min }
+
+    pub fn corners(&self) -> [Vec3; 8] {
+        let (a, b) = (self.min, self.max);
+        [
+            Vec3::new(a.x, a.y, a.z), Vec3::new(b.x, a.y, a.z), Vec3::new(a.x, b.y, a.z), Vec3::new(b.x, b.y, a.z),
+            Vec3::new(a.x, a.y, b.z), Vec3::new(b.x, a.y, b.z), Vec3::new(a.x, b.y, b.z), Vec3::new(b.x, b.y, b.z),
+        ]
+    }
+
+    /// Bounds of the box after rotating it by `rotation` about the origin.
+    pub fn rotate(&self, rotation: Quat) -> Aabb {
+        if !self.is_finite() {
+            // Rotation mixes axes, so one infinite axis can end up anywhere
+            return Aabb::INFINITE;
+        }
+        let corners = self.corners().map(|c| rotation * c);
+        corners.iter().fold(Aabb::new(corners[0], corners[0]), |acc, c| acc.union(&Aabb::new(*c, *c)))
+    }
+
+    /// Bounds of the box reflected across the plane `dot(p, normal) = offset` (`normal` unit length).
+    pub fn reflect(&self, normal: Vec3, offset: f32) -> Aabb {
+        if !self.is_finite() {
+            return Aabb::INFINITE;
+        }
+        let corners = self.corners().map(|c| c - 2.0 * (c.dot(normal) - offset) * normal);
+        corners.iter().fold(Aabb::new(corners[0], corners[0]), |acc, c| acc.union(&Aabb::new(*c, *c)))
+    }
+
+    /// Bounds invariant to any rotation about the origin.
+    fn rotation_invariant(&self) -> Aabb {
+        let r = self.min.abs().max(self.max.abs()).length();
+        Aabb::cube(r)
+    }
+}
+
+impl TimeExpr {
+    /// Range of values the expression can take over all time.
+    pub fn range(&self) -> (f32, f32) {
+        let (lo, hi) = match self.wave {
+            Wave::Sin | Wave::Cos => (-self.amp.abs(), self.amp.abs()),
+            Wave::Saw => (self.amp.min(0.0), self.amp.max(0.0)),
+            Wave::Linear if self.amp == 0.0 || self.freq == 0.0 => (0.0, 0.0),
+            Wave::Linear => (f32::NEG_INFINITY, f32::INFINITY),
+        };
+        (self.base + lo, self.base + hi)
+    }
+}
+
+fn axis_vec(axis: &[f32; 3]) -> Vec3 {
+    if axis[0] > 0.9 { Vec3::X } else if axis[1] > 0.9 { Vec3::Y } else { Vec3::Z }
+}
+
+impl SdfNode {
+    /// Conservative world-space bounds of the solid. Repeated axes are unbounded, so callers
+    /// such as the mesh exporters must clamp to a user-supplied region.
+    pub fn bounds(&self) -> Aabb {
+        match &self.op {
+            SdfOp::Sphere { radius } => Aabb::cube(*radius),
+            SdfOp::Box { size } | SdfOp::Wedge { size } => Aabb::centered(Vec3::from(*size)),
+            SdfOp::Cylinder { radius, height } => Aabb::centered(Vec3::new(*radius, *height, *radius)),
+            SdfOp::Torus { major_radius, minor_radius, axis } => {
+                let r = major_radius + minor_radius;
+                let along = Vec3::from(*axis).abs();
+                Aabb::centered(Vec3::splat(r) * (1.0 - along) + *minor_radius * along)
+            }
+            SdfOp::SuperEllipsoid { radii, .. } => Aabb::centered(Vec3::from(*radii)),
+            // Every point outside the escape radius is outside the set
+            SdfOp::Mandelbulb { .. } => Aabb::cube(2.0),
+            SdfOp::Capsule { a, b, radius } => {
+                let (a, b) = (Vec3::from(*a), Vec3::from(*b));
+                Aabb::new(a.min(b), a.max(b)).expand(*radius)
+            }
+            SdfOp::Tetrahedron { size } => {
+                let r = size * 0.942_809; // base circumradius, sqrt(8) / 3
+                Aabb::new(Vec3::new(-r, -size / 3.0, -r), Vec3::new(r, *size, r))
+            }
+            SdfOp::CutHollowSphere { radius, cut_height, thickness } => {
+                let r = radius + thickness;
+                Aabb::new(Vec3::splat(-r), Vec3::new(r, cut_height + thickness, r))
+            }
+            SdfOp::Helix { radius, pitch, thickness, turns } => {
+                let r = radius + thickness;
+                Aabb::centered(Vec3::new(r, 0.5 * pitch * turns + thickness, r))
+            }
+            // Bounded only across the axis, and only when it is aligned with a coordinate axis
+            SdfOp::InfiniteCylinder { radius, axis } => {
+                let extent = Vec3::from(*axis).to_array().map(|a| if a.abs() > 1e-6 { f32::INFINITY } else { *radius });
+                Aabb::centered(Vec3::from(extent))
+            }
+            SdfOp::InfiniteCone { .. } => Aabb::INFINITE,
+            SdfOp::MeshVolume { volume } => Aabb::new(volume.origin, volume.max()),
+            SdfOp::SvgProfile { segments, .. } => {
+                let (mut min, mut max) = (Vec3::new(f32::INFINITY, f32::INFINITY, f32::NEG_INFINITY), Vec3::new(f32::NEG_INFINITY, f32::NEG_INFINITY, f32::INFINITY));
+                for s in segments {
+                    min = min.min(Vec3::new(s[0].min(s[2]), s[1].min(s[3]), f32::NEG_INFINITY));
+                    max = max.max(Vec3::new(s[0].max(s[2]), s[1].max(s[3]), f32::INFINITY));
+                }
+                Aabb::new(min, max)
+            }
+
+            // The polynomial smooth-min bulges by at most k/4
+            // The exponential one by at most k
+            SdfOp::Union { a, b, smooth, kind } => {
+                let bulge = match kind { SmoothKind::Poly => 0.25, SmoothKind::Exp => 1.0 };
+                a.bounds().union(&b.bounds()).expand(smooth * bulge)
+            }
+            // And over n operands by at most k * log2(n)
+            SdfOp::SmoothGroup { children, k } => children.iter()
+                .map(|c| c.bounds())
+                .reduce(|a, b| a.union(&b))
+                .unwrap_or(Aabb::INFINITE)
+                .expand(k * (children.len() as f32).log2()),
+            SdfOp::Subtract { a, .. } | SdfOp::Groove { target: a, .. } => a.bounds(),
+            SdfOp::Pipe { a, b, radius } => a.bounds().expand(*radius).intersect(&b.bounds().expand(*radius)),
+            SdfOp::Intersect { a, b, .. } | SdfOp::Farthest { a, b } => a.bounds().intersect(&b.bounds()),
+            SdfOp::Nearest { a, b } => a.bounds().union(&b.bounds()),
+
+            SdfOp::Translate { target, .. } | SdfOp::Rotate { target, .. } | SdfOp::Transform { target, .. } | SdfOp::Scale { target, .. }
+            | SdfOp::Mirror { target, .. } | SdfOp::MirrorPlane { target, .. } | SdfOp::Repeat { target, .. }
+            | SdfOp::ArrayLinear { target, .. } | SdfOp::Warp { target, .. } | SdfOp::Annular { target, .. }
+            | SdfOp::OnionRings { target, .. } | SdfOp::DisplaceMap { target, .. } | SdfOp::Color { target, .. }
+            | SdfOp::InsideColor { target, .. } | SdfOp::Clearcoat { target, .. } | SdfOp::Volume { target, .. }
+            | SdfOp::Animated { target, .. } | SdfOp::UvScale { target, .. } => self.wrap_bounds(target.bounds()),
+        }
+    }
+
+    /// Bounds of this single-target op (a transform, deformation or material) whose target has
+    /// bounds `b`.
+    fn wrap_bounds(&self, b: Aabb) -> Aabb {
+        match &self.op {
+            SdfOp::Translate { offset, .. } => b.translate(Vec3::from(*offset)),
+            SdfOp::Rotate { axis, angle_deg, .. } => b.rotate(Quat::from_axis_angle(axis_vec(axis), angle_deg.to_radians())),
+            SdfOp::Transform { rotation, offset, .. } => b.rotate(Quat::from_array(*rotation)).translate(Vec3::from(*offset)),
+            SdfOp::Scale { factor, .. } => b.scale(*factor),
+            SdfOp::Mirror { axis, .. } => {
+                let extent = b.min.abs().max(b.max.abs());
+                let mut out = b;
+                for i in 0..3 {
+                    if axis[i] > 0.9 {
+                        out.min[i] = -extent[i];
+                        out.max[i] = extent[i];
+                    }
+                }
+                out
+            }
+            // Whatever the target has on the kept side, plus its reflection
+            SdfOp::MirrorPlane { normal, offset, .. } => b.union(&b.reflect(Vec3::from(*normal), *offset)),
+            SdfOp::Repeat { spacing, .. } => {
+                let mut out = b;
+                for (i, s) in spacing.iter().enumerate() {
+                    if *s > 0.0 {
+                        out.min[i] = f32::NEG_INFINITY;
+                        out.max[i] = f32::INFINITY;
+                    }
+                }
+                out
+            }
+            SdfOp::ArrayLinear { count, offset, rotate_step_deg, .. } => {
+                (0..*count).fold(b, |acc, i| {
+                    let k = i as f32;
+                    let [rx, ry, rz] = rotate_step_deg.map(|deg| (deg * k).to_radians());
+                    let rotation = Quat::from_rotation_z(rz) * Quat::from_rotation_y(ry) * Quat::from_rotation_x(rx);
+                    let copy = if i == 0 { b } else { b.rotate(rotation) };
+                    acc.union(&copy.translate(Vec3::from(*offset) * k))
+                })
+            }
+            // The warp vector has length at most amplitude * sqrt(3)
+            SdfOp::Warp { amplitude, .. } => b.expand(amplitude * 3f32.sqrt()),
+            SdfOp::Annular { thickness, .. } => b.expand(*thickness),
+            SdfOp::OnionRings { spacing, .. } => b.expand(0.25 * spacing),
+            SdfOp::DisplaceMap { amplitude, .. } => b.expand(amplitude * 0.5),
+
+            SdfOp::Animated { channel, expr, .. } => {
+                let (lo, hi) = expr.range();
+                if !(lo.is_finite() && hi.is_finite()) {
+                    return Aabb::INFINITE;
+                }
+                match channel {
+                    AnimChannel::Translate { dir } => {
+                        let dir = Vec3::from(*dir);
+                        let (a, c) = (dir * lo, dir * hi);
+                        Aabb::new(b.min + a.min(c), b.max + a.max(c))
+                    }
+                    AnimChannel::Rotate { .. } => b.rotation_invariant(),
+                    AnimChannel::Inflate => b.expand(hi.max(0.0)),
+                }
+            }
+
+            // Materials leave the extent alone; a volume holds no surface, but its extent is
+            // where the shader integrates it
+            _ => b,
+        }
+    }
+
+    /// World-space bounds of each primitive in script order: its own box carried through the
+    /// transforms above it, ignoring the booleans that combine it with the others. For the
+    /// "Primitive bounds" overlay, which shows where each part of an assembly sits.
+    pub fn primitive_bounds(&self) -> Vec<Aabb> {
+        match &self.op {
+            SdfOp::Union { .. } | SdfOp::Subtract { .. } | SdfOp::Intersect { .. } | SdfOp::Pipe { .. }
+            | SdfOp::Nearest { .. } | SdfOp::Farthest { .. } | SdfOp::Groove { .. } | SdfOp::SmoothGroup { .. } => {
+                self.children().into_iter().flat_map(|c| c.primitive_bounds()).collect()
+            }
+            _ => match self.children().first() {
+                Some(target) => target.primitive_bounds().into_iter().map(|b| self.wrap_bounds(b)).collect(),
+                None => vec![self.bounds()],
+            },
+        }
+    }
+
+    /// Region the exporters should sample: the scene bounds (padded so the surface is not
+    /// clipped) clamped to the user's `[-bounds, bounds]^3` cube.
+    pub fn export_region(&self, bounds: f32) -> Aabb {
+        let padding = bounds * 0.02;
+        self.bounds().expand(padding).intersect(&Aabb::cube(bounds))
+    }
+}
@@ -0,0 +1,72 @@
+use glam::Vec3;
+use crate::sdf_ast::{SdfNode, SdfOp};
+use crate::sdf_bounds::Aabb;
+use crate::sdf_export::XorShift;
+
+/// Surface points sampled per check; enough to catch a coincident face covering a few percent
+/// of a cut without slowing down compiles noticeably.
+const SAMPLES: usize = 256;
+
+impl SdfNode {
+    /// Heuristic scan for results the raymarcher renders badly: subtractions whose cutter shares
+    /// a face with the solid (zero-thickness films and shimmering where the two surfaces tie) and
+    /// walls thinner than the hit epsilon can resolve. Each warning suggests an offset that fixes
+    /// it. Sampled on the CPU at `t = 0`, so it can miss small regions. The wall check probes
+    /// along the gradient and is skipped for scenes with non-exact distances, where that is
+    /// unreliable.
+    pub fn thin_result_warnings(&self) -> Vec<String> {
+        let region = self.bounds().intersect(&Aabb::cube(10.0));
+        if region.is_empty() {
+            return Vec::new();
+        }
+        let eps = (region.size().length() * 5e-4).max(1e-4);
+        let mut warnings = Vec::new();
+        self.collect_coincident_cuts(self, &mut Vec::new(), eps, &mut warnings);
+
+        if self.step_factor() < 1.0 {
+            return warnings;
+        }
+        let mut rng = XorShift(0x2545_F491);
+        let surface: Vec<Vec3> = (0..SAMPLES).filter_map(|_| self.project_to_surface(rng.next_in_box(&region))).collect();
+        let thin: Vec<&Vec3> = surface.iter().filter(|q| {
+            // A solid at least 4 eps thick is still inside 4 eps behind its surface. Having come
+            // out through a face turned back towards us rules out probes that merely cut across
+            // a sharp edge.
+            let n = self.gradient(**q);
+            let behind = **q - n * 4.0 * eps;
+            n != Vec3::ZERO && self.distance(behind) > 0.0 && self.gradient(behind).dot(n) < -0.7
+        }).collect();
+        if thin.len() * 100 > surface.len() {
+            let q = thin[0];
+            warnings.push(format!(
+                "Wall thinner than {:.4} near ({:.2}, {:.2}, {:.2}); it may flicker or show holes. Shrink the cutter or grow the solid by about {:.3}.",
+                4.0 * eps, q.x, q.y, q.z, 10.0 * eps,
+            ));
+        }
+        warnings
+    }
+
+    /// Checks every hard `subtract` under `self`, which sits at `path` below `root`.
+    fn collect_coincident_cuts(&self, root: &SdfNode, path: &mut Vec<usize>, eps: f32, warnings: &mut Vec<String>) {
+        if let SdfOp::Subtract { a, b, smooth, .. } = &self.op {
+            let region = a.bounds().intersect(&b.bounds().expand(eps)).intersect(&Aabb::cube(10.0));
+            if *smooth == 0.0 && !region.is_empty() {
+                let mut rng = XorShift(0x9E37_79B9);
+                let on_a: Vec<Vec3> = (0..SAMPLES).filter_map(|_| a.project_to_surface(rng.next_in_box(&region))).collect();
+                let shared: Vec<&Vec3> = on_a.iter().filter(|q| b.distance(**q).abs() < eps).collect();
+                if shared.len() * 50 > on_a.len() {
+                    let q = root.to_world(path, *shared[0], true);
+                    warnings.push(format!(
+                        "A subtract's cutter shares a face with the solid near ({:.2}, {:.2}, {:.2}), leaving zero-thickness or flickering faces. Make the cutter about {:.3} larger so it passes through.",
+                        q.x, q.y, q.z, 10.0 * eps,
+                    ));
+                }
+            }
+        }
+        for (i, child) in self.children().into_iter().enumerate() {
+            path.push(i);
+            child.collect_coincident_cuts(root, path, eps, warnings);
+            path.pop();
+        }
+    }
+}
@@ -0,0 +1,640 @@
+use glam::{IVec3, Quat, Vec2, Vec3};
+use crate::height_map::HeightMap;
+use crate::sdf_ast::{AnimChannel, SdfNode, SdfOp, SmoothKind, TimeExpr, Wave};
+
+/// Color of primitives that are not wrapped in `SdfOp::Color`; must match `WgslGenerator`.
+pub const DEFAULT_COLOR: Vec3 = Vec3::new(0.2, 0.55, 1.0);
+
+/// Distance of space without surfaces, which is what an `SdfOp::Volume` is to everything but
+/// the shader's volume pass; must match `WgslGenerator`.
+pub const EMPTY_DIST: f32 = 1e10;
+
+/// CPU counterpart of the WGSL `SdfResult` struct.
+#[derive(Clone, Copy, Debug)]
+pub struct SdfResult {
+    pub dist: f32,
+    pub color: Vec3,
+    pub inside: Vec3,
+}
+
+impl SdfResult {
+    pub fn new(dist: f32, color: Vec3) -> Self { Self { dist, color, inside: color } }
+}
+
+// --- SDF Primitives (mirror shader_template.wgsl) ---
+
+fn sd_sphere(p: Vec3, s: f32) -> f32 {
+    p.length() - s
+}
+
+fn sd_box(p: Vec3, b: Vec3) -> f32 {
+    let q = p.abs() - b;
+    q.max(Vec3::ZERO).length() + q.max_element().min(0.0)
+}
+
+fn sd_cylinder(p: Vec3, r: f32, h: f32) -> f32 {
+    let d = Vec2::new(Vec2::new(p.x, p.z).length(), p.y).abs() - Vec2::new(r, h);
+    d.x.max(d.y).min(0.0) + d.max(Vec2::ZERO).length()
+}
+
+fn sd_torus(p: Vec3, t: Vec2) -> f32 {
+    let q = Vec2::new(Vec2::new(p.x, p.z).length() - t.x, p.y);
+    q.length() - t.y
+}
+
+/// Moves `axis` (a coordinate axis) onto Y, matching the swizzle `WgslGenerator` emits.
+fn torus_swizzle(p: Vec3, axis: &[f32; 3]) -> Vec3 {
+    if axis[0] > 0.5 { Vec3::new(p.y, p.x, p.z) } else if axis[2] > 0.5 { Vec3::new(p.x, p.z, p.y) } else { p }
+}
+
+fn sd_cut_hollow_sphere(p: Vec3, r: f32, h: f32, t: f32) -> f32 {
+    let w = (r * r - h * h).sqrt();
+    let q = Vec2::new(Vec2::new(p.x, p.z).length(), p.y);
+    (if h * q.x < w * q.y { (q - Vec2::new(w, h)).length() } else { (q.length() - r).abs() }) - t
+}
+
+fn sd_wedge(p: Vec3, b: Vec3) -> f32 {
+    let slope = (p.y * b.x - p.x * b.y) / Vec2::new(b.x, b.y).length();
+    sd_box(p, b).max(slope)
+}
+
+fn sd_superellipsoid(p: Vec3, r: Vec3, e1: f32, e2: f32) -> f32 {
+    let q = (p / r).abs().max(Vec3::splat(1e-6));
+    let f = (q.x.powf(2.0 / e2) + q.z.powf(2.0 / e2)).powf(e2 / e1) + q.y.powf(2.0 / e1);
+    (f.powf(e1 * 0.5) - 1.0) * r.min_element()
+}
+
+fn sd_capsule(p: Vec3, a: Vec3, b: Vec3, r: f32) -> f32 {
+    let (pa, ba) = (p - a, b - a);
+    let h = (pa.dot(ba) / ba.dot(ba).max(1e-12)).clamp(0.0, 1.0);
+    (pa - ba * h).length() - r
+}
+
+fn ud_triangle(p: Vec3, a: Vec3, b: Vec3, c: Vec3) -> f32 {
+    let (ba, pa) = (b - a, p - a);
+    let (cb, pb) = (c - b, p - b);
+    let (ac, pc) = (a - c, p - c);
+    let nor = ba.cross(ac);
+    let outside_prism = ba.cross(nor).dot(pa).signum() + cb.cross(nor).dot(pb).signum() + ac.cross(nor).dot(pc).signum() < 2.0;
+    let d2 = if outside_prism {
+        let edge = |e: Vec3, q: Vec3| (e * (e.dot(q) / e.dot(e)).clamp(0.0, 1.0) - q).length_squared();
+        edge(ba, pa).min(edge(cb, pb)).min(edge(ac, pc))
+    } else {
+        nor.dot(pa) * nor.dot(pa) / nor.length_squared()
+    };
+    d2.sqrt()
+}
+
+fn sd_tetrahedron(p: Vec3, s: f32) -> f32 {
+    let rb = s * 0.942_809;
+    let v0 = Vec3::new(0.0, s, 0.0);
+    let v1 = Vec3::new(0.0, -s / 3.0, rb);
+    let v2 = Vec3::new(rb * 0.866_025_4, -s / 3.0, -rb * 0.5);
+    let v3 = Vec3::new(-rb * 0.866_025_4, -s / 3.0, -rb * 0.5);
+    let d = ud_triangle(p, v0, v1, v2).min(ud_triangle(p, v0, v2, v3))
+        .min(ud_triangle(p, v0, v3, v1)).min(ud_triangle(p, v1, v3, v2));
+    let planes = p.dot(-v0).max(p.dot(-v1)).max(p.dot(-v2)).max(p.dot(-v3)) / s - s / 3.0;
+    if planes > 0.0 { d } else { -d }
+}
+
+fn sd_helix(p: Vec3, radius: f32, pitch: f32, thickness: f32, turns: f32) -> f32 {
+    use std::f32::consts::TAU;
+    let half_h = 0.5 * pitch * turns;
+    let a = (p.z.atan2(p.x) / TAU + 1.0).fract();
+    let k_max = (turns - a).floor();
+    let k = ((p.y + half_h) / pitch - a).round().clamp(0.0, k_max.max(0.0));
+    let dy = p.y - (pitch * (a + k) - half_h);
+    let cos_slope = TAU * radius / Vec2::new(TAU * radius, pitch).length();
+    let body = if k_max >= 0.0 { Vec2::new(Vec2::new(p.x, p.z).length() - radius, dy * cos_slope).length() } else { 1e10 };
+    let end_angle = TAU * turns;
+    let start = Vec3::new(radius, -half_h, 0.0);
+    let end = Vec3::new(radius * end_angle.cos(), half_h, radius * end_angle.sin());
+    body.min((p - start).length()).min((p - end).length()) - thickness
+}
+
+fn sd_inf_cylinder(p: Vec3, r: f32, axis: Vec3) -> f32 {
+    (p - axis * p.dot(axis)).length() - r
+}
+
+fn sd_inf_cone(p: Vec3, angle_deg: f32, axis: Vec3) -> f32 {
+    let h = p.dot(axis);
+    let q = Vec2::new((p - axis * h).length(), h);
+    let c = Vec2::from(angle_deg.to_radians().sin_cos());
+    let d = (q - c * q.dot(c).max(0.0)).length();
+    if q.x * c.y - q.y * c.x < 0.0 { -d } else { d }
+}
+
+/// Even-odd signed distance to a set of closed polygon edges `[ax, ay, bx, by]`.
+fn sd_polygon(p: Vec2, segments: &[[f32; 4]]) -> f32 {
+    let mut d = f32::MAX;
+    let mut s = 1.0;
+    for seg in segments {
+        let (a, b) = (Vec2::new(seg[0], seg[1]), Vec2::new(seg[2], seg[3]));
+        let (e, w) = (b - a, p - a);
+        let proj = w - e * (w.dot(e) / e.dot(e)).clamp(0.0, 1.0);
+        d = d.min(proj.dot(proj));
+        // Crossing test for a horizontal ray from p
+        if (a.y <= p.y) != (b.y <= p.y) && (e.x * w.y > e.y * w.x) == (b.y > a.y) {
+            s = -s;
+        }
+    }
+    s * d.sqrt()
+}
+
+fn sd_mandelbulb(p: Vec3, power: f32, iterations: u32) -> f32 {
+    let mut z = p;
+    let mut dr = 1.0;
+    for _ in 0..iterations {
+        let r = z.length().max(1e-6);
+        if r > 2.0 {
+            break;
+        }
+        let theta = (z.y / r).clamp(-1.0, 1.0).acos() * power;
+        let phi = z.z.atan2(z.x) * power;
+        dr = r.powf(power - 1.0) * power * dr + 1.0;
+        z = r.powf(power) * Vec3::new(theta.sin() * phi.cos(), theta.cos(), theta.sin() * phi.sin()) + p;
+    }
+    let r = z.length().max(1e-6);
+    0.5 * r.ln() * r / dr
+}
+
+// --- Result & Material Helpers ---
+
+fn op_union(a: SdfResult, b: SdfResult) -> SdfResult {
+    if a.dist < b.dist { a } else { b }
+}
+
+/// Smooth minimum of `a` and `b`, with the weight of `a` for blending material attributes.
+/// The smooth maximum used by subtract/intersect is `-smooth_min(-a, -b)`.
+fn smooth_min(a: f32, b: f32, k: f32, kind: SmoothKind) -> (f32, f32) {
+    match kind {
+        SmoothKind::Poly => {
+            let h = (0.5 + 0.5 * (b - a) / k).clamp(0.0, 1.0);
+            (b + (a - b) * h - k * h * (1.0 - h), h)
+        }
+        SmoothKind::Exp => {
+            // Log-sum-exp, shifted by the minimum so it cannot overflow
+            let m = a.min(b);
+            let (ea, eb) = (((m - a) / k).exp2(), ((m - b) / k).exp2());
+            (m - k * (ea + eb).log2(), ea / (ea + eb))
+        }
+    }
+}
+
+fn op_union_smooth(a: SdfResult, b: SdfResult, k: f32, kind: SmoothKind) -> SdfResult {
+    let (d, h) = smooth_min(a.dist, b.dist, k, kind);
+    SdfResult { dist: d, color: b.color.lerp(a.color, h), inside: b.inside.lerp(a.inside, h) }
+}
+
+/// Exponential smooth minimum over any number of operands, each weighted by its share of the
+/// sum; symmetric, so the order of `results` does not matter. `k <= 0` is a hard union.
+fn op_smooth_group(results: &[SdfResult], k: f32) -> SdfResult {
+    let nearest = results.iter().copied().reduce(op_union).expect("smooth_group has children");
+    if k <= 0.0 {
+        return nearest;
+    }
+    let m = nearest.dist;
+    let weights: Vec<f32> = results.iter().map(|r| ((m - r.dist) / k).exp2()).collect();
+    let sum: f32 = weights.iter().sum();
+    let blend = |attr: fn(&SdfResult) -> Vec3| results.iter().zip(&weights).map(|(r, w)| attr(r) * *w).sum::<Vec3>() / sum;
+    SdfResult { dist: m - k * sum.log2(), color: blend(|r| r.color), inside: blend(|r| r.inside) }
+}
+
+fn op_subtract(a: SdfResult, b: SdfResult) -> SdfResult {
+    if -b.dist > a.dist { SdfResult::new(-b.dist, a.inside) } else { a }
+}
+
+fn op_subtract_smooth(a: SdfResult, b: SdfResult, k: f32, kind: SmoothKind) -> SdfResult {
+    let (d, h) = smooth_min(-a.dist, b.dist, k, kind);
+    // `h` weighs the kept shape; the rest of the blend is the cut, shown in the inside color
+    SdfResult { dist: -d, color: a.inside.lerp(a.color, h), inside: a.inside }
+}
+
+fn op_intersect(a: SdfResult, b: SdfResult) -> SdfResult {
+    if a.dist > b.dist { a } else { b }
+}
+
+fn op_intersect_smooth(a: SdfResult, b: SdfResult, k: f32, kind: SmoothKind) -> SdfResult {
+    let (d, h) = smooth_min(-a.dist, -b.dist, k, kind);
+    SdfResult { dist: -d, color: b.color.lerp(a.color, h), inside: b.inside.lerp(a.inside, h) }
+}
+
+/// `max(d, min(d + depth, width - |cutter|))`: the second term is the channel, a slab of
+/// half-width `width` around the cutter's surface, floored `depth` below the target's surface.
+fn op_groove(res: SdfResult, cutter: f32, depth: f32, width: f32) -> SdfResult {
+    let channel = (res.dist + depth).min(width - cutter.abs());
+    if channel > res.dist { SdfResult::new(channel, res.inside) } else { res }
+}
+
+fn op_annular(res: SdfResult, thickness: f32) -> SdfResult {
+    SdfResult { dist: res.dist.abs() - thickness, ..res }
+}
+
+fn op_onion_rings(res: SdfResult, count: u32, spacing: f32) -> SdfResult {
+    let k = (-res.dist / spacing).round().clamp(0.0, (count - 1) as f32);
+    SdfResult { dist: (res.dist + k * spacing).abs() - 0.25 * spacing, ..res }
+}
+
+// --- Noise ---
+
+fn hash3(c: IVec3) -> f32 {
+    let (x, y, z) = (c.x as u32, c.y as u32, c.z as u32);
+    let mut h = x.wrapping_mul(73856093) ^ y.wrapping_mul(19349663) ^ z.wrapping_mul(83492791);
+    h = h.wrapping_mul(747796405).wrapping_add(2891336453);
+    let mut w = ((h >> ((h >> 28) + 4)) ^ h).wrapping_mul(277803737);
+    w ^= w >> 22;
+    w as f32 / 4294967295.0 * 2.0 - 1.0
+}
+
+fn value_noise(p: Vec3) -> f32 {
+    let i = p.floor().as_ivec3();
+    let f = p - p.floor();
+    let u = f * f * (Vec3::splat(3.0) - 2.0 * f);
+    let lerp = |a: f32, b: f32, t: f32| a + (b - a) * t;
+    let x00 = lerp(hash3(i), hash3(i + IVec3::new(1, 0, 0)), u.x);
+    let x10 = lerp(hash3(i + IVec3::new(0, 1, 0)), hash3(i + IVec3::new(1, 1, 0)), u.x);
+    let x01 = lerp(hash3(i + IVec3::new(0, 0, 1)), hash3(i + IVec3::new(1, 0, 1)), u.x);
+    let x11 = lerp(hash3(i + IVec3::new(0, 1, 1)), hash3(i + IVec3::new(1, 1, 1)), u.x);
+    lerp(lerp(x00, x10, u.y), lerp(x01, x11, u.y), u.z)
+}
+
+/// Height map sampled on the three axis planes through `p`, blended by the surface normal `n`
+/// so each projection fades out where it would stretch.
+fn triplanar(map: &HeightMap, p: Vec3, n: Vec3) -> f32 {
+    let w = n.abs().powf(4.0);
+    let w = w / (w.x + w.y + w.z).max(1e-6);
+    map.sample(Vec2::new(p.y, p.z)) * w.x + map.sample(Vec2::new(p.x, p.z)) * w.y + map.sample(Vec2::new(p.x, p.y)) * w.z
+}
+
+// --- Transforms ---
+
+fn op_warp(p: Vec3, amp: f32, freq: f32) -> Vec3 {
+    let q = p * freq;
+    let w = Vec3::new(
+        value_noise(q),
+        value_noise(q + Vec3::new(31.7, 11.3, 47.1)),
+        value_noise(q + Vec3::new(-23.9, 57.5, 5.3)),
+    );
+    p + amp * w
+}
+
+fn op_mirror_plane(p: Vec3, n: Vec3, d: f32) -> Vec3 {
+    p - 2.0 * (p.dot(n) - d).min(0.0) * n
+}
+
+impl TimeExpr {
+    /// Value at `t` seconds, matching the expression `WgslGenerator` bakes into the shader.
+    pub fn value(&self, t: f32) -> f32 {
+        let phase = self.freq * t;
+        let wave = match self.wave {
+            Wave::Sin => (std::f32::consts::TAU * phase).sin(),
+            Wave::Cos => (std::f32::consts::TAU * phase).cos(),
+            Wave::Saw => phase.fract(),
+            Wave::Linear => phase,
+        };
+        self.base + self.amp * wave
+    }
+}
+
+fn rotate_x(p: Vec3, angle: f32) -> Vec3 {
+    let (s, c) = angle.sin_cos();
+    Vec3::new(p.x, c * p.y - s * p.z, s * p.y + c * p.z)
+}
+
+fn rotate_y(p: Vec3, angle: f32) -> Vec3 {
+    let (s, c) = angle.sin_cos();
+    Vec3::new(c * p.x + s * p.z, p.y, -s * p.x + c * p.z)
+}
+
+fn rotate_z(p: Vec3, angle: f32) -> Vec3 {
+    let (s, c) = angle.sin_cos();
+    Vec3::new(c * p.x - s * p.y, s * p.x + c * p.y, p.z)
+}
+
+fn rotate_axis(p: Vec3, axis: &[f32; 3], angle: f32) -> Vec3 {
+    if axis[0] > 0.9 { rotate_x(p, angle) } else if axis[1] > 0.9 { rotate_y(p, angle) } else { rotate_z(p, angle) }
+}
+
+/// One sample of `SdfNode::march_trace`: the ray distance and the field there (negated when
+/// marching from inside). A retreat sample overshot the surface or the previous step's sphere
+/// and is discarded, falling back to plain sphere tracing.
+#[derive(Clone, Copy, Debug)]
+pub struct MarchStep {
+    pub t: f32,
+    pub dist: f32,
+    pub retreat: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MarchEnd {
+    Hit,
+    /// Went past the far distance.
+    Miss,
+    /// Used up the shader's 128 steps, which it draws as a miss.
+    StepLimit,
+}
+
+#[derive(Clone, Debug)]
+pub struct MarchTrace {
+    pub steps: Vec<MarchStep>,
+    pub end: MarchEnd,
+}
+
+/// `p` in the frame of copy `i` of an `ArrayLinear`, as the generated `array_*` loop computes it.
+fn array_copy_point(p: Vec3, i: u32, offset: &[f32; 3], rotate_step_deg: &[f32; 3]) -> Vec3 {
+    let k = i as f32;
+    let [rx, ry, rz] = rotate_step_deg.map(|deg| -(deg * k).to_radians());
+    rotate_x(rotate_y(rotate_z(p - Vec3::from(*offset) * k, rz), ry), rx)
+}
+
+impl SdfNode {
+    /// Evaluates the field at `p`, following the same math as the generated `map` function.
+    /// Animated nodes are evaluated at `t = 0`.
+    pub fn evaluate(&self, p: Vec3) -> SdfResult {
+        self.evaluate_uv(p, Vec3::ONE)
+    }
+
+    /// `evaluate` with `uv`, the product of the `UvScale` nodes above `self`, applied to the
+    /// texture coordinates of any `DisplaceMap` below.
+    fn evaluate_uv(&self, p: Vec3, uv: Vec3) -> SdfResult {
+        match &self.op {
+            SdfOp::Sphere { radius } => SdfResult::new(sd_sphere(p, *radius), DEFAULT_COLOR),
+            SdfOp::Box { size } => SdfResult::new(sd_box(p, Vec3::from(*size)), DEFAULT_COLOR),
+            SdfOp::Cylinder { radius, height } => SdfResult::new(sd_cylinder(p, *radius, *height), DEFAULT_COLOR),
+            SdfOp::Torus { major_radius, minor_radius, axis } => {
+                SdfResult::new(sd_torus(torus_swizzle(p, axis), Vec2::new(*major_radius, *minor_radius)), DEFAULT_COLOR)
+            }
+            SdfOp::Wedge { size } => SdfResult::new(sd_wedge(p, Vec3::from(*size)), DEFAULT_COLOR),
+            SdfOp::SuperEllipsoid { radii, e1, e2 } => SdfResult::new(sd_superellipsoid(p, Vec3::from(*radii), *e1, *e2), DEFAULT_COLOR),
+            SdfOp::Mandelbulb { power, iterations } => SdfResult::new(sd_mandelbulb(p, *power, *iterations), DEFAULT_COLOR),
+            SdfOp::Capsule { a, b, radius } => SdfResult::new(sd_capsule(p, Vec3::from(*a), Vec3::from(*b), *radius), DEFAULT_COLOR),
+            SdfOp::Tetrahedron { size } => SdfResult::new(sd_tetrahedron(p, *size), DEFAULT_COLOR),
+            SdfOp::CutHollowSphere { radius, cut_height, thickness } => SdfResult::new(sd_cut_hollow_sphere(p, *radius, *cut_height, *thickness), DEFAULT_COLOR),
+            SdfOp::Helix { radius, pitch, thickness, turns } => SdfResult::new(sd_helix(p, *radius, *pitch, *thickness, *turns), DEFAULT_COLOR),
+            SdfOp::SvgProfile { segments, .. } => SdfResult::new(sd_polygon(Vec2::new(p.x, p.y), segments), DEFAULT_COLOR),
+            SdfOp::InfiniteCylinder { radius, axis } => SdfResult::new(sd_inf_cylinder(p, *radius, Vec3::from(*axis)), DEFAULT_COLOR),
+            SdfOp::InfiniteCone { angle_deg, axis } => SdfResult::new(sd_inf_cone(p, *angle_deg, Vec3::from(*axis)), DEFAULT_COLOR),
+            SdfOp::MeshVolume { volume } => SdfResult::new(volume.sample(p), DEFAULT_COLOR),
+
+            SdfOp::Union { a, b, smooth, kind } => {
+                let (res1, res2) = (a.evaluate_uv(p, uv), b.evaluate_uv(p, uv));
+                if *smooth > 0.0 { op_union_smooth(res1, res2, *smooth, *kind) } else { op_union(res1, res2) }
+            }
+            SdfOp::Subtract { a, b, smooth, kind } => {
+                let (res1, res2) = (a.evaluate_uv(p, uv), b.evaluate_uv(p, uv));
+                if *smooth > 0.0 { op_subtract_smooth(res1, res2, *smooth, *kind) } else { op_subtract(res1, res2) }
+            }
+            SdfOp::Intersect { a, b, smooth, kind } => {
+                let (res1, res2) = (a.evaluate_uv(p, uv), b.evaluate_uv(p, uv));
+                if *smooth > 0.0 { op_intersect_smooth(res1, res2, *smooth, *kind) } else { op_intersect(res1, res2) }
+            }
+
+            SdfOp::Nearest { a, b } => op_union(a.evaluate_uv(p, uv), b.evaluate_uv(p, uv)),
+            SdfOp::Farthest { a, b } => op_intersect(a.evaluate_uv(p, uv), b.evaluate_uv(p, uv)),
+            SdfOp::SmoothGroup { children, k } => {
+                let results: Vec<SdfResult> = children.iter().map(|c| c.evaluate_uv(p, uv)).collect();
+                op_smooth_group(&results, *k)
+            }
+
+            SdfOp::Groove { target, cutter, depth, width } => op_groove(target.evaluate_uv(p, uv), cutter.evaluate_uv(p, uv).dist, *depth, *width),
+            SdfOp::Pipe { a, b, radius } => {
+                let res = a.evaluate_uv(p, uv);
+                SdfResult { dist: Vec2::new(res.dist, b.evaluate_uv(p, uv).dist).length() - radius, ..res }
+            }
+
+            SdfOp::Translate { target, .. } | SdfOp::Rotate { target, .. } | SdfOp::Transform { target, .. } | SdfOp::Mirror { target, .. }
+            | SdfOp::MirrorPlane { target, .. } | SdfOp::Repeat { target, .. } | SdfOp::ArrayLinear { target, .. } | SdfOp::Warp { target, .. } => target.evaluate_uv(self.local_point(p), uv),
+            SdfOp::DisplaceMap { target, texture, amplitude, scale } => {
+                let res = target.evaluate_uv(p, uv);
+                let h = triplanar(texture, p * *scale * uv, target.blend_normal(p, uv));
+                SdfResult { dist: res.dist + amplitude * (h - 0.5), ..res }
+            }
+            SdfOp::UvScale { target, scale } => target.evaluate_uv(p, uv * Vec3::from(*scale)),
+            SdfOp::Scale { target, factor } => {
+                let res = target.evaluate_uv(self.local_point(p), uv);
+                SdfResult { dist: res.dist * factor, ..res }
+            }
+            SdfOp::Annular { target, thickness } => op_annular(target.evaluate_uv(p, uv), *thickness),
+            SdfOp::OnionRings { target, count, spacing } => op_onion_rings(target.evaluate_uv(p, uv), *count, *spacing),
+            SdfOp::Color { target, color } => SdfResult::new(target.evaluate_uv(p, uv).dist, Vec3::from(*color)),
+            SdfOp::InsideColor { target, color } => SdfResult { inside: Vec3::from(*color), ..target.evaluate_uv(p, uv) },
+            SdfOp::Clearcoat { target, .. } => target.evaluate_uv(p, uv),
+            SdfOp::Volume { .. } => SdfResult::new(EMPTY_DIST, DEFAULT_COLOR),
+
+            SdfOp::Animated { target, channel, expr } => {
+                let value = expr.value(0.0);
+                match channel {
+                    AnimChannel::Translate { .. } | AnimChannel::Rotate { .. } => target.evaluate_uv(self.local_point(p), uv),
+                    AnimChannel::Inflate => {
+                        let res = target.evaluate_uv(p, uv);
+                        SdfResult { dist: res.dist - value, ..res }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Point at which a single-child node samples its target: the inverse of its transform.
+    /// Nodes that do not move space return `p` unchanged.
+    fn local_point(&self, p: Vec3) -> Vec3 {
+        match &self.op {
+            SdfOp::Translate { offset, .. } => p - Vec3::from(*offset),
+            SdfOp::Rotate { axis, angle_deg, .. } => rotate_axis(p, axis, (-angle_deg).to_radians()),
+            SdfOp::Transform { rotation, offset, .. } => Quat::from_array(*rotation).inverse() * (p - Vec3::from(*offset)),
+            SdfOp::Scale { factor, .. } => p / *factor,
+            SdfOp::Mirror { axis, .. } => {
+                let mut new_p = p;
+                if axis[0] > 0.9 { new_p.x = new_p.x.abs(); }
+                if axis[1] > 0.9 { new_p.y = new_p.y.abs(); }
+                if axis[2] > 0.9 { new_p.z = new_p.z.abs(); }
+                new_p
+            }
+            SdfOp::MirrorPlane { normal, offset, .. } => op_mirror_plane(p, Vec3::from(*normal), *offset),
+            SdfOp::Repeat { spacing, .. } => {
+                let mut new_p = p;
+                for (i, s) in spacing.iter().enumerate() {
+                    if *s > 0.0 { new_p[i] -= s * (new_p[i] / s).round(); }
+                }
+                new_p
+            }
+            // The copy the union keeps: the one closest to `p`, first one on ties
+            SdfOp::ArrayLinear { target, count, offset, rotate_step_deg } => (0..*count)
+                .map(|i| array_copy_point(p, i, offset, rotate_step_deg))
+                .map(|q| (target.distance(q), q))
+                .fold((f32::INFINITY, p), |best, copy| if copy.0 < best.0 { copy } else { best })
+                .1,
+            SdfOp::Warp { amplitude, frequency, .. } => op_warp(p, *amplitude, *frequency),
+            SdfOp::Animated { channel, expr, .. } => {
+                let value = expr.value(0.0);
+                match channel {
+                    AnimChannel::Translate { dir } => p - Vec3::from(*dir) * value,
+                    AnimChannel::Rotate { axis } => rotate_axis(p, axis, (-value).to_radians()),
+                    AnimChannel::Inflate => p,
+                }
+            }
+            _ => p,
+        }
+    }
+
+    /// Inverse of `local_point` for the transforms that can be undone (translate, rotate and
+    /// scale, static or animated at `t = 0`); folds such as mirror and repeat map back to the primary
+    /// instance. Directions (`is_point == false`) are only rotated.
+    fn forward(&self, v: Vec3, is_point: bool) -> Vec3 {
+        let shift = |offset: Vec3| if is_point { v + offset } else { v };
+        match &self.op {
+            SdfOp::Translate { offset, .. } => shift(Vec3::from(*offset)),
+            SdfOp::Rotate { axis, angle_deg, .. } => rotate_axis(v, axis, angle_deg.to_radians()),
+            SdfOp::Transform { rotation, offset, .. } => {
+                let v = Quat::from_array(*rotation) * v;
+                if is_point { v + Vec3::from(*offset) } else { v }
+            }
+            SdfOp::Scale { factor, .. } => if is_point { v * *factor } else { v },
+            SdfOp::Animated { channel, expr, .. } => {
+                let value = expr.value(0.0);
+                match channel {
+                    AnimChannel::Translate { dir } => shift(Vec3::from(*dir) * value),
+                    AnimChannel::Rotate { axis } => rotate_axis(v, axis, value.to_radians()),
+                    AnimChannel::Inflate => v,
+                }
+            }
+            _ => v,
+        }
+    }
+
+    /// Maps `v`, given in the input space of the node at `path`, to world space by applying
+    /// the forward transforms of its ancestors.
+    pub fn to_world(&self, path: &[usize], v: Vec3, is_point: bool) -> Vec3 {
+        (0..path.len()).rev().fold(v, |v, k| self.node_at(&path[..k]).map_or(v, |node| node.forward(v, is_point)))
+    }
+
+    /// Child-index path from `self` to the primitive whose surface is closest to `p`, following
+    /// the same choices the boolean ops make in `evaluate`.
+    pub fn pick_path(&self, p: Vec3) -> Vec<usize> {
+        let (index, child_p) = match &self.op {
+            SdfOp::Union { a, b, .. } | SdfOp::Nearest { a, b } => (usize::from(b.distance(p) < a.distance(p)), p),
+            SdfOp::Intersect { a, b, .. } | SdfOp::Farthest { a, b } => (usize::from(b.distance(p) > a.distance(p)), p),
+            SdfOp::Subtract { a, b, .. } => (usize::from(-b.distance(p) > a.distance(p)), p),
+            SdfOp::Groove { target, cutter, width, .. } => (usize::from(width - cutter.distance(p).abs() > target.distance(p)), p),
+            SdfOp::Pipe { a, b, .. } => (usize::from(b.distance(p).abs() < a.distance(p).abs()), p),
+            SdfOp::SmoothGroup { children, .. } => {
+                let distances: Vec<f32> = children.iter().map(|c| c.distance(p)).collect();
+                ((0..distances.len()).fold(0, |best, i| if distances[i] < distances[best] { i } else { best }), p)
+            }
+            _ => (0, self.local_point(p)),
+        };
+        match self.children().get(index) {
+            Some(child) => {
+                let mut path = vec![index];
+                path.extend(child.pick_path(child_p));
+                path
+            }
+            None => Vec::new(),
+        }
+    }
+
+    /// Sphere-traces a ray with the same step count and epsilon as `ray_march` in the shader.
+    /// Returns the hit distance along the normalized `rd`.
+    pub fn raycast(&self, ro: Vec3, rd: Vec3, near: f32, far: f32) -> Option<f32> {
+        let step_factor = self.step_factor();
+        let mut t = near;
+        for _ in 0..128 {
+            let d = self.distance(ro + rd * t);
+            if d < 0.0005 {
+                return Some(t);
+            }
+            t += d * step_factor;
+            if t > far {
+                break;
+            }
+        }
+        None
+    }
+
+    /// Height along `axis` (1 for Y, 2 for Z) of the lowest surface point, found by marching rays
+    /// up from below the bounds on a grid across them; features thinner than a grid cell can be
+    /// missed. Falls back to the bounds when they are infinite across the axis, and is `None`
+    /// when the scene is unbounded below or empty.
+    pub fn lowest_point(&self, axis: usize) -> Option<f32> {
+        const GRID: usize = 64;
+        let bounds = self.bounds();
+        if bounds.is_empty() || !bounds.min[axis].is_finite() {
+            return None;
+        }
+        if !bounds.is_finite() {
+            return Some(bounds.min[axis]);
+        }
+        let (u, v) = ((axis + 1) % 3, (axis + 2) % 3);
+        let up = Vec3::AXES[axis];
+        let far = bounds.size()[axis] + 0.2;
+        let mut lowest: Option<f32> = None;
+        for i in 0..=GRID {
+            for j in 0..=GRID {
+                let mut ro = Vec3::ZERO;
+                ro[axis] = bounds.min[axis] - 0.1;
+                ro[u] = bounds.min[u] + bounds.size()[u] * i as f32 / GRID as f32;
+                ro[v] = bounds.min[v] + bounds.size()[v] * j as f32 / GRID as f32;
+                if let Some(t) = self.raycast(ro, up, 0.0, far) {
+                    let height = ro[axis] + t;
+                    lowest = Some(lowest.map_or(height, |l| l.min(height)));
+                }
+            }
+        }
+        lowest
+    }
+
+    /// Every step `ray_march` in the shader takes along a ray, including the over-relaxation of
+    /// `relaxation > 1` and its retreats, and marching the far side of a shell when `ro` is
+    /// inside a solid.
+    pub fn march_trace(&self, ro: Vec3, rd: Vec3, near: f32, far: f32, relaxation: f32) -> MarchTrace {
+        let step_factor = self.step_factor();
+        let side = if self.distance(ro + rd * near) < 0.0 { -1.0 } else { 1.0 };
+        let mut trace = MarchTrace { steps: Vec::new(), end: MarchEnd::StepLimit };
+        let (mut t, mut omega, mut prev_t, mut prev_r) = (near, relaxation, near, 0.0);
+        for _ in 0..128 {
+            let d = self.distance(ro + rd * t) * side;
+            let r = d * step_factor;
+            if omega > 1.0 && (d < 0.0 || r + prev_r < t - prev_t) {
+                trace.steps.push(MarchStep { t, dist: d, retreat: true });
+                omega = 1.0;
+                t = prev_t + prev_r;
+                continue;
+            }
+            trace.steps.push(MarchStep { t, dist: d, retreat: false });
+            if d < 0.0005 || t > far {
+                trace.end = if d < 0.0005 && t < far { MarchEnd::Hit } else { MarchEnd::Miss };
+                break;
+            }
+            prev_t = t;
+            prev_r = r;
+            t += r * omega;
+        }
+        trace
+    }
+
+    /// Signed distance at `p`, exact wherever `step_factor` is 1. Every transform is rigid or a
+    /// uniform `scale` (which rescales its target's distance), so the mesh exporters find the
+    /// surface where the shader draws it.
+    pub fn distance(&self, p: Vec3) -> f32 {
+        self.evaluate(p).dist
+    }
+
+    /// Normalized central-difference gradient, the CPU version of `calc_normal`.
+    pub fn gradient(&self, p: Vec3) -> Vec3 {
+        let e = 1e-3;
+        Vec3::new(
+            self.distance(p + Vec3::X * e) - self.distance(p - Vec3::X * e),
+            self.distance(p + Vec3::Y * e) - self.distance(p - Vec3::Y * e),
+            self.distance(p + Vec3::Z * e) - self.distance(p - Vec3::Z * e),
+        ).normalize_or_zero()
+    }
+
+    /// Unnormalized four-tap (tetrahedral) gradient, the estimate the shader's `displace_*`
+    /// helpers use for a `DisplaceMap`'s triplanar weights, which only need its direction.
+    fn blend_normal(&self, p: Vec3, uv: Vec3) -> Vec3 {
+        let e = 1e-3;
+        [Vec3::new(1.0, -1.0, -1.0), Vec3::new(-1.0, -1.0, 1.0), Vec3::new(-1.0, 1.0, -1.0), Vec3::ONE]
+            .into_iter()
+            .map(|k| k * self.evaluate_uv(p + k * e, uv).dist)
+            .sum()
+    }
+}
@@ -0,0 +1,546 @@
+use glam::{Mat3, Vec3};
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver};
+use std::sync::Arc;
+use crate::sdf_ast::SdfNode;
+use crate::sdf_bounds::Aabb;
+
+/// Small xorshift generator so sampling is reproducible without pulling in `rand`.
+pub(crate) struct XorShift(pub(crate) u32);
+
+impl XorShift {
+    pub(crate) fn next_f32(&mut self) -> f32 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 17;
+        self.0 ^= self.0 << 5;
+        (self.0 >> 8) as f32 / (1u32 << 24) as f32
+    }
+
+    pub(crate) fn next_in_box(&mut self, region: &Aabb) -> Vec3 {
+        region.min + Vec3::new(self.next_f32(), self.next_f32(), self.next_f32()) * region.size()
+    }
+}
+
+/// Progress callback for long exports: receives the completed fraction and returns `false` to
+/// cancel, which makes the export fail with `io::ErrorKind::Interrupted`.
+pub type Progress<'a> = &'a mut dyn FnMut(f32) -> bool;
+
+fn report(progress: &mut Progress, fraction: f32) -> io::Result<()> {
+    if progress(fraction) { Ok(()) } else { Err(io::Error::new(io::ErrorKind::Interrupted, "export cancelled")) }
+}
+
+/// An export running on a worker thread, polled from the UI each frame.
+pub struct ExportJob {
+    rx: Receiver<ExportMessage>,
+    cancel: Arc<AtomicBool>,
+    pub progress: f32,
+}
+
+enum ExportMessage {
+    Progress(f32),
+    Finished(Result<String, String>),
+}
+
+impl ExportJob {
+    /// Runs `export` on a new thread. It returns the status line to show when it finishes.
+    pub fn spawn(export: impl FnOnce(Progress) -> Result<String, String> + Send + 'static) -> Self {
+        let (tx, rx) = mpsc::channel();
+        let cancel = Arc::new(AtomicBool::new(false));
+        let cancelled = cancel.clone();
+        std::thread::spawn(move || {
+            let mut progress = |fraction: f32| {
+                let _ = tx.send(ExportMessage::Progress(fraction));
+                !cancelled.load(Ordering::Relaxed)
+            };
+            let result = export(&mut progress);
+            let _ = tx.send(ExportMessage::Finished(result));
+        });
+        Self { rx, cancel, progress: 0.0 }
+    }
+
+    pub fn cancel(&self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+
+    /// Drains pending messages; returns the final status once the worker is done.
+    pub fn poll(&mut self) -> Option<Result<String, String>> {
+        while let Ok(message) = self.rx.try_recv() {
+            match message {
+                ExportMessage::Progress(fraction) => self.progress = fraction,
+                ExportMessage::Finished(result) => return Some(result),
+            }
+        }
+        None
+    }
+}
+
+/// `name` as a relative URI reference: everything but RFC 3986's unreserved characters is
+/// percent-encoded, so spaces, quotes and backslashes in file names survive.
+fn uri_encode(name: &str) -> String {
+    name.bytes().map(|b| if b.is_ascii_alphanumeric() || b"-._~".contains(&b) { (b as char).to_string() } else { format!("%{b:02X}") }).collect()
+}
+
+fn color_to_u8(c: Vec3) -> [u8; 3] {
+    let c = c.clamp(Vec3::ZERO, Vec3::ONE) * 255.0;
+    [c.x.round() as u8, c.y.round() as u8, c.z.round() as u8]
+}
+
+/// Distance samples on a regular lattice of cubic cells covering `region`, with `resolution`
+/// samples along its longest side. Shared by the mesh exporters so they all see the same field.
+pub struct SampleGrid {
+    pub dims: [usize; 3],
+    pub origin: Vec3,
+    pub cell_size: f32,
+    values: Vec<f32>,
+}
+
+impl SampleGrid {
+    /// Reports progress per z-slice, scaled into `[0, progress_scale]`.
+    pub fn sample(node: &SdfNode, resolution: u32, region: &Aabb, progress: &mut Progress, progress_scale: f32) -> io::Result<Self> {
+        let size = region.size().max(Vec3::splat(1e-4));
+        let cell_size = size.max_element() / (resolution.max(2) - 1) as f32;
+        let dims = (size / cell_size).ceil().to_array().map(|d| d as usize + 1);
+        let mut grid = Self { dims, origin: region.min, cell_size, values: Vec::with_capacity(dims[0] * dims[1] * dims[2]) };
+        for k in 0..dims[2] {
+            for j in 0..dims[1] {
+                for i in 0..dims[0] {
+                    let d = node.distance(grid.position(i, j, k));
+                    grid.values.push(d);
+                }
+            }
+            report(progress, progress_scale * (k + 1) as f32 / dims[2] as f32)?;
+        }
+        Ok(grid)
+    }
+
+    pub fn position(&self, i: usize, j: usize, k: usize) -> Vec3 {
+        self.origin + Vec3::new(i as f32, j as f32, k as f32) * self.cell_size
+    }
+
+    pub fn value(&self, i: usize, j: usize, k: usize) -> f32 {
+        self.values[i + self.dims[0] * (j + self.dims[1] * k)]
+    }
+}
+
+/// Indexed triangle mesh produced by the exporters.
+#[derive(Default)]
+pub struct Mesh {
+    pub positions: Vec<Vec3>,
+    pub normals: Vec<Vec3>,
+    pub colors: Vec<Vec3>,
+    pub triangles: Vec<[u32; 3]>,
+}
+
+impl Mesh {
+    /// Writes the mesh as OBJ, PLY, binary glTF (`.glb`) or glTF with a sidecar `.bin`
+    /// (`.gltf`) depending on the file extension (OBJ by default).
+    pub fn write(&self, path: &Path) -> io::Result<()> {
+        let extension = path.extension().map(|e| e.to_ascii_lowercase());
+        let extension = extension.as_ref().and_then(|e| e.to_str());
+        if extension == Some("gltf") {
+            return self.write_gltf(path);
+        }
+        let mut w = BufWriter::new(File::create(path)?);
+        match extension {
+            Some("ply") => self.write_ply(&mut w)?,
+            Some("glb") => self.write_glb(&mut w)?,
+            _ => self.write_obj(&mut w)?,
+        }
+        w.flush()
+    }
+
+    fn write_obj(&self, w: &mut impl Write) -> io::Result<()> {
+        // Vertex colors use the widely supported `v x y z r g b` extension (Blender, MeshLab).
+        for (p, c) in self.positions.iter().zip(&self.colors) {
+            let c = c.clamp(Vec3::ZERO, Vec3::ONE);
+            writeln!(w, "v {} {} {} {} {} {}", p.x, p.y, p.z, c.x, c.y, c.z)?;
+        }
+        for n in &self.normals {
+            writeln!(w, "vn {} {} {}", n.x, n.y, n.z)?;
+        }
+        for [a, b, c] in &self.triangles {
+            // OBJ indices are 1-based
+            writeln!(w, "f {0}//{0} {1}//{1} {2}//{2}", a + 1, b + 1, c + 1)?;
+        }
+        Ok(())
+    }
+
+    fn write_ply(&self, w: &mut impl Write) -> io::Result<()> {
+        writeln!(w, "ply")?;
+        writeln!(w, "format ascii 1.0")?;
+        writeln!(w, "element vertex {}", self.positions.len())?;
+        for prop in ["x", "y", "z", "nx", "ny", "nz"] {
+            writeln!(w, "property float {prop}")?;
+        }
+        for prop in ["red", "green", "blue"] {
+            writeln!(w, "property uchar {prop}")?;
+        }
+        writeln!(w, "element face {}", self.triangles.len())?;
+        writeln!(w, "property list uchar int vertex_indices")?;
+        writeln!(w, "end_header")?;
+        for ((p, n), c) in self.positions.iter().zip(&self.normals).zip(&self.colors) {
+            let [r, g, b] = color_to_u8(*c);
+            writeln!(w, "{} {} {} {} {} {} {r} {g} {b}", p.x, p.y, p.z, n.x, n.y, n.z)?;
+        }
+        for [a, b, c] in &self.triangles {
+            writeln!(w, "3 {a} {b} {c}")?;
+        }
+        Ok(())
+    }
+
+    /// glTF document and binary buffer: one mesh with a primitive per material, all sharing the
+    /// position and normal accessors. Each triangle takes the material of its first vertex's
+    /// color; glTF multiplies vertex colors into the base color, so they are left out.
+    /// `bin_uri` names the sidecar buffer file, `None` for a GLB's embedded chunk.
+    fn gltf_parts(&self, bin_uri: Option<&str>) -> io::Result<(String, Vec<u8>)> {
+        use gltf_json::validation::Checked::Valid;
+        use gltf_json::validation::USize64;
+        use gltf_json::{accessor, buffer, material, mesh, Index};
+
+        let mut colors: Vec<[u8; 3]> = Vec::new();
+        let mut groups: Vec<Vec<u32>> = Vec::new();
+        for tri in &self.triangles {
+            let key = color_to_u8(self.colors[tri[0] as usize]);
+            let index = colors.iter().position(|c| *c == key).unwrap_or_else(|| {
+                colors.push(key);
+                groups.push(Vec::new());
+                colors.len() - 1
+            });
+            groups[index].extend(tri);
+        }
+
+        let mut root = gltf_json::Root {
+            asset: gltf_json::Asset { generator: Some("sdf_poc".to_string()), ..Default::default() },
+            ..Default::default()
+        };
+        let scene = |nodes| gltf_json::Scene { extensions: None, extras: Default::default(), name: None, nodes };
+        if groups.is_empty() {
+            root.scene = Some(root.push(scene(Vec::new())));
+            return Ok((root.to_string().map_err(io::Error::other)?, Vec::new()));
+        }
+
+        let mut bin: Vec<u8> = Vec::new();
+        let buffer = Index::new(0);
+        let mut push_view = |root: &mut gltf_json::Root, bytes: &[u8], target: buffer::Target| {
+            let view = root.push(buffer::View {
+                buffer,
+                byte_length: USize64::from(bytes.len()),
+                byte_offset: Some(USize64::from(bin.len())),
+                byte_stride: None,
+                name: None,
+                target: Some(Valid(target)),
+                extensions: None,
+                extras: Default::default(),
+            });
+            bin.extend_from_slice(bytes);
+            view
+        };
+        let accessor = |view: Index<buffer::View>, count: usize, component: accessor::ComponentType, type_: accessor::Type| accessor::Accessor {
+            buffer_view: Some(view),
+            byte_offset: None,
+            count: USize64::from(count),
+            component_type: Valid(accessor::GenericComponentType(component)),
+            extensions: None,
+            extras: Default::default(),
+            type_: Valid(type_),
+            min: None,
+            max: None,
+            name: None,
+            normalized: false,
+            sparse: None,
+        };
+
+        let normals: Vec<Vec3> = self.normals.iter().map(|n| n.try_normalize().unwrap_or(Vec3::Y)).collect();
+        let position_view = push_view(&mut root, bytemuck::cast_slice(&self.positions), buffer::Target::ArrayBuffer);
+        let normal_view = push_view(&mut root, bytemuck::cast_slice(&normals), buffer::Target::ArrayBuffer);
+        let (min, max) = self.positions.iter().fold((Vec3::INFINITY, Vec3::NEG_INFINITY), |(lo, hi), p| (lo.min(*p), hi.max(*p)));
+        let positions = root.push(accessor::Accessor {
+            min: Some(gltf_json::Value::from(min.to_array().to_vec())),
+            max: Some(gltf_json::Value::from(max.to_array().to_vec())),
+            ..accessor(position_view, self.positions.len(), accessor::ComponentType::F32, accessor::Type::Vec3)
+        });
+        let normals = root.push(accessor(normal_view, normals.len(), accessor::ComponentType::F32, accessor::Type::Vec3));
+
+        let mut primitives = Vec::new();
+        for (i, (group, color)) in groups.iter().zip(&colors).enumerate() {
+            let view = push_view(&mut root, bytemuck::cast_slice(group), buffer::Target::ElementArrayBuffer);
+            let indices = root.push(accessor(view, group.len(), accessor::ComponentType::U32, accessor::Type::Scalar));
+            let [r, g, b] = color.map(|c| c as f32 / 255.0);
+            let material = root.push(material::Material {
+                name: Some(format!("color_{i}")),
+                pbr_metallic_roughness: material::PbrMetallicRoughness {
+                    base_color_factor: material::PbrBaseColorFactor([r, g, b, 1.0]),
+                    metallic_factor: material::StrengthFactor(0.0),
+                    roughness_factor: material::StrengthFactor(0.5),
+                    ..Default::default()
+                },
+                ..Default::default()
+            });
+            primitives.push(mesh::Primitive {
+                attributes: [(Valid(mesh::Semantic::Positions), positions), (Valid(mesh::Semantic::Normals), normals)].into(),
+                extensions: None,
+                extras: Default::default(),
+                indices: Some(indices),
+                material: Some(material),
+                mode: Valid(mesh::Mode::Triangles),
+                targets: None,
+            });
+        }
+        let mesh = root.push(gltf_json::Mesh { extensions: None, extras: Default::default(), name: None, primitives, weights: None });
+        let node = root.push(gltf_json::Node { mesh: Some(mesh), ..Default::default() });
+        root.scene = Some(root.push(scene(vec![node])));
+        root.push(gltf_json::Buffer {
+            byte_length: USize64::from(bin.len()),
+            name: None,
+            uri: bin_uri.map(uri_encode),
+            extensions: None,
+            extras: Default::default(),
+        });
+        Ok((root.to_string().map_err(io::Error::other)?, bin))
+    }
+
+    fn write_gltf(&self, path: &Path) -> io::Result<()> {
+        let bin_path = path.with_extension("bin");
+        let bin_name = bin_path.file_name().and_then(|n| n.to_str()).unwrap_or("mesh.bin");
+        let (json, bin) = self.gltf_parts(Some(bin_name))?;
+        std::fs::write(&bin_path, bin)?;
+        std::fs::write(path, json)
+    }
+
+    fn write_glb(&self, w: &mut impl Write) -> io::Result<()> {
+        let (json, bin) = self.gltf_parts(None)?;
+        // Chunks are 4-byte aligned: JSON padded with spaces, binary with zeros
+        let mut json = json.into_bytes();
+        json.resize(json.len().next_multiple_of(4), b' ');
+        let bin_padded = bin.len().next_multiple_of(4);
+        let bin_chunk = if bin.is_empty() { 0 } else { 8 + bin_padded };
+        let total = 12 + 8 + json.len() + bin_chunk;
+
+        w.write_all(b"glTF")?;
+        w.write_all(&2u32.to_le_bytes())?;
+        w.write_all(&(total as u32).to_le_bytes())?;
+        w.write_all(&(json.len() as u32).to_le_bytes())?;
+        w.write_all(b"JSON")?;
+        w.write_all(&json)?;
+        if !bin.is_empty() {
+            w.write_all(&(bin_padded as u32).to_le_bytes())?;
+            w.write_all(b"BIN\0")?;
+            w.write_all(&bin)?;
+            w.write_all(&vec![0; bin_padded - bin.len()])?;
+        }
+        Ok(())
+    }
+}
+
+/// Corner offsets of the 12 cell edges, as (start corner, end corner).
+const CELL_EDGES: [([usize; 3], [usize; 3]); 12] = [
+    ([0, 0, 0], [1, 0, 0]), ([0, 1, 0], [1, 1, 0]), ([0, 0, 1], [1, 0, 1]), ([0, 1, 1], [1, 1, 1]),
+    ([0, 0, 0], [0, 1, 0]), ([1, 0, 0], [1, 1, 0]), ([0, 0, 1], [0, 1, 1]), ([1, 0, 1], [1, 1, 1]),
+    ([0, 0, 0], [0, 0, 1]), ([1, 0, 0], [1, 0, 1]), ([0, 1, 0], [0, 1, 1]), ([1, 1, 0], [1, 1, 1]),
+];
+
+impl SdfNode {
+    /// Places one vertex per sign-changing cell by minimizing the quadratic error against the
+    /// cell's Hermite data (edge crossings + surface normals). Vertices snap onto creases, which
+    /// is what keeps box edges and chamfers sharp where marching cubes would round them off.
+    fn dual_contour_vertex(&self, grid: &SampleGrid, i: usize, j: usize, k: usize) -> Option<Vec3> {
+        let mut ata = Mat3::ZERO;
+        let mut atb = Vec3::ZERO;
+        let mut mass_point = Vec3::ZERO;
+        let mut crossings = 0;
+
+        for (c0, c1) in CELL_EDGES {
+            let d0 = grid.value(i + c0[0], j + c0[1], k + c0[2]);
+            let d1 = grid.value(i + c1[0], j + c1[1], k + c1[2]);
+            if (d0 < 0.0) == (d1 < 0.0) {
+                continue;
+            }
+            let p0 = grid.position(i + c0[0], j + c0[1], k + c0[2]);
+            let p1 = grid.position(i + c1[0], j + c1[1], k + c1[2]);
+            let p = p0.lerp(p1, d0 / (d0 - d1));
+            let n = self.gradient(p);
+
+            ata += Mat3::from_cols(n * n.x, n * n.y, n * n.z);
+            atb += n * n.dot(p);
+            mass_point += p;
+            crossings += 1;
+        }
+        if crossings == 0 {
+            return None;
+        }
+        mass_point /= crossings as f32;
+
+        // Solve around the mass point with a small regularization so flat regions stay centered.
+        let reg = 0.05;
+        let lhs = ata + Mat3::from_diagonal(Vec3::splat(reg));
+        let rhs = atb - ata * mass_point;
+        let mut x = mass_point;
+        if lhs.determinant().abs() > 1e-8 {
+            x += lhs.inverse() * rhs;
+        }
+
+        let min = grid.position(i, j, k);
+        let max = grid.position(i + 1, j + 1, k + 1);
+        Some(x.clamp(min, max))
+    }
+
+    /// Meshes the surface inside `[-bounds, bounds]^3` with dual contouring.
+    pub fn mesh_dual_contour(&self, resolution: u32, bounds: f32, mut progress: Progress) -> io::Result<Mesh> {
+        let mut mesh = Mesh::default();
+        let region = self.export_region(bounds);
+        if region.is_empty() {
+            return Ok(mesh);
+        }
+        // Sampling and vertex placement dominate; connecting quads is cheap
+        let grid = SampleGrid::sample(self, resolution, &region, &mut progress, 0.5)?;
+        let n = grid.dims;
+        let cells = n.map(|d| d - 1);
+        let cell_index = |i: usize, j: usize, k: usize| i + cells[0] * (j + cells[1] * k);
+
+        let mut cell_vertex = vec![u32::MAX; cells[0] * cells[1] * cells[2]];
+        for k in 0..cells[2] {
+            for j in 0..cells[1] {
+                for i in 0..cells[0] {
+                    if let Some(v) = self.dual_contour_vertex(&grid, i, j, k) {
+                        cell_vertex[cell_index(i, j, k)] = mesh.positions.len() as u32;
+                        mesh.positions.push(v);
+                        mesh.normals.push(self.gradient(v));
+                        mesh.colors.push(self.evaluate(v).color);
+                    }
+                }
+            }
+            report(&mut progress, 0.5 + 0.45 * (k + 1) as f32 / cells[2] as f32)?;
+        }
+
+        // Every sign-changing lattice edge is shared by four cells; connect their vertices.
+        for k in 0..n[2] {
+            for j in 0..n[1] {
+                for i in 0..n[0] {
+                    let here = [i, j, k];
+                    for axis in 0..3 {
+                        let (u, v) = ((axis + 1) % 3, (axis + 2) % 3);
+                        if here[axis] + 1 >= n[axis] || here[u] == 0 || here[v] == 0 || here[u] >= cells[u] || here[v] >= cells[v] {
+                            continue;
+                        }
+                        let mut next = here;
+                        next[axis] += 1;
+                        let d0 = grid.value(i, j, k);
+                        let d1 = grid.value(next[0], next[1], next[2]);
+                        if (d0 < 0.0) == (d1 < 0.0) {
+                            continue;
+                        }
+
+                        let mut quad = [0u32; 4];
+                        for (slot, (du, dv)) in [(1, 1), (0, 1), (0, 0), (1, 0)].into_iter().enumerate() {
+                            let mut c = here;
+                            c[u] -= du;
+                            c[v] -= dv;
+                            quad[slot] = cell_vertex[cell_index(c[0], c[1], c[2])];
+                        }
+                        if quad.contains(&u32::MAX) {
+                            continue;
+                        }
+                        if d0 >= 0.0 {
+                            quad.reverse();
+                        }
+                        mesh.triangles.push([quad[0], quad[1], quad[2]]);
+                        mesh.triangles.push([quad[0], quad[2], quad[3]]);
+                    }
+                }
+            }
+        }
+
+        report(&mut progress, 1.0)?;
+        Ok(mesh)
+    }
+
+    /// Writes a dual-contoured mesh to `path` (`.obj`, `.ply`, `.glb` or `.gltf`). Returns the
+    /// triangle count.
+    pub fn export_mesh_dual_contour(&self, resolution: u32, bounds: f32, path: impl AsRef<Path>, progress: Progress) -> io::Result<usize> {
+        let mesh = self.mesh_dual_contour(resolution, bounds, progress)?;
+        mesh.write(path.as_ref())?;
+        Ok(mesh.triangles.len())
+    }
+
+    /// Moves `p` onto the zero-isosurface by stepping along the gradient.
+    /// Returns `None` if it does not converge (e.g. the point started far from any surface).
+    pub fn project_to_surface(&self, mut p: Vec3) -> Option<Vec3> {
+        for _ in 0..32 {
+            let d = self.distance(p);
+            if d.abs() < 1e-4 {
+                return Some(p);
+            }
+            let g = self.gradient(p);
+            if g == Vec3::ZERO {
+                return None;
+            }
+            p -= g * d;
+        }
+        None
+    }
+
+    /// Writes up to `count` points sampled on the surface inside `[-bounds, bounds]^3` as an
+    /// ASCII PLY with per-point normals and colors. Returns the number of points written.
+    pub fn export_point_cloud_ply(&self, count: u32, bounds: f32, path: impl AsRef<Path>, mut progress: Progress) -> io::Result<usize> {
+        let mut rng = XorShift(0x9E37_79B9);
+        let region = self.export_region(bounds);
+        let mut points = Vec::with_capacity(count as usize);
+        let max_attempts = count as usize * 64;
+
+        for attempt in 0..max_attempts {
+            if points.len() >= count as usize || region.is_empty() {
+                break;
+            }
+            if attempt % 1024 == 0 {
+                report(&mut progress, points.len() as f32 / count as f32)?;
+            }
+            let Some(p) = self.project_to_surface(rng.next_in_box(&region)) else { continue };
+            if p.cmplt(region.min).any() || p.cmpgt(region.max).any() {
+                continue;
+            }
+            points.push((p, self.gradient(p), self.evaluate(p).color));
+        }
+
+        let mut w = BufWriter::new(File::create(path)?);
+        writeln!(w, "ply")?;
+        writeln!(w, "format ascii 1.0")?;
+        writeln!(w, "element vertex {}", points.len())?;
+        for prop in ["x", "y", "z", "nx", "ny", "nz"] {
+            writeln!(w, "property float {prop}")?;
+        }
+        for prop in ["red", "green", "blue"] {
+            writeln!(w, "property uchar {prop}")?;
+        }
+        writeln!(w, "end_header")?;
+        for (p, n, c) in &points {
+            let [r, g, b] = color_to_u8(*c);
+            writeln!(w, "{} {} {} {} {} {} {r} {g} {b}", p.x, p.y, p.z, n.x, n.y, n.z)?;
+        }
+        w.flush()?;
+
+        Ok(points.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scaled_sphere_meshes_like_a_larger_sphere() {
+        let (resolution, bounds) = (24, 3.0);
+        let scaled = SdfNode::new_sphere(1.0).scale(2.0).mesh_dual_contour(resolution, bounds, &mut |_| true).unwrap();
+        let plain = SdfNode::new_sphere(2.0).mesh_dual_contour(resolution, bounds, &mut |_| true).unwrap();
+        // Both cover the same region, so the sample grids line up cell for cell
+        let cell = SdfNode::new_sphere(2.0).export_region(bounds).size().max_element() / resolution as f32;
+        assert!(!plain.positions.is_empty());
+        assert_eq!(scaled.positions.len(), plain.positions.len());
+        for (a, b) in scaled.positions.iter().zip(&plain.positions) {
+            assert!(a.distance(*b) < cell, "{a} vs {b}");
+            assert!((a.length() - 2.0).abs() < cell, "{a} is off the radius-2 sphere");
+        }
+    }
+}
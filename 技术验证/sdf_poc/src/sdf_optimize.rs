@@ -0,0 +1,189 @@
+use glam::{Quat, Vec3};
+use crate::sdf_ast::{smooth_group_k, SdfNode, SdfOp, SmoothKind};
+
+/// Rigid transform `x -> rotation * x + offset`, the forward placement of a node's target.
+#[derive(Clone, Copy)]
+struct Placement {
+    rotation: Quat,
+    offset: Vec3,
+}
+
+impl Placement {
+    /// Placement of a static translate or rotate node, or `None` for any other node.
+    fn of(node: &SdfNode) -> Option<Placement> {
+        match &node.op {
+            SdfOp::Translate { offset, .. } => Some(Placement { rotation: Quat::IDENTITY, offset: Vec3::from(*offset) }),
+            SdfOp::Rotate { axis, angle_deg, .. } => {
+                let axis = if axis[0] > 0.9 { Vec3::X } else if axis[1] > 0.9 { Vec3::Y } else { Vec3::Z };
+                Some(Placement { rotation: Quat::from_axis_angle(axis, angle_deg.to_radians()), offset: Vec3::ZERO })
+            }
+            SdfOp::Transform { rotation, offset, .. } => Some(Placement { rotation: Quat::from_array(*rotation), offset: Vec3::from(*offset) }),
+            _ => None,
+        }
+    }
+
+    /// `self` applied after `inner`.
+    fn then(self, inner: Placement) -> Placement {
+        Placement { rotation: (self.rotation * inner.rotation).normalize(), offset: self.rotation * inner.offset + self.offset }
+    }
+}
+
+impl SdfNode {
+    /// Copy of the tree rewritten for the shader generator: every chain of two or more static
+    /// translates and rotates becomes a single `Transform` (or `Translate` when nothing rotates),
+    /// so the sample point is moved once instead of once per node. Leaves keep their order, and
+    /// with it their primitive ids. Animated transforms are left alone.
+    pub fn optimize(&self) -> SdfNode {
+        let mut node = self;
+        let mut placement = Placement { rotation: Quat::IDENTITY, offset: Vec3::ZERO };
+        let mut chain = 0;
+        while let Some(inner) = Placement::of(node) {
+            placement = placement.then(inner);
+            node = node.children()[0];
+            chain += 1;
+        }
+        if chain < 2 {
+            let mut copy = self.clone();
+            for child in copy.children_mut() {
+                *child = child.optimize();
+            }
+            return copy;
+        }
+
+        let target = Box::new(node.optimize());
+        let op = if placement.rotation.xyz().length() < 1e-6 {
+            SdfOp::Translate { target, offset: placement.offset.into() }
+        } else {
+            SdfOp::Transform { target, rotation: placement.rotation.to_array(), offset: placement.offset.into() }
+        };
+        SdfNode { op }
+    }
+
+    /// Copy of the tree with every crisp union and subtract smoothed by `k` (of the polynomial
+    /// kind), and every hard `smooth_group` given `k`, keeping the radius of those that already blend.
+    pub fn with_global_smooth(&self, k: f32) -> SdfNode {
+        let mut copy = self.clone();
+        match &mut copy.op {
+            SdfOp::Union { smooth, kind, .. } | SdfOp::Subtract { smooth, kind, .. } if *smooth == 0.0 => {
+                *smooth = k;
+                *kind = SmoothKind::Poly;
+            }
+            // A hard group stays order-independent, so it takes the exponential blend
+            SdfOp::SmoothGroup { k: smooth, .. } if *smooth == 0.0 => *smooth = smooth_group_k(k),
+            _ => {}
+        }
+        for child in copy.children_mut() {
+            *child = child.with_global_smooth(k);
+        }
+        copy
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rhai::Dynamic;
+
+    /// Points around and inside the test shapes, on all sides of the origin.
+    fn sample_points() -> Vec<Vec3> {
+        let mut points = Vec::new();
+        for x in -4..=4 {
+            for y in -3..=3 {
+                for z in -3..=3 {
+                    points.push(Vec3::new(x as f32 * 0.7 + 0.13, y as f32 * 0.6 - 0.07, z as f32 * 0.65 + 0.05));
+                }
+            }
+        }
+        points
+    }
+
+    fn assert_same_field(original: &SdfNode, optimized: &SdfNode) {
+        for p in sample_points() {
+            let (a, b) = (original.evaluate(p), optimized.evaluate(p));
+            assert!((a.dist - b.dist).abs() < 1e-4, "distance at {p}: {} vs {}", a.dist, b.dist);
+            assert!((a.color - b.color).length() < 1e-4, "color at {p}: {} vs {}", a.color, b.color);
+        }
+    }
+
+    /// Number of static translate, rotate and transform nodes in the tree.
+    fn transform_count(node: &SdfNode) -> usize {
+        let own = usize::from(Placement::of(node).is_some());
+        own + node.children().iter().map(|c| transform_count(c)).sum::<usize>()
+    }
+
+    fn transform(target: SdfNode, axis: Vec3, angle_deg: f32, offset: [f32; 3]) -> SdfNode {
+        let rotation = Quat::from_axis_angle(axis.normalize(), angle_deg.to_radians()).to_array();
+        SdfNode { op: SdfOp::Transform { target: Box::new(target), rotation, offset } }
+    }
+
+    #[test]
+    fn nested_translate_rotate_chain_collapses_to_one_transform() {
+        let original = SdfNode::new_box(0.8, 0.4, 0.2)
+            .translate(0.5, 0.0, 0.0)
+            .rotate_z(30.0)
+            .translate(0.0, 0.3, -0.2)
+            .rotate_x(45.0)
+            .rotate_y(-60.0)
+            .translate(0.1, 0.0, 0.4);
+        let optimized = original.optimize();
+        assert!(matches!(optimized.op, SdfOp::Transform { .. }));
+        assert_eq!(transform_count(&optimized), 1);
+        assert_same_field(&original, &optimized);
+    }
+
+    #[test]
+    fn transform_nodes_join_the_chain() {
+        let inner = SdfNode::new_cylinder(0.3, 0.9).rotate_x(20.0).translate(0.2, 0.0, 0.0);
+        let original = transform(inner, Vec3::new(1.0, 1.0, 0.0), 50.0, [0.0, -0.4, 0.3]).rotate_z(-15.0);
+        let optimized = original.optimize();
+        assert_eq!(transform_count(&optimized), 1);
+        assert_same_field(&original, &optimized);
+        // Optimizing again finds a single node, so nothing changes
+        assert_same_field(&original, &optimized.optimize());
+    }
+
+    #[test]
+    fn chains_under_unions_and_arrays() {
+        let arm = SdfNode::new_box(0.6, 0.1, 0.1).translate(0.6, 0.0, 0.0).rotate_y(35.0).translate(0.0, 0.2, 0.0);
+        let ring = SdfNode::new_torus(0.7, 0.1).color(1.0, 0.2, 0.2).rotate_x(90.0).rotate_z(10.0);
+        let original = arm
+            .clone()
+            .array_rotated(4, 0.0, 0.1, 0.0, 0.0, 90.0, 0.0)
+            .union(ring)
+            .smooth_union(SdfNode::new_sphere(0.3).translate(0.0, -1.0, 0.0).translate(0.4, 0.0, 0.0), 0.2);
+        let optimized = original.optimize();
+        // Three chains, one transform each
+        assert_eq!(transform_count(&optimized), 3);
+        assert_same_field(&original, &optimized);
+    }
+
+    #[test]
+    fn identity_rotation_collapses_to_translate() {
+        let original = SdfNode::new_sphere(0.5)
+            .translate(0.3, 0.0, 0.0)
+            .rotate_y(90.0)
+            .rotate_y(-90.0)
+            .translate(0.0, 0.2, -0.1);
+        let optimized = original.optimize();
+        let SdfOp::Translate { offset, .. } = optimized.op else { panic!("expected a translate, got {:?}", optimized.op) };
+        assert!((Vec3::from(offset) - Vec3::new(0.3, 0.2, -0.1)).length() < 1e-5);
+        assert_same_field(&original, &optimized);
+    }
+
+    #[test]
+    fn single_transforms_are_kept() {
+        let original = SdfNode::new_sphere(0.5).translate(1.0, 0.0, 0.0).union(SdfNode::new_box(0.3, 0.3, 0.3).rotate_x(30.0));
+        let optimized = original.optimize();
+        assert_eq!(transform_count(&optimized), 2);
+        assert_same_field(&original, &optimized);
+    }
+
+    #[test]
+    fn tiny_global_smooth_leaves_groups_hard() {
+        let group = SdfNode::new_smooth_group(vec![Dynamic::from(SdfNode::new_sphere(0.5)), Dynamic::from(SdfNode::new_sphere(0.4))], 0.0).unwrap();
+        let SdfOp::SmoothGroup { k, .. } = group.with_global_smooth(1e-6).op else { panic!("expected a smooth_group") };
+        assert_eq!(k, 0.0);
+        let SdfOp::SmoothGroup { k, .. } = group.with_global_smooth(0.1).op else { panic!("expected a smooth_group") };
+        assert_eq!(k, 0.1);
+    }
+}
@@ -0,0 +1,93 @@
+use crate::sdf_ast::{AnimChannel, SdfNode, SdfOp};
+
+/// Size and cost figures for the "Statistics" panel.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SceneStats {
+    pub primitives: usize,
+    /// Union, subtract and intersect (smooth or not), groove and pipe.
+    pub booleans: usize,
+    pub max_depth: usize,
+    /// Rough instruction count of one `map()` call; see `SdfNode::stats`.
+    pub estimated_instructions: usize,
+    /// Whether any op has an approximate distance (step factor below 1).
+    pub non_exact: bool,
+}
+
+impl SdfNode {
+    /// Walks the tree once. The instruction estimate mirrors how `WgslGenerator` inlines code:
+    /// a transform's point expression is pasted into every primitive below it, so its cost is
+    /// paid once per primitive, and a displaced target is evaluated five times (value plus
+    /// gradient). Weights are hand-tuned guesses, only meant for comparing scenes.
+    pub fn stats(&self) -> SceneStats {
+        let mut stats = SceneStats { non_exact: self.step_factor() < 1.0, ..Default::default() };
+        stats.estimated_instructions = self.collect_stats(0, 0, &mut stats);
+        stats
+    }
+
+    /// Adds this subtree's counts to `stats` and returns its instruction estimate, given the
+    /// cost `point_cost` of computing the sample point it receives.
+    fn collect_stats(&self, depth: usize, point_cost: usize, stats: &mut SceneStats) -> usize {
+        stats.max_depth = stats.max_depth.max(depth + 1);
+        let child = |node: &SdfNode, point_cost: usize, stats: &mut SceneStats| node.collect_stats(depth + 1, point_cost, stats);
+        let primitive = |cost: usize, stats: &mut SceneStats| {
+            stats.primitives += 1;
+            cost + point_cost
+        };
+        match &self.op {
+            SdfOp::Sphere { .. } => primitive(4, stats),
+            SdfOp::InfiniteCylinder { .. } => primitive(6, stats),
+            SdfOp::Torus { .. } | SdfOp::InfiniteCone { .. } => primitive(8, stats),
+            SdfOp::Capsule { .. } => primitive(9, stats),
+            SdfOp::Box { .. } | SdfOp::Cylinder { .. } => primitive(10, stats),
+            SdfOp::Wedge { .. } | SdfOp::CutHollowSphere { .. } => primitive(16, stats),
+            SdfOp::SuperEllipsoid { .. } | SdfOp::Helix { .. } | SdfOp::MeshVolume { .. } => primitive(30, stats),
+            SdfOp::Tetrahedron { .. } => primitive(40, stats),
+            SdfOp::Mandelbulb { iterations, .. } => primitive(40 * *iterations as usize, stats),
+            SdfOp::SvgProfile { segments, .. } => primitive(12 * segments.len(), stats),
+
+            SdfOp::Union { a, b, smooth, .. } | SdfOp::Subtract { a, b, smooth, .. } | SdfOp::Intersect { a, b, smooth, .. } => {
+                stats.booleans += 1;
+                child(a, point_cost, stats) + child(b, point_cost, stats) + if *smooth > 0.0 { 12 } else { 3 }
+            }
+            SdfOp::SmoothGroup { children, k } => {
+                stats.booleans += children.len().saturating_sub(1);
+                let per_child = if *k > 0.0 { 14 } else { 3 };
+                children.iter().map(|c| child(c, point_cost, stats) + per_child).sum()
+            }
+            SdfOp::Nearest { a, b } | SdfOp::Farthest { a, b } => {
+                stats.booleans += 1;
+                child(a, point_cost, stats) + child(b, point_cost, stats) + 3
+            }
+            SdfOp::Groove { target: a, cutter: b, .. } | SdfOp::Pipe { a, b, .. } => {
+                stats.booleans += 1;
+                child(a, point_cost, stats) + child(b, point_cost, stats) + 6
+            }
+
+            SdfOp::Translate { target, .. } | SdfOp::Mirror { target, .. } => child(target, point_cost + 3, stats),
+            SdfOp::Rotate { target, .. } | SdfOp::MirrorPlane { target, .. } => child(target, point_cost + 8, stats),
+            SdfOp::Transform { target, .. } => child(target, point_cost + 12, stats),
+            SdfOp::Scale { target, .. } => child(target, point_cost + 3, stats) + 2,
+            SdfOp::Repeat { target, spacing } => child(target, point_cost + 6 * spacing.iter().filter(|s| **s > 0.0).count(), stats),
+            SdfOp::Warp { target, .. } => child(target, point_cost + 60, stats),
+            // Like `DisplaceMap`, the target is a helper called once per copy
+            SdfOp::ArrayLinear { target, count, .. } => {
+                stats.booleans += 1;
+                point_cost + *count as usize * (child(target, 0, stats) + 20)
+            }
+            // The target becomes a helper function taking the point, so its transforms are not
+            // repeated per call
+            SdfOp::DisplaceMap { target, .. } => point_cost + 5 * child(target, 0, stats) + 20,
+            SdfOp::OnionRings { target, .. } => child(target, point_cost, stats) + 6,
+            // Folded into the texture coordinates when the shader is generated
+            SdfOp::UvScale { target, .. } => child(target, point_cost, stats),
+            SdfOp::Annular { target, .. } | SdfOp::Color { target, .. } | SdfOp::InsideColor { target, .. }
+            | SdfOp::Clearcoat { target, .. } => child(target, point_cost, stats) + 2,
+            // Evaluated by the volume pass rather than `map()`, but on the same pixels
+            SdfOp::Volume { target, .. } => child(target, point_cost, stats) + 4,
+            SdfOp::Animated { target, channel, .. } => match channel {
+                AnimChannel::Inflate => child(target, point_cost, stats) + 10,
+                AnimChannel::Translate { .. } | AnimChannel::Rotate { .. } => child(target, point_cost + 10, stats),
+            },
+        }
+    }
+}
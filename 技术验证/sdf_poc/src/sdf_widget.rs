@@ -4,70 +4,285 @@ use eframe::wgpu;
 use wgpu::util::DeviceExt;
 use bytemuck::{Pod, Zeroable};
 use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use crate::env_map::EnvMap;
+use crate::height_map::HeightMap;
+use crate::mesh_volume::{self, MeshVolume};
 
 #[repr(C)]
 #[derive(Copy, Clone, Debug, Pod, Zeroable)]
 struct Uniforms {
     rect_data: [f32; 4],     // x, y, w, h
-    time_data: [f32; 4],     // time, padding...
+    time_data: [f32; 4],     // time, SSAA samples, material-ID view, Z up
     cam_pos:   [f32; 4],     // x, y, z, padding
     cam_right: [f32; 4],     // x, y, z, padding
     cam_up:    [f32; 4],     // x, y, z, padding
     cam_front: [f32; 4],     // x, y, z, padding
+    env_data:  [f32; 4],     // enabled, intensity, padding
+    dof_data:  [f32; 4],     // focus distance, aperture, padding
+    bg_color:  [f32; 4],     // r, g, b, padding
+    slice_plane: [f32; 4],   // normal (zero when disabled), offset
+    inside_color: [f32; 4],  // r, g, b, enabled
+    light_dir: [f32; 4],     // x, y, z (Y-up frame), padding
+    output_data: [f32; 4],   // encode sRGB in the shader, outlines on, crease and depth thresholds
+    ambient:   [f32; 4],     // r, g, b (color times intensity), padding
+}
+
+impl Uniforms {
+    /// `srgb_target`: the render target encodes sRGB on write, so the shader must not.
+    fn new(rect: [f32; 4], time: f32, c: &CameraUniformData, params: &RenderParams, has_env: bool, srgb_target: bool) -> Self {
+        Self {
+            rect_data: rect,
+            time_data: [time, params.ssaa_samples as f32, if params.show_ids { 1.0 } else { 0.0 }, if params.z_up { 1.0 } else { 0.0 }],
+            cam_pos:   [c.pos[0], c.pos[1], c.pos[2], 1.0],
+            cam_right: [c.right[0], c.right[1], c.right[2], 0.0],
+            cam_up:    [c.up[0], c.up[1], c.up[2], 0.0],
+            cam_front: [c.front[0], c.front[1], c.front[2], 0.0],
+            env_data:  [if has_env { 1.0 } else { 0.0 }, params.env_intensity, 0.0, 0.0],
+            dof_data:  [params.focus_distance, params.aperture, 0.0, 0.0],
+            bg_color:  [params.background[0], params.background[1], params.background[2], 0.0],
+            slice_plane: params.slice_plane,
+            inside_color: match params.inside_tint {
+                Some(c) => [c[0], c[1], c[2], 1.0],
+                None => [0.0; 4],
+            },
+            light_dir: [params.light_dir[0], params.light_dir[1], params.light_dir[2], 0.0],
+            output_data: match params.outline {
+                Some([crease, depth]) => [if params.gamma_correct && !srgb_target { 1.0 } else { 0.0 }, 1.0, crease, depth],
+                None => [if params.gamma_correct && !srgb_target { 1.0 } else { 0.0 }, 0.0, 0.0, 0.0],
+            },
+            ambient:   [params.ambient[0], params.ambient[1], params.ambient[2], 0.0],
+        }
+    }
+}
+
+/// The uniform block for `camera` and `params` as plain floats, for viewers outside the app
+/// (the HTML export) that upload the same layout. The viewport and time are left for them to fill.
+/// Browsers' preferred canvas formats are not sRGB, so the shader does the encoding.
+pub fn uniform_floats(camera: &CameraUniformData, params: &RenderParams) -> Vec<f32> {
+    bytemuck::cast_slice(&[Uniforms::new([0.0, 0.0, 1.0, 1.0], 0.0, camera, params, false, false)]).to_vec()
 }
 
 pub struct SdfRenderResources {
     pipeline: wgpu::RenderPipeline,
     bind_group: wgpu::BindGroup,
     uniform_buffer: wgpu::Buffer,
+    has_env: bool,
+    target_format: wgpu::TextureFormat,
     start_time: std::time::Instant,
+    /// Set once the camera uniform has been written; `paint` asserts it in debug builds.
+    uniforms_written: AtomicBool,
 }
 
 impl SdfRenderResources {
-    pub fn create(device: &wgpu::Device, target_format: wgpu::TextureFormat, shader_source: &str) -> Option<Self> {
+    pub fn create(device: &wgpu::Device, queue: &wgpu::Queue, target_format: wgpu::TextureFormat, shader_source: &str, env: Option<&EnvMap>, height_maps: &[Arc<HeightMap>], mesh_volumes: &[Arc<MeshVolume>]) -> Option<Self> {
         // Compile Shader
         let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
             label: Some("SDF Shader"),
             source: wgpu::ShaderSource::Wgsl(shader_source.into()),
         });
 
-        // Initial zeroed uniforms
-        let uniforms = Uniforms {
-            rect_data: [0.0; 4],
-            time_data: [0.0; 4],
-            cam_pos:   [0.0; 4],
-            cam_right: [0.0; 4],
-            cam_up:    [0.0; 4],
-            cam_front: [0.0; 4],
-        };
-        
+        // The zeroed contents are never drawn with: every draw (`SdfCallback::prepare` and
+        // `render_to_texture`) writes the camera first, so a recompile cannot flash the origin view.
+        // `uniforms_written` checks this in debug builds
+        let uniforms = Uniforms::zeroed();
+
         let uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
             label: Some("SDF Uniform Buffer"),
             contents: bytemuck::cast_slice(&[uniforms]),
             usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
         });
 
+        // Environment map; a 1x1 black texture keeps the bind group valid when none is loaded
+        let (env_size, env_data) = match env {
+            Some(env) => ((env.width, env.height), env.mips.concat()),
+            None => ((1, 1), vec![0u32]),
+        };
+        let env_texture = device.create_texture_with_data(
+            queue,
+            &wgpu::TextureDescriptor {
+                label: Some("SDF Environment Map"),
+                size: wgpu::Extent3d { width: env_size.0, height: env_size.1, depth_or_array_layers: 1 },
+                mip_level_count: env.map_or(1, |e| e.mips.len() as u32),
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: wgpu::TextureFormat::Rgb9e5Ufloat,
+                usage: wgpu::TextureUsages::TEXTURE_BINDING,
+                view_formats: &[],
+            },
+            wgpu::util::TextureDataOrder::LayerMajor,
+            bytemuck::cast_slice(&env_data),
+        );
+        let env_view = env_texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let env_sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("SDF Environment Sampler"),
+            address_mode_u: wgpu::AddressMode::Repeat,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        // Height maps share one texture array, so each is resampled to the largest map's size,
+        // capped at the GPU's limit. A 1x1 mid-gray layer keeps the bind group valid when the
+        // scene uses none.
+        let max_layers = device.limits().max_texture_array_layers;
+        if height_maps.len() > max_layers as usize {
+            log::error!("The scene uses {} height maps, over this GPU's limit of {} texture layers", height_maps.len(), max_layers);
+            return None;
+        }
+        let max_2d = device.limits().max_texture_dimension_2d;
+        let map_size = height_maps.iter().fold((1, 1), |(w, h), m| (w.max(m.width), h.max(m.height)));
+        if map_size.0 > max_2d || map_size.1 > max_2d {
+            log::warn!("Height maps of {}x{} are over this GPU's texture limit of {}; uploading them downsampled", map_size.0, map_size.1, max_2d);
+        }
+        let map_size = (map_size.0.min(max_2d), map_size.1.min(max_2d));
+        let map_data = if height_maps.is_empty() {
+            vec![128u8]
+        } else {
+            height_maps.iter().flat_map(|m| m.resized(map_size.0, map_size.1)).collect()
+        };
+        let height_texture = device.create_texture_with_data(
+            queue,
+            &wgpu::TextureDescriptor {
+                label: Some("SDF Height Maps"),
+                size: wgpu::Extent3d { width: map_size.0, height: map_size.1, depth_or_array_layers: height_maps.len().max(1) as u32 },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: wgpu::TextureFormat::R8Unorm,
+                usage: wgpu::TextureUsages::TEXTURE_BINDING,
+                view_formats: &[],
+            },
+            wgpu::util::TextureDataOrder::LayerMajor,
+            &map_data,
+        );
+        let height_view = height_texture.create_view(&wgpu::TextureViewDescriptor {
+            dimension: Some(wgpu::TextureViewDimension::D2Array),
+            ..Default::default()
+        });
+        let height_sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("SDF Height Map Sampler"),
+            address_mode_u: wgpu::AddressMode::Repeat,
+            address_mode_v: wgpu::AddressMode::Repeat,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        // Baked meshes share one 3D texture, stacked along Z; a single texel keeps the bind group
+        // valid when the scene uses none
+        let (_, volume_size) = mesh_volume::atlas_layout(mesh_volumes);
+        let max_3d = device.limits().max_texture_dimension_3d;
+        if volume_size.iter().any(|n| *n > max_3d) {
+            log::error!("Baked meshes need a {}x{}x{} texture, over this GPU's limit of {}; lower their resolution", volume_size[0], volume_size[1], volume_size[2], max_3d);
+            return None;
+        }
+        let volume_texture = device.create_texture_with_data(
+            queue,
+            &wgpu::TextureDescriptor {
+                label: Some("SDF Mesh Volumes"),
+                size: wgpu::Extent3d { width: volume_size[0], height: volume_size[1], depth_or_array_layers: volume_size[2] },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D3,
+                format: wgpu::TextureFormat::R32Float,
+                usage: wgpu::TextureUsages::TEXTURE_BINDING,
+                view_formats: &[],
+            },
+            wgpu::util::TextureDataOrder::LayerMajor,
+            bytemuck::cast_slice(&mesh_volume::atlas_data(mesh_volumes)),
+        );
+        let volume_view = volume_texture.create_view(&wgpu::TextureViewDescriptor::default());
+
         let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
             label: Some("SDF Bind Group Layout"),
-            entries: &[wgpu::BindGroupLayoutEntry {
-                binding: 0,
-                visibility: wgpu::ShaderStages::FRAGMENT | wgpu::ShaderStages::VERTEX,
-                ty: wgpu::BindingType::Buffer {
-                    ty: wgpu::BufferBindingType::Uniform,
-                    has_dynamic_offset: false,
-                    min_binding_size: None,
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT | wgpu::ShaderStages::VERTEX,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 3,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2Array,
+                        multisampled: false,
+                    },
+                    count: None,
                 },
-                count: None,
-            }],
+                wgpu::BindGroupLayoutEntry {
+                    binding: 4,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 5,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                        view_dimension: wgpu::TextureViewDimension::D3,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+            ],
         });
 
         let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
             label: Some("SDF Bind Group"),
             layout: &bind_group_layout,
-            entries: &[wgpu::BindGroupEntry {
-                binding: 0,
-                resource: uniform_buffer.as_entire_binding(),
-            }],
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: uniform_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(&env_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::Sampler(&env_sampler),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: wgpu::BindingResource::TextureView(&height_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 4,
+                    resource: wgpu::BindingResource::Sampler(&height_sampler),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 5,
+                    resource: wgpu::BindingResource::TextureView(&volume_view),
+                },
+            ],
         });
 
         let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
@@ -109,20 +324,114 @@ impl SdfRenderResources {
             pipeline,
             bind_group,
             uniform_buffer,
+            has_env: env.is_some(),
+            target_format,
             start_time: std::time::Instant::now(),
+            uniforms_written: AtomicBool::new(false),
         })
     }
 
-    pub fn new(cc: &eframe::CreationContext<'_>, shader_source: &str) -> Option<Self> {
+    pub fn new(cc: &eframe::CreationContext<'_>, shader_source: &str, env: Option<&EnvMap>, height_maps: &[Arc<HeightMap>], mesh_volumes: &[Arc<MeshVolume>]) -> Option<Self> {
         let wgpu_render_state = cc.wgpu_render_state.as_ref()?;
-        Self::create(&wgpu_render_state.device, wgpu_render_state.target_format, shader_source)
+        Self::create(&wgpu_render_state.device, &wgpu_render_state.queue, wgpu_render_state.target_format, shader_source, env, height_maps, mesh_volumes)
     }
     
-    pub fn from_wgpu_state(rs: &egui_wgpu::RenderState, shader_source: &str) -> Option<Self> {
-        Self::create(&rs.device, rs.target_format, shader_source)
+    pub fn from_wgpu_state(rs: &egui_wgpu::RenderState, shader_source: &str, env: Option<&EnvMap>, height_maps: &[Arc<HeightMap>], mesh_volumes: &[Arc<MeshVolume>]) -> Option<Self> {
+        Self::create(&rs.device, &rs.queue, rs.target_format, shader_source, env, height_maps, mesh_volumes)
+    }
+
+    /// Renders one frame into a new texture of `size` pixels in the surface format. The work is
+    /// submitted right away, so it lands before the egui frame that samples the texture.
+    pub fn render_to_texture(&self, device: &wgpu::Device, queue: &wgpu::Queue, size: [u32; 2], camera: &CameraUniformData, params: &RenderParams) -> wgpu::Texture {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("SDF Offscreen Target"),
+            size: wgpu::Extent3d { width: size[0], height: size[1], depth_or_array_layers: 1 },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: self.target_format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        let rect = [0.0, 0.0, size[0] as f32, size[1] as f32];
+        let time = self.start_time.elapsed().as_secs_f32();
+        let uniforms = Uniforms::new(rect, time, camera, params, self.has_env, self.target_format.is_srgb());
+        queue.write_buffer(&self.uniform_buffer, 0, bytemuck::cast_slice(&[uniforms]));
+        self.uniforms_written.store(true, Ordering::Relaxed);
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: Some("SDF Offscreen Encoder") });
+        {
+            let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("SDF Offscreen Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &view,
+                    resolve_target: None,
+                    ops: wgpu::Operations { load: wgpu::LoadOp::Clear(wgpu::Color::BLACK), store: wgpu::StoreOp::Store },
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+            pass.set_pipeline(&self.pipeline);
+            pass.set_bind_group(0, &self.bind_group, &[]);
+            pass.draw(0..4, 0..1);
+        }
+        queue.submit(Some(encoder.finish()));
+        texture
+    }
+
+    /// Renders one frame like `render_to_texture` and reads it back as tightly packed RGBA8 rows,
+    /// blocking until the GPU is done. `None` if the surface format is not 8-bit RGBA/BGRA or the
+    /// readback fails.
+    pub fn render_to_rgba(&self, device: &wgpu::Device, queue: &wgpu::Queue, size: [u32; 2], camera: &CameraUniformData, params: &RenderParams) -> Option<Vec<u8>> {
+        use wgpu::TextureFormat as F;
+        let bgra = match self.target_format {
+            F::Bgra8Unorm | F::Bgra8UnormSrgb => true,
+            F::Rgba8Unorm | F::Rgba8UnormSrgb => false,
+            _ => return None,
+        };
+        let texture = self.render_to_texture(device, queue, size, camera, params);
+
+        // Buffer rows must be padded to the copy alignment
+        let bytes_per_row = (size[0] * 4).next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT);
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("SDF Readback Buffer"),
+            size: (bytes_per_row * size[1]) as u64,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: Some("SDF Readback Encoder") });
+        encoder.copy_texture_to_buffer(
+            texture.as_image_copy(),
+            wgpu::ImageCopyBuffer {
+                buffer: &buffer,
+                layout: wgpu::ImageDataLayout { offset: 0, bytes_per_row: Some(bytes_per_row), rows_per_image: None },
+            },
+            wgpu::Extent3d { width: size[0], height: size[1], depth_or_array_layers: 1 },
+        );
+        queue.submit(Some(encoder.finish()));
+
+        let slice = buffer.slice(..);
+        let (sender, receiver) = std::sync::mpsc::channel();
+        slice.map_async(wgpu::MapMode::Read, move |result| { let _ = sender.send(result); });
+        device.poll(wgpu::Maintain::Wait);
+        receiver.recv().ok()?.ok()?;
+
+        let data = slice.get_mapped_range();
+        let mut rgba = Vec::with_capacity((size[0] * size[1] * 4) as usize);
+        for row in data.chunks(bytes_per_row as usize) {
+            for px in row[..(size[0] * 4) as usize].chunks(4) {
+                let [r, g, b] = if bgra { [px[2], px[1], px[0]] } else { [px[0], px[1], px[2]] };
+                rgba.extend_from_slice(&[r, g, b, 255]);
+            }
+        }
+        Some(rgba)
     }
 }
 
+#[derive(Clone, Copy)]
 pub struct CameraUniformData {
     pub pos: [f32; 3],
     pub right: [f32; 3],
@@ -130,14 +439,48 @@ pub struct CameraUniformData {
     pub front: [f32; 3],
 }
 
+/// Per-frame shading parameters that are written to the uniforms without recompiling.
+#[derive(Clone, Copy, PartialEq)]
+pub struct RenderParams {
+    pub env_intensity: f32,
+    pub focus_distance: f32,
+    /// Lens radius; 0 disables depth of field.
+    pub aperture: f32,
+    pub background: [f32; 3],
+    /// Distance slice `[nx, ny, nz, offset]`; a zero normal renders the surface normally.
+    pub slice_plane: [f32; 4],
+    /// Color for surfaces seen from inside a solid; `None` shades them like the outside.
+    pub inside_tint: Option<[f32; 3]>,
+    /// Supersamples per pixel: 1, 2 or 4.
+    pub ssaa_samples: u32,
+    /// Debug view: color surfaces by the primitive that produced them instead of their material.
+    pub show_ids: bool,
+    /// Direction towards the key light; +Y is up, or +Z when `z_up` is set.
+    pub light_dir: [f32; 3],
+    /// Fill light added to the key light's diffuse term (color times intensity), so faces turned
+    /// away from it are not black. An environment map replaces it with its own ambient light.
+    pub ambient: [f32; 3],
+    /// Treat the shaded color as linear and sRGB-encode it for display (in the shader, unless the
+    /// target format does it on write). Off writes the values unchanged.
+    pub gamma_correct: bool,
+    /// Dark outlines where neighboring samples differ by more than `[crease, depth]`: a normal
+    /// difference `1 - cos(angle)` or a relative depth jump. `None` draws none.
+    pub outline: Option<[f32; 2]>,
+    /// World Z is up: the ground grid lies in the XY plane and the sky and key light are above +Z.
+    pub z_up: bool,
+}
+
 pub struct SdfCallback {
     resources: Arc<SdfRenderResources>,
     time: f32,
     rect: Rect,
     camera: CameraUniformData,
+    params: RenderParams,
 }
 
 impl CallbackTrait for SdfCallback {
+    /// egui runs every callback's `prepare` before opening the render pass, so the uniforms of
+    /// resources created this frame are filled in before their first `paint`.
     fn prepare(
         &self,
         _device: &wgpu::Device,
@@ -148,23 +491,16 @@ impl CallbackTrait for SdfCallback {
     ) -> Vec<wgpu::CommandBuffer> {
         let ppp = screen_descriptor.pixels_per_point;
         
-        let c = &self.camera;
-        
-        let uniforms = Uniforms {
-            rect_data: [
-                self.rect.min.x * ppp,
-                self.rect.min.y * ppp,
-                self.rect.width() * ppp,
-                self.rect.height() * ppp,
-            ],
-            time_data: [self.time, 0.0, 0.0, 0.0],
-            cam_pos:   [c.pos[0], c.pos[1], c.pos[2], 1.0],
-            cam_right: [c.right[0], c.right[1], c.right[2], 0.0],
-            cam_up:    [c.up[0], c.up[1], c.up[2], 0.0],
-            cam_front: [c.front[0], c.front[1], c.front[2], 0.0],
-        };
+        let rect = [
+            self.rect.min.x * ppp,
+            self.rect.min.y * ppp,
+            self.rect.width() * ppp,
+            self.rect.height() * ppp,
+        ];
+        let uniforms = Uniforms::new(rect, self.time, &self.camera, &self.params, self.resources.has_env, self.resources.target_format.is_srgb());
         
         queue.write_buffer(&self.resources.uniform_buffer, 0, bytemuck::cast_slice(&[uniforms]));
+        self.resources.uniforms_written.store(true, Ordering::Relaxed);
         Vec::new()
     }
 
@@ -174,13 +510,14 @@ impl CallbackTrait for SdfCallback {
         render_pass: &mut wgpu::RenderPass<'static>,
         _callback_resources: &egui_wgpu::CallbackResources,
     ) {
+        debug_assert!(self.resources.uniforms_written.load(Ordering::Relaxed), "SDF resources drawn before their camera uniform was written");
         render_pass.set_pipeline(&self.resources.pipeline);
         render_pass.set_bind_group(0, &self.resources.bind_group, &[]);
         render_pass.draw(0..4, 0..1);
     }
 }
 
-pub fn sdf_view(ui: &mut Ui, resources: &Arc<SdfRenderResources>, camera: CameraUniformData) -> eframe::egui::Response {
+pub fn sdf_view(ui: &mut Ui, resources: &Arc<SdfRenderResources>, camera: CameraUniformData, params: RenderParams) -> eframe::egui::Response {
     let available = ui.available_size();
     let size = Vec2::new(available.x.max(100.0), available.y.max(100.0));
     let (rect, response) = ui.allocate_exact_size(size, Sense::click_and_drag());
@@ -194,6 +531,7 @@ pub fn sdf_view(ui: &mut Ui, resources: &Arc<SdfRenderResources>, camera: Camera
             time,
             rect,
             camera,
+            params,
         },
     );
 
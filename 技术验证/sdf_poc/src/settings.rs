@@ -0,0 +1,497 @@
+use eframe::egui;
+use rhai::{Array, Dynamic, Map};
+use std::path::{Path, PathBuf};
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum SliceAxis { X, Y, Z }
+
+impl SliceAxis {
+    pub fn normal(self) -> [f32; 3] {
+        match self {
+            SliceAxis::X => [1.0, 0.0, 0.0],
+            SliceAxis::Y => [0.0, 1.0, 0.0],
+            SliceAxis::Z => [0.0, 0.0, 1.0],
+        }
+    }
+}
+
+/// Sub-pixel positions of the shader's 4 anti-aliasing samples.
+#[derive(Clone, Copy, PartialEq)]
+pub enum AaPattern { Grid, RotatedGrid, Halton }
+
+impl AaPattern {
+    /// Offsets from the pixel center, in pixels.
+    pub fn offsets(self) -> [[f32; 2]; 4] {
+        match self {
+            AaPattern::Grid => [[-0.25, -0.25], [0.25, -0.25], [-0.25, 0.25], [0.25, 0.25]],
+            // Every sample has its own row and column, so near-axis-aligned edges get 4 levels
+            AaPattern::RotatedGrid => [[0.125, -0.375], [0.375, 0.125], [-0.125, 0.375], [-0.375, -0.125]],
+            // Halton (2, 3) points 1..=4, centered on the pixel
+            AaPattern::Halton => [[0.0, -1.0 / 6.0], [-0.25, 1.0 / 6.0], [0.25, -7.0 / 18.0], [-0.375, -1.0 / 18.0]],
+        }
+    }
+}
+
+/// Raymarching scheme baked into the shader.
+#[derive(Clone, Copy, PartialEq)]
+pub enum MarchMethod {
+    /// Plain sphere tracing: each step is the distance to the nearest surface.
+    Sphere,
+    /// Over-relaxed (enhanced) sphere tracing: steps are lengthened while consecutive distance
+    /// spheres still overlap, and retreat to a plain step when they do not.
+    Relaxed,
+}
+
+impl MarchMethod {
+    /// Step length multiplier `MARCH_RELAXATION`; 1 disables relaxation.
+    pub fn relaxation(self) -> f32 {
+        match self {
+            MarchMethod::Sphere => 1.0,
+            MarchMethod::Relaxed => 1.6,
+        }
+    }
+}
+
+/// Viewport and rendering options shown in the "Settings" panel.
+///
+/// The ones that decide how a scene looks can be saved next to its script (see `look_json` and
+/// `scene_settings_path`) and are offered when the scene is opened; the rest are not saved.
+#[derive(Clone)]
+pub struct SdfSettings {
+    /// Only repaint continuously while the camera moves or the scene is animated.
+    pub pause_when_idle: bool,
+    /// Repaint rate of a static scene when `pause_when_idle` is off. Camera moves and animated
+    /// scenes still repaint every frame (up to vsync).
+    pub idle_fps: f32,
+    /// Camera movement per pixel of right-drag panning.
+    pub pan_speed: f32,
+    /// Distance the arrow keys move the selected `translate` node.
+    pub nudge_step: f32,
+    /// Treat world Z as up (CAD convention) for the camera, nudging, the ground grid, the sky and
+    /// lighting. Scene coordinates are unchanged; primitives keep their Y-aligned axes.
+    pub z_up: bool,
+    /// Round interactively moved offsets to multiples of `snap_increment` (hold Alt to bypass).
+    pub snap: bool,
+    pub snap_increment: f32,
+    /// Color of rays that miss the scene when no environment map is loaded.
+    pub background: [f32; 3],
+    /// Ray distance range baked into the shader: surfaces closer than `march_near` are clipped
+    /// away and rays give up past `march_far`, which also bounds the ground grid.
+    pub march_near: f32,
+    pub march_far: f32,
+    pub aa_pattern: AaPattern,
+    pub march_method: MarchMethod,
+    /// Exploded view: each visible object is moved away from the objects' common center by this
+    /// multiple of its distance from it (see `Scene::explode_offsets`).
+    pub explode: f32,
+    /// Blend radius given to every crisp union and subtract in the generated shader, to preview
+    /// a softer model without editing the script; 0 leaves them crisp. Picking, measuring and
+    /// exports still use the scene as written.
+    pub global_smooth: f32,
+    /// Supersamples per pixel (1, 2 or 4). A uniform, so changing it needs no recompile.
+    pub ssaa_samples: u32,
+    /// Pick the sample count each frame to keep the frame rate near `target_fps`.
+    pub auto_ssaa: bool,
+    pub target_fps: f32,
+    /// Direction towards the key light, in the Y-up frame (+Y is whichever axis is up, see
+    /// `z_up`). Need not be normalized.
+    pub light_dir: [f32; 3],
+    /// Light from the camera, shining where it looks (for inspecting cavities); `light_dir` is
+    /// ignored while set.
+    pub headlight: bool,
+    /// Fill light that reaches every surface, so the side turned away from the key light keeps
+    /// some shading. Unused while an environment map lights the scene.
+    pub ambient_color: [f32; 3],
+    pub ambient_intensity: f32,
+    /// sRGB-encode the linear shading result for display; see `RenderParams::gamma_correct`.
+    pub gamma_correct: bool,
+    /// Equirectangular `.hdr`/`.png` used for the background, reflections and ambient light.
+    pub hdri_path: String,
+    pub hdri_intensity: f32,
+    /// Distance along the view direction that stays sharp.
+    pub focus_distance: f32,
+    /// Lens radius for depth of field; 0 keeps everything in focus.
+    pub aperture: f32,
+    /// Surfaces seen from inside a solid (camera in a wall) are shaded with flipped normals,
+    /// and tinted with `inside_tint` when `tint_inside` is on.
+    pub tint_inside: bool,
+    pub inside_tint: [f32; 3],
+    /// Debug view: shade the signed distance on a plane instead of raymarching the surface.
+    pub slice_view: bool,
+    /// Debug view: color each surface by the leaf primitive it comes from (numbered in script
+    /// order), to check which solid a boolean keeps or cuts.
+    pub show_ids: bool,
+    /// Debug overlay: draw each primitive's bounding box as a wireframe over the view.
+    pub show_bounds: bool,
+    /// Toon/blueprint style: outline silhouettes (depth jumps of more than `outline_depth` times
+    /// the distance) and creases sharper than `outline_crease_deg`.
+    pub outlines: bool,
+    pub outline_crease_deg: f32,
+    pub outline_depth: f32,
+    pub slice_axis: SliceAxis,
+    /// Draw a color bar mapping the slice view's colors to distances.
+    pub slice_legend: bool,
+    /// Plane position along `slice_axis`.
+    pub slice_offset: f32,
+    /// Developer mode: read `shader_template.wgsl` from the source tree on every compile.
+    /// Defaults to on when the `SDF_DEV_TEMPLATE` environment variable is set.
+    pub template_from_disk: bool,
+}
+
+impl Default for SdfSettings {
+    fn default() -> Self {
+        Self {
+            pause_when_idle: true,
+            idle_fps: 30.0,
+            pan_speed: 0.01,
+            nudge_step: 0.1,
+            z_up: false,
+            snap: false,
+            snap_increment: 0.25,
+            background: [0.08, 0.08, 0.1],
+            march_near: 0.0,
+            march_far: 50.0,
+            aa_pattern: AaPattern::Grid,
+            march_method: MarchMethod::Sphere,
+            explode: 0.0,
+            global_smooth: 0.0,
+            ssaa_samples: 4,
+            auto_ssaa: false,
+            target_fps: 60.0,
+            light_dir: glam::Vec3::new(2.0, 4.0, 3.0).normalize().into(),
+            headlight: false,
+            ambient_color: [1.0, 1.0, 1.0],
+            ambient_intensity: 0.1,
+            gamma_correct: true,
+            hdri_path: String::new(),
+            hdri_intensity: 1.0,
+            focus_distance: 8.0,
+            aperture: 0.0,
+            tint_inside: false,
+            inside_tint: [0.85, 0.35, 0.3],
+            slice_view: false,
+            show_ids: false,
+            show_bounds: false,
+            outlines: false,
+            outline_crease_deg: 40.0,
+            outline_depth: 0.1,
+            slice_axis: SliceAxis::Y,
+            slice_legend: true,
+            slice_offset: 0.0,
+            template_from_disk: std::env::var_os("SDF_DEV_TEMPLATE").is_some(),
+        }
+    }
+}
+
+impl SdfSettings {
+    /// Returns true when the user asked to (re)load the environment map. SSAA options above
+    /// `max_ssaa` samples are disabled.
+    pub fn ui(&mut self, ui: &mut egui::Ui, max_ssaa: u32) -> bool {
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut self.pause_when_idle, "Pause rendering when idle")
+                .on_hover_text("Stop redrawing a static scene until there is input, to save power");
+            ui.add_enabled(!self.pause_when_idle, egui::DragValue::new(&mut self.idle_fps).range(1.0..=240.0).suffix(" FPS"))
+                .on_hover_text("Otherwise redraw a static scene at this rate; camera moves and animations are not capped");
+        });
+        ui.add(egui::DragValue::new(&mut self.pan_speed).range(0.0001..=1.0).speed(0.001).prefix("Pan speed: "))
+            .on_hover_text("Distance the camera moves per pixel of right-mouse drag");
+        ui.checkbox(&mut self.z_up, "Z up")
+            .on_hover_text("Orbit, move and draw the ground grid around the Z axis, as in CAD tools. \
+                Cylinders, cones and other axial primitives still point along Y; rotate them by 90° about X to stand them up");
+        ui.add(egui::DragValue::new(&mut self.nudge_step).range(0.001..=10.0).speed(0.01).prefix("Nudge step: "))
+            .on_hover_text("Arrow keys / PgUp / PgDn move the selected translate node by this much");
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut self.snap, "Snap to grid").on_hover_text("Hold Alt while nudging to bypass");
+            ui.add_enabled(self.snap, egui::DragValue::new(&mut self.snap_increment).range(0.001..=10.0).speed(0.01));
+        });
+        ui.horizontal(|ui| {
+            ui.label("Background:");
+            ui.color_edit_button_rgb(&mut self.background);
+        });
+        ui.horizontal(|ui| {
+            ui.label("March range:");
+            ui.add(egui::DragValue::new(&mut self.march_near).range(0.0..=self.march_far).speed(0.05).prefix("near "));
+            ui.add(egui::DragValue::new(&mut self.march_far).range(self.march_near.max(0.1)..=1000.0).speed(0.5).prefix("far "));
+        }).response.on_hover_text("Recompiles the shader; a larger far range costs more steps on misses");
+        ui.horizontal(|ui| {
+            ui.label("Marching:");
+            ui.selectable_value(&mut self.march_method, MarchMethod::Sphere, "Sphere tracing");
+            ui.selectable_value(&mut self.march_method, MarchMethod::Relaxed, "Relaxed")
+                .on_hover_text("Over-relaxed sphere tracing: fewer steps on open scenes and along surfaces, same image");
+        });
+        ui.add(egui::Slider::new(&mut self.global_smooth, 0.0..=0.5).text("Global smoothing"))
+            .on_hover_text("Smooth every crisp union and subtract by this radius (recompiles the shader). Preview only: picking and exports ignore it");
+        ui.horizontal(|ui| {
+            ui.label("AA pattern:");
+            ui.selectable_value(&mut self.aa_pattern, AaPattern::Grid, "Grid");
+            ui.selectable_value(&mut self.aa_pattern, AaPattern::RotatedGrid, "Rotated grid")
+                .on_hover_text("Smoother near-horizontal and near-vertical edges");
+            ui.selectable_value(&mut self.aa_pattern, AaPattern::Halton, "Halton");
+        });
+        ui.horizontal(|ui| {
+            ui.label("SSAA:");
+            ui.add_enabled_ui(!self.auto_ssaa, |ui| {
+                for samples in [1, 2, 4] {
+                    let label = egui::SelectableLabel::new(self.ssaa_samples == samples, format!("{samples}x"));
+                    if ui.add_enabled(samples <= max_ssaa, label).on_disabled_hover_text("Too slow on a software renderer").clicked() {
+                        self.ssaa_samples = samples;
+                    }
+                }
+            });
+            ui.add_enabled(max_ssaa > 1, egui::Checkbox::new(&mut self.auto_ssaa, "Auto"))
+                .on_hover_text("Lower the sample count when frames take longer than the target allows, raise it when there is headroom");
+            ui.add_enabled(self.auto_ssaa, egui::DragValue::new(&mut self.target_fps).range(10.0..=240.0).suffix(" FPS"));
+        });
+
+        ui.checkbox(&mut self.gamma_correct, "Gamma-correct output (sRGB)")
+            .on_hover_text("Shading is computed in linear light; encode it as sRGB for the screen. \
+                Off shows the linear values directly, which looks darker (how this viewer used to render)");
+
+        ui.separator();
+        ui.horizontal(|ui| {
+            ui.label("Light direction:");
+            ui.checkbox(&mut self.headlight, "Headlight")
+                .on_hover_text("Light the scene from the camera, following it as you move, instead of from a fixed direction");
+        });
+        ui.add_enabled_ui(!self.headlight, |ui| ui.horizontal(|ui| {
+            light_gizmo(ui, &mut self.light_dir)
+                .on_hover_text("Drag to move the light over the sky; the center is straight up, the rim the horizon");
+            // Edited components are not renormalized here, so dragging one does not move the others
+            ui.vertical(|ui| {
+                for (v, axis) in self.light_dir.iter_mut().zip(["x ", "y ", "z "]) {
+                    ui.add(egui::DragValue::new(v).range(-1.0..=1.0).speed(0.01).max_decimals(3).prefix(axis));
+                }
+            });
+        }));
+
+        ui.horizontal(|ui| {
+            ui.label("Ambient:");
+            ui.color_edit_button_rgb(&mut self.ambient_color);
+            ui.add(egui::Slider::new(&mut self.ambient_intensity, 0.0..=1.0).text("intensity"));
+        }).response.on_hover_text("Fill light on every surface, so areas facing away from the light are not black. \
+            An environment map provides its own instead");
+
+        ui.separator();
+        let mut load_env = false;
+        ui.horizontal(|ui| {
+            ui.label("HDRI:");
+            ui.add(egui::TextEdit::singleline(&mut self.hdri_path).hint_text("path/to/env.hdr").desired_width(180.0));
+            load_env = ui.button("Load").on_hover_text("Load the environment map; an empty path clears it").clicked();
+        });
+        ui.add(egui::Slider::new(&mut self.hdri_intensity, 0.0..=4.0).text("Environment intensity"));
+
+        ui.separator();
+        ui.add(egui::Slider::new(&mut self.focus_distance, 0.1..=50.0).logarithmic(true).text("Focus distance"));
+        ui.add(egui::Slider::new(&mut self.aperture, 0.0..=0.5).text("Aperture"))
+            .on_hover_text("Depth of field blur; spread over the anti-aliasing samples, so large values look steppy and 1x SSAA shows none");
+
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut self.tint_inside, "Tint inside surfaces")
+                .on_hover_text("Color surfaces seen from inside a solid, e.g. when the camera flies into a wall");
+            ui.add_enabled_ui(self.tint_inside, |ui| ui.color_edit_button_rgb(&mut self.inside_tint));
+        });
+
+        ui.separator();
+        ui.checkbox(&mut self.show_ids, "Color by primitive")
+            .on_hover_text("Give every primitive its own color; a subtract's cut faces take the cutter's color");
+        ui.checkbox(&mut self.show_bounds, "Primitive bounds")
+            .on_hover_text("Outline each primitive's bounding box after its transforms, to see how the parts of a boolean assembly sit");
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut self.outlines, "Outlines")
+                .on_hover_text("Draw dark lines along silhouettes and sharp creases, for a toon or blueprint look");
+            ui.add_enabled_ui(self.outlines, |ui| {
+                ui.add(egui::DragValue::new(&mut self.outline_crease_deg).range(1.0..=120.0).speed(0.5).suffix("°").prefix("crease "))
+                    .on_hover_text("Outline edges where the surface turns by more than this angle");
+                ui.add(egui::DragValue::new(&mut self.outline_depth).range(0.005..=1.0).speed(0.005).prefix("depth "))
+                    .on_hover_text("Outline where the distance to the surface jumps by more than this fraction, e.g. at silhouettes");
+            });
+        });
+        ui.checkbox(&mut self.slice_view, "Distance slice view")
+            .on_hover_text("Show the signed distance on a plane: red inside, blue outside, white at the surface");
+        ui.add_enabled_ui(self.slice_view, |ui| {
+            ui.horizontal(|ui| {
+                ui.label("Plane normal:");
+                ui.selectable_value(&mut self.slice_axis, SliceAxis::X, "X");
+                ui.selectable_value(&mut self.slice_axis, SliceAxis::Y, "Y");
+                ui.selectable_value(&mut self.slice_axis, SliceAxis::Z, "Z");
+            });
+            ui.add(egui::DragValue::new(&mut self.slice_offset).speed(0.02).prefix("Offset: "));
+            ui.checkbox(&mut self.slice_legend, "Show legend");
+        });
+
+        ui.separator();
+        ui.checkbox(&mut self.template_from_disk, "Load shader template from disk")
+            .on_hover_text("Developer mode: re-read src/shader_template.wgsl on each compile instead of the built-in copy");
+        load_env
+    }
+
+    /// The settings that decide how a scene looks (sampling, marching, lighting, environment,
+    /// lens, output and up axis) as JSON, for `scene_settings_path`. Viewer preferences, debug
+    /// views and the exploded view are left out.
+    pub fn look_json(&self) -> String {
+        let float = |v: f32| Dynamic::from_float(v);
+        let rgb = |c: [f32; 3]| Dynamic::from_array(c.iter().map(|v| Dynamic::from_float(*v)).collect());
+        let mut map = Map::new();
+        map.insert("ssaa_samples".into(), Dynamic::from_int(self.ssaa_samples as i64));
+        map.insert("auto_ssaa".into(), Dynamic::from_bool(self.auto_ssaa));
+        map.insert("target_fps".into(), float(self.target_fps));
+        map.insert("aa_pattern".into(), Dynamic::from(match self.aa_pattern {
+            AaPattern::Grid => "grid",
+            AaPattern::RotatedGrid => "rotated_grid",
+            AaPattern::Halton => "halton",
+        }.to_string()));
+        map.insert("march_method".into(), Dynamic::from(match self.march_method {
+            MarchMethod::Sphere => "sphere",
+            MarchMethod::Relaxed => "relaxed",
+        }.to_string()));
+        map.insert("march_near".into(), float(self.march_near));
+        map.insert("march_far".into(), float(self.march_far));
+        map.insert("global_smooth".into(), float(self.global_smooth));
+        map.insert("z_up".into(), Dynamic::from_bool(self.z_up));
+        map.insert("background".into(), rgb(self.background));
+        map.insert("light_dir".into(), rgb(self.light_dir));
+        map.insert("headlight".into(), Dynamic::from_bool(self.headlight));
+        map.insert("ambient_color".into(), rgb(self.ambient_color));
+        map.insert("ambient_intensity".into(), float(self.ambient_intensity));
+        map.insert("gamma_correct".into(), Dynamic::from_bool(self.gamma_correct));
+        map.insert("hdri_path".into(), Dynamic::from(self.hdri_path.clone()));
+        map.insert("hdri_intensity".into(), float(self.hdri_intensity));
+        map.insert("focus_distance".into(), float(self.focus_distance));
+        map.insert("aperture".into(), float(self.aperture));
+        map.insert("tint_inside".into(), Dynamic::from_bool(self.tint_inside));
+        map.insert("inside_tint".into(), rgb(self.inside_tint));
+        map.insert("outlines".into(), Dynamic::from_bool(self.outlines));
+        map.insert("outline_crease_deg".into(), float(self.outline_crease_deg));
+        map.insert("outline_depth".into(), float(self.outline_depth));
+        rhai::format_map_as_json(&map)
+    }
+
+    /// Takes the fields present in `look`, as written by `look_json` and parsed with
+    /// `Engine::parse_json`; unknown keys are ignored. Changes nothing if any field is invalid.
+    pub fn apply_look(&mut self, look: &Map) -> Result<(), String> {
+        let mut out = self.clone();
+        for (key, value) in look {
+            let bad = || format!("\"{key}\" has an invalid value {value}");
+            let float = || json_float(value).ok_or_else(bad);
+            let flag = || value.as_bool().map_err(|_| bad());
+            let rgb = || json_rgb(value).ok_or_else(bad);
+            let text = || value.clone().into_string().map_err(|_| bad());
+            match key.as_str() {
+                "ssaa_samples" => out.ssaa_samples = value.as_int().ok().filter(|n| [1, 2, 4].contains(n)).ok_or_else(bad)? as u32,
+                "auto_ssaa" => out.auto_ssaa = flag()?,
+                "target_fps" => out.target_fps = float()?.clamp(10.0, 240.0),
+                "aa_pattern" => out.aa_pattern = match text()?.as_str() {
+                    "grid" => AaPattern::Grid,
+                    "rotated_grid" => AaPattern::RotatedGrid,
+                    "halton" => AaPattern::Halton,
+                    _ => return Err(bad()),
+                },
+                "march_method" => out.march_method = match text()?.as_str() {
+                    "sphere" => MarchMethod::Sphere,
+                    "relaxed" => MarchMethod::Relaxed,
+                    _ => return Err(bad()),
+                },
+                "march_near" => out.march_near = float()?.max(0.0),
+                "march_far" => out.march_far = float()?.clamp(0.1, 1000.0),
+                "global_smooth" => out.global_smooth = float()?.clamp(0.0, 0.5),
+                "z_up" => out.z_up = flag()?,
+                "background" => out.background = rgb()?,
+                "light_dir" => out.light_dir = rgb()?,
+                "headlight" => out.headlight = flag()?,
+                "ambient_color" => out.ambient_color = rgb()?,
+                "ambient_intensity" => out.ambient_intensity = float()?.max(0.0),
+                "gamma_correct" => out.gamma_correct = flag()?,
+                "hdri_path" => out.hdri_path = text()?,
+                "hdri_intensity" => out.hdri_intensity = float()?.max(0.0),
+                "focus_distance" => out.focus_distance = float()?.max(0.1),
+                "aperture" => out.aperture = float()?.max(0.0),
+                "tint_inside" => out.tint_inside = flag()?,
+                "inside_tint" => out.inside_tint = rgb()?,
+                "outlines" => out.outlines = flag()?,
+                "outline_crease_deg" => out.outline_crease_deg = float()?,
+                "outline_depth" => out.outline_depth = float()?,
+                _ => {}
+            }
+        }
+        out.march_near = out.march_near.min(out.march_far);
+        *self = out;
+        Ok(())
+    }
+}
+
+/// Where a scene's saved look lives: `settings.json` in a project folder, or
+/// `<name>.settings.json` next to a single script.
+pub fn scene_settings_path(script: &Path) -> PathBuf {
+    if script.is_dir() { script.join("settings.json") } else { script.with_extension("settings.json") }
+}
+
+/// A JSON number; whole numbers parse as integers.
+fn json_float(value: &Dynamic) -> Option<f32> {
+    value.as_float().ok().or_else(|| value.as_int().ok().map(|n| n as f32)).filter(|v| v.is_finite())
+}
+
+fn json_rgb(value: &Dynamic) -> Option<[f32; 3]> {
+    let values: Option<Vec<f32>> = value.clone().try_cast::<Array>()?.iter().map(json_float).collect();
+    values?.try_into().ok()
+}
+
+/// Trackball-style light picker: a disc showing the sky from above (+X right, +Z down, up out of
+/// the screen). Dragging puts the light over the pointer, on the upper hemisphere.
+fn light_gizmo(ui: &mut egui::Ui, dir: &mut [f32; 3]) -> egui::Response {
+    let (rect, mut response) = ui.allocate_exact_size(egui::vec2(80.0, 80.0), egui::Sense::click_and_drag());
+    let radius = rect.width() * 0.5 - 2.0;
+    if let Some(pos) = response.interact_pointer_pos() {
+        let d = (pos - rect.center()) / radius;
+        let d = if d.length() > 1.0 { d.normalized() } else { d };
+        let up = (1.0 - d.length_sq()).max(0.0).sqrt();
+        let new_dir = glam::Vec3::new(d.x, up, d.y).normalize_or(glam::Vec3::Y).into();
+        if *dir != new_dir {
+            *dir = new_dir;
+            response.mark_changed();
+        }
+    }
+
+    let painter = ui.painter_at(rect);
+    let visuals = ui.style().interact(&response);
+    painter.circle(rect.center(), radius, ui.visuals().extreme_bg_color, visuals.fg_stroke);
+    painter.circle_stroke(rect.center(), radius * 0.5, egui::Stroke::new(1.0, ui.visuals().weak_text_color()));
+    // Lights below the horizon show as a hollow marker at their position mirrored upwards
+    let shown = glam::Vec3::from(*dir).normalize_or(glam::Vec3::Y);
+    let marker = rect.center() + egui::vec2(shown.x, shown.z) * radius;
+    let color = egui::Color32::from_rgb(255, 210, 80);
+    if shown.y >= 0.0 {
+        painter.circle_filled(marker, 5.0, color);
+    } else {
+        painter.circle_stroke(marker, 5.0, egui::Stroke::new(1.5, color));
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn look_survives_a_json_round_trip() {
+        let mut saved = SdfSettings { ssaa_samples: 2, aa_pattern: AaPattern::Halton, march_method: MarchMethod::Relaxed, ..SdfSettings::default() };
+        saved.background = [0.5, 0.25, 1.0];
+        saved.hdri_path = "env/studio.hdr".to_string();
+        saved.aperture = 0.125;
+        saved.z_up = true;
+        let look = rhai::Engine::new().parse_json(saved.look_json(), true).unwrap();
+
+        let mut loaded = SdfSettings { slice_view: true, ..SdfSettings::default() };
+        loaded.apply_look(&look).unwrap();
+        assert_eq!(loaded.look_json(), saved.look_json());
+        // Debug views are the viewer's own
+        assert!(loaded.slice_view);
+    }
+
+    #[test]
+    fn invalid_look_changes_nothing() {
+        let look = rhai::Engine::new().parse_json(r#"{ "aperture": 0.3, "ssaa_samples": 3 }"#, true).unwrap();
+        let mut settings = SdfSettings::default();
+        assert!(settings.apply_look(&look).is_err());
+        assert_eq!(settings.aperture, 0.0);
+    }
+}
@@ -1,15 +1,63 @@
 struct Uniforms {
     rect_data: vec4<f32>,
-    time_data: vec4<f32>,
+    time_data: vec4<f32>, // time, SSAA samples (1, 2 or 4), material-ID view (1 = on), Z up (1 = on)
     cam_pos: vec4<f32>,
     cam_right: vec4<f32>,
     cam_up: vec4<f32>,
     cam_front: vec4<f32>,
+    env_data: vec4<f32>, // enabled, intensity, 0, 0
+    dof_data: vec4<f32>, // focus distance, aperture radius, 0, 0
+    bg_color: vec4<f32>, // miss color when no environment map is loaded
+    slice_plane: vec4<f32>, // debug slice: normal (zero = off), offset
+    inside_color: vec4<f32>, // tint for surfaces seen from inside a solid; w = enabled
+    light_dir: vec4<f32>, // towards the directional key light, Y-up frame (see to_y_up)
+    output_data: vec4<f32>, // x: sRGB-encode the result (1 = on; off when the target does it), y: outlines on, z: crease threshold (1 - cos), w: relative depth threshold
+    ambient: vec4<f32>, // fill light color times intensity when no environment map is loaded
 };
 
 @group(0) @binding(0)
 var<uniform> uniforms: Uniforms;
 
+// Equirectangular HDR environment (1x1 black when none is loaded)
+@group(0) @binding(1)
+var env_tex: texture_2d<f32>;
+@group(0) @binding(2)
+var env_sampler: sampler;
+
+// Height maps of `displace_map`, one layer each (a single blank layer when none are used).
+// The sampler repeats, so maps tile.
+@group(0) @binding(3)
+var height_maps: texture_2d_array<f32>;
+@group(0) @binding(4)
+var height_sampler: sampler;
+
+// Signed distance grids of `mesh`, stacked along Z (a single texel when none are used). R32Float
+// is not filterable everywhere, so `sd_mesh_volume` filters with textureLoad.
+@group(0) @binding(5)
+var mesh_volumes: texture_3d<f32>;
+
+// The viewport conventions (sky, grid, light) are written for Y up; with the Z-up setting
+// (time_data.w) world vectors are rotated into that frame first.
+fn to_y_up(v: vec3<f32>) -> vec3<f32> {
+    return select(v, vec3<f32>(v.x, v.z, -v.y), uniforms.time_data.w > 0.5);
+}
+
+fn from_y_up(v: vec3<f32>) -> vec3<f32> {
+    return select(v, vec3<f32>(v.x, -v.z, v.y), uniforms.time_data.w > 0.5);
+}
+
+fn sample_env(world_dir: vec3<f32>, lod: f32) -> vec3<f32> {
+    let dir = to_y_up(world_dir);
+    let uv = vec2<f32>(atan2(dir.z, dir.x) / 6.2831853 + 0.5, acos(clamp(dir.y, -1.0, 1.0)) / 3.1415927);
+    return textureSampleLevel(env_tex, env_sampler, uv, lod).rgb * uniforms.env_data.y;
+}
+
+// Heavily blurred lookup used as image-based ambient light
+fn sample_env_ambient(dir: vec3<f32>) -> vec3<f32> {
+    let lod = max(f32(textureNumLevels(env_tex)) - 4.0, 0.0);
+    return sample_env(dir, lod);
+}
+
 // --- SDF Primitives ---
 
 fn sd_sphere(p: vec3<f32>, s: f32) -> f32 {
@@ -31,29 +79,187 @@ fn sd_torus(p: vec3<f32>, t: vec2<f32>) -> f32 {
     return length(q) - t.y;
 }
 
+// Spherical shell of radius `r` cut open at height `h` (opening facing +Y), wall half-thickness `t`.
+// Exact; the rim is rounded.
+fn sd_cut_hollow_sphere(p: vec3<f32>, r: f32, h: f32, t: f32) -> f32 {
+    let w = sqrt(r * r - h * h);
+    let q = vec2<f32>(length(p.xz), p.y);
+    return select(abs(length(q) - r), length(q - vec2<f32>(w, h)), h * q.x < w * q.y) - t;
+}
+
+// Ramp: the box `b` (half extents) cut diagonally, rising from y = -b.y at x = -b.x to y = b.y
+// at x = b.x. Box/plane intersection, so a bound rather than exact outside the sloped edges.
+fn sd_wedge(p: vec3<f32>, b: vec3<f32>) -> f32 {
+    let slope = (p.y * b.x - p.x * b.y) / length(b.xy);
+    return max(sd_box(p, b), slope);
+}
+
+// Superellipsoid with semi-axes `r`; `e1` shapes the vertical profile, `e2` the horizontal one
+// (1 = ellipsoid, towards 0 = box, 2 = octahedron). The implicit form is not a distance: this
+// rescales it to roughly unit gradient, so the raymarcher takes shortened steps.
+fn sd_superellipsoid(p: vec3<f32>, r: vec3<f32>, e1: f32, e2: f32) -> f32 {
+    let q = max(abs(p / r), vec3<f32>(1e-6));
+    let f = pow(pow(q.x, 2.0 / e2) + pow(q.z, 2.0 / e2), e2 / e1) + pow(q.y, 2.0 / e1);
+    return (pow(f, e1 * 0.5) - 1.0) * min(r.x, min(r.y, r.z));
+}
+
+// Segment from `a` to `b` thickened by `r`; a zero-length segment is a sphere
+fn sd_capsule(p: vec3<f32>, a: vec3<f32>, b: vec3<f32>, r: f32) -> f32 {
+    let pa = p - a;
+    let ba = b - a;
+    let h = clamp(dot(pa, ba) / max(dot(ba, ba), 1e-12), 0.0, 1.0);
+    return length(pa - ba * h) - r;
+}
+
+fn dot2(v: vec3<f32>) -> f32 { return dot(v, v); }
+
+// Unsigned distance to triangle abc
+fn ud_triangle(p: vec3<f32>, a: vec3<f32>, b: vec3<f32>, c: vec3<f32>) -> f32 {
+    let ba = b - a; let pa = p - a;
+    let cb = c - b; let pb = p - b;
+    let ac = a - c; let pc = p - c;
+    let nor = cross(ba, ac);
+    let outside_prism = sign(dot(cross(ba, nor), pa)) + sign(dot(cross(cb, nor), pb)) + sign(dot(cross(ac, nor), pc)) < 2.0;
+    let edges = min(min(
+        dot2(ba * clamp(dot(ba, pa) / dot2(ba), 0.0, 1.0) - pa),
+        dot2(cb * clamp(dot(cb, pb) / dot2(cb), 0.0, 1.0) - pb)),
+        dot2(ac * clamp(dot(ac, pc) / dot2(ac), 0.0, 1.0) - pc));
+    let face = dot(nor, pa) * dot(nor, pa) / dot2(nor);
+    return sqrt(select(face, edges, outside_prism));
+}
+
+// Regular tetrahedron with circumradius `s`, centered on the origin with one vertex up (+Y). Exact.
+fn sd_tetrahedron(p: vec3<f32>, s: f32) -> f32 {
+    let rb = s * 0.9428090; // sqrt(8) / 3
+    let v0 = vec3<f32>(0.0, s, 0.0);
+    let v1 = vec3<f32>(0.0, -s / 3.0, rb);
+    let v2 = vec3<f32>(rb * 0.8660254, -s / 3.0, -rb * 0.5);
+    let v3 = vec3<f32>(-rb * 0.8660254, -s / 3.0, -rb * 0.5);
+    let d = min(min(ud_triangle(p, v0, v1, v2), ud_triangle(p, v0, v2, v3)),
+                min(ud_triangle(p, v0, v3, v1), ud_triangle(p, v1, v3, v2)));
+    // Each face's outward normal points away from the opposite vertex
+    let planes = max(max(dot(p, -v0), dot(p, -v1)), max(dot(p, -v2), dot(p, -v3))) / s - s / 3.0;
+    return select(-d, d, planes > 0.0);
+}
+
+// Unbounded cylinder around the unit `axis` through the origin
+fn sd_inf_cylinder(p: vec3<f32>, r: f32, axis: vec3<f32>) -> f32 {
+    return length(p - axis * dot(p, axis)) - r;
+}
+
+// Unbounded cone, apex at the origin, opening along the unit `axis`; `c` is (sin, cos) of the
+// half-angle. Exact, including the region behind the apex.
+fn sd_inf_cone(p: vec3<f32>, c: vec2<f32>, axis: vec3<f32>) -> f32 {
+    let h = dot(p, axis);
+    let q = vec2<f32>(length(p - axis * h), h);
+    let d = length(q - c * max(dot(q, c), 0.0));
+    return select(d, -d, q.x * c.y - q.y * c.x < 0.0);
+}
+
+// Coil along Y, centered on the origin, `pitch * turns` tall. Not an exact distance: the tube
+// cross-section is measured in the unrolled helix plane, which is close for slender coils.
+fn sd_helix(p: vec3<f32>, radius: f32, pitch: f32, thickness: f32, turns: f32) -> f32 {
+    let half_h = 0.5 * pitch * turns;
+    let a = fract(atan2(p.z, p.x) / 6.2831853 + 1.0);
+    // Nearest turn at this angle that lies within the coil
+    let k_max = floor(turns - a);
+    let k = clamp(round((p.y + half_h) / pitch - a), 0.0, max(k_max, 0.0));
+    let dy = p.y - (pitch * (a + k) - half_h);
+    let cos_slope = 6.2831853 * radius / length(vec2<f32>(6.2831853 * radius, pitch));
+    let body = select(1e10, length(vec2<f32>(length(p.xz) - radius, dy * cos_slope)), k_max >= 0.0);
+    // Rounded end caps
+    let end_angle = 6.2831853 * turns;
+    let start = vec3<f32>(radius, -half_h, 0.0);
+    let end = vec3<f32>(radius * cos(end_angle), half_h, radius * sin(end_angle));
+    return min(body, min(length(p - start), length(p - end))) - thickness;
+}
+
+// Mandelbulb distance estimator (escape radius 2). Approximate and expensive: every map() call
+// runs up to `iterations` rounds of the power-`power` spherical iteration.
+fn sd_mandelbulb(p: vec3<f32>, power: f32, iterations: u32) -> f32 {
+    var z = p;
+    var dr = 1.0;
+    var r = length(z);
+    for (var i = 0u; i < iterations; i++) {
+        r = max(length(z), 1e-6);
+        if (r > 2.0) { break; }
+        let theta = acos(clamp(z.y / r, -1.0, 1.0)) * power;
+        let phi = atan2(z.z, z.x) * power;
+        dr = pow(r, power - 1.0) * power * dr + 1.0;
+        z = pow(r, power) * vec3<f32>(sin(theta) * cos(phi), cos(theta), sin(theta) * sin(phi)) + p;
+    }
+    r = max(length(z), 1e-6);
+    return 0.5 * log(r) * r / dr;
+}
+
+// Baked mesh whose `res` grid points start at `origin`, `cell` apart, stored from slice `z0` of
+// `mesh_volumes`; trilinear like `MeshVolume::sample`. Outside the grid: the distance to the
+// grid box shrunk by its two padding cells, which encloses the surface.
+fn sd_mesh_volume(p: vec3<f32>, origin: vec3<f32>, cell: f32, res: vec3<i32>, z0: i32) -> f32 {
+    let g = (p - origin) / cell;
+    let top = vec3<f32>(res - 1);
+    if (any(g < vec3<f32>(0.0)) || any(g > top)) {
+        let half = top * cell * 0.5;
+        return sd_box(p - origin - half, half - 2.0 * cell);
+    }
+    let i = min(vec3<i32>(floor(g)), res - 2);
+    let f = g - vec3<f32>(i);
+    let base = i + vec3<i32>(0, 0, z0);
+    let x00 = mix(textureLoad(mesh_volumes, base, 0).r, textureLoad(mesh_volumes, base + vec3<i32>(1, 0, 0), 0).r, f.x);
+    let x10 = mix(textureLoad(mesh_volumes, base + vec3<i32>(0, 1, 0), 0).r, textureLoad(mesh_volumes, base + vec3<i32>(1, 1, 0), 0).r, f.x);
+    let x01 = mix(textureLoad(mesh_volumes, base + vec3<i32>(0, 0, 1), 0).r, textureLoad(mesh_volumes, base + vec3<i32>(1, 0, 1), 0).r, f.x);
+    let x11 = mix(textureLoad(mesh_volumes, base + vec3<i32>(0, 1, 1), 0).r, textureLoad(mesh_volumes, base + vec3<i32>(1, 1, 1), 0).r, f.x);
+    return mix(mix(x00, x10, f.y), mix(x01, x11, f.y), f.z);
+}
+
 // --- Result & Material Helpers ---
 
+// `inside` is the color shown where a subtraction cuts into the shape (see op_subtract).
+// `coat` is the clearcoat layer as (strength, roughness); zero means no coat.
+// `id` is the leaf primitive that produced the surface, numbered in AST order by the generator
+// (see set_id) and shown by the material-ID debug view.
+fn sdf_result(d: f32, col: vec3<f32>) -> SdfResult {
+    return SdfResult(d, col, col, vec2<f32>(0.0), 0.0);
+}
+
+// Whether the generated `bounded_*` functions may return the distance to a bounding box far from
+// their subtree. The slice view shows the field itself, so it needs the real values everywhere.
+fn bounds_skip_allowed() -> bool {
+    return dot(uniforms.slice_plane.xyz, uniforms.slice_plane.xyz) == 0.0;
+}
+
+fn set_id(res: SdfResult, id: f32) -> SdfResult {
+    var out = res;
+    out.id = id;
+    return out;
+}
+
 fn op_union(a: SdfResult, b: SdfResult) -> SdfResult {
     if (a.dist < b.dist) { return a; }
     return b;
 }
 
+// Material attributes blend with the same factor `h` as the distance, so colors fade across
+// the fillet instead of meeting at a seam.
 fn op_union_smooth(a: SdfResult, b: SdfResult, k: f32) -> SdfResult {
     let h = clamp(0.5 + 0.5 * (b.dist - a.dist) / k, 0.0, 1.0);
     let d = mix(b.dist, a.dist, h) - k * h * (1.0 - h);
     let col = mix(b.color, a.color, h);
-    return SdfResult(d, col);
+    let inside = mix(b.inside, a.inside, h);
+    return SdfResult(d, col, inside, mix(b.coat, a.coat, h), select(b.id, a.id, h > 0.5));
 }
 
+// Where the cutter's surface wins, the hit is on the revealed interior of `a` (in `a`'s inside
+// color, but with the cutter's id).
 fn op_subtract(a: SdfResult, b: SdfResult) -> SdfResult {
-    let d = max(a.dist, -b.dist);
-    return SdfResult(d, a.color);
+    if (-b.dist > a.dist) { return SdfResult(-b.dist, a.inside, a.inside, a.coat, b.id); }
+    return a;
 }
 
 fn op_subtract_smooth(a: SdfResult, b: SdfResult, k: f32) -> SdfResult {
     let h = clamp(0.5 - 0.5 * (b.dist + a.dist) / k, 0.0, 1.0);
     let d = mix(a.dist, -b.dist, h) + k * h * (1.0 - h);
-    return SdfResult(d, a.color);
+    return SdfResult(d, mix(a.color, a.inside, h), a.inside, a.coat, select(a.id, b.id, h > 0.5));
 }
 
 fn op_intersect(a: SdfResult, b: SdfResult) -> SdfResult {
@@ -61,14 +267,219 @@ fn op_intersect(a: SdfResult, b: SdfResult) -> SdfResult {
     return b;
 }
 
+fn op_intersect_smooth(a: SdfResult, b: SdfResult, k: f32) -> SdfResult {
+    let h = clamp(0.5 - 0.5 * (b.dist - a.dist) / k, 0.0, 1.0);
+    let d = mix(b.dist, a.dist, h) + k * h * (1.0 - h);
+    let col = mix(b.color, a.color, h);
+    let inside = mix(b.inside, a.inside, h);
+    return SdfResult(d, col, inside, mix(b.coat, a.coat, h), select(b.id, a.id, h > 0.5));
+}
+
+// Exponential smooth minimum (log-sum-exp, shifted by the minimum so it cannot overflow).
+// Returns the distance and the weight of `a`. Smooth max is -smin_exp(-a, -b).
+fn smin_exp(a: f32, b: f32, k: f32) -> vec2<f32> {
+    let m = min(a, b);
+    let ea = exp2((m - a) / k);
+    let eb = exp2((m - b) / k);
+    return vec2<f32>(m - k * log2(ea + eb), ea / (ea + eb));
+}
+
+fn op_union_smooth_exp(a: SdfResult, b: SdfResult, k: f32) -> SdfResult {
+    let s = smin_exp(a.dist, b.dist, k);
+    return SdfResult(s.x, mix(b.color, a.color, s.y), mix(b.inside, a.inside, s.y), mix(b.coat, a.coat, s.y), select(b.id, a.id, s.y > 0.5));
+}
+
+fn op_subtract_smooth_exp(a: SdfResult, b: SdfResult, k: f32) -> SdfResult {
+    let s = smin_exp(-a.dist, b.dist, k);
+    return SdfResult(-s.x, mix(a.inside, a.color, s.y), a.inside, a.coat, select(b.id, a.id, s.y > 0.5));
+}
+
+fn op_intersect_smooth_exp(a: SdfResult, b: SdfResult, k: f32) -> SdfResult {
+    let s = smin_exp(-a.dist, -b.dist, k);
+    return SdfResult(-s.x, mix(b.color, a.color, s.y), mix(b.inside, a.inside, s.y), mix(b.coat, a.coat, s.y), select(b.id, a.id, s.y > 0.5));
+}
+
+// `smooth_group`: the exponential smooth minimum folded over any number of operands. The sum of
+// exp2(-d / k) is kept relative to the smallest distance so far (rescaled when it drops), along
+// with the attributes weighted the same way, so the result does not depend on operand order.
+struct SmoothGroup {
+    m: f32,
+    sum: f32,
+    color: vec3<f32>,
+    inside: vec3<f32>,
+    coat: vec2<f32>,
+    id: f32,
+}
+
+fn smooth_group_start(r: SdfResult) -> SmoothGroup {
+    return SmoothGroup(r.dist, 1.0, r.color, r.inside, r.coat, r.id);
+}
+
+fn smooth_group_add(g: SmoothGroup, r: SdfResult, k: f32) -> SmoothGroup {
+    let m = min(g.m, r.dist);
+    let sg = exp2((m - g.m) / k);
+    let sr = exp2((m - r.dist) / k);
+    return SmoothGroup(m, g.sum * sg + sr, g.color * sg + r.color * sr, g.inside * sg + r.inside * sr,
+        g.coat * sg + r.coat * sr, select(g.id, r.id, r.dist < g.m));
+}
+
+fn smooth_group_end(g: SmoothGroup, k: f32) -> SdfResult {
+    return SdfResult(g.m - k * log2(g.sum), g.color / g.sum, g.inside / g.sum, g.coat / g.sum, g.id);
+}
+
+// `volume`: what the volume pass needs at a point, the distance to the nearest boundary of any
+// volume (the extinction is constant up to there) and the summed extinction of those containing it
+struct VolumeSample {
+    edge: f32,
+    extinction: vec3<f32>,
+}
+
+fn volume_empty() -> VolumeSample {
+    return VolumeSample(1e10, vec3<f32>(0.0));
+}
+
+fn volume_sample(d: f32, extinction: vec3<f32>) -> VolumeSample {
+    return VolumeSample(abs(d), select(vec3<f32>(0.0), extinction, d < 0.0));
+}
+
+fn op_volume_union(a: VolumeSample, b: VolumeSample) -> VolumeSample {
+    return VolumeSample(min(a.edge, b.edge), a.extinction + b.extinction);
+}
+
+fn op_volume_scale(v: VolumeSample, s: f32) -> VolumeSample {
+    return VolumeSample(v.edge * s, v.extinction);
+}
+
+fn op_scale(res: SdfResult, s: f32) -> SdfResult {
+    var out = res;
+    out.dist = res.dist * s;
+    return out;
+}
+
+fn op_offset(res: SdfResult, r: f32) -> SdfResult {
+    var out = res;
+    out.dist = res.dist - r;
+    return out;
+}
+
+// iq's groove: max(d, min(d + depth, width - |cutter|)). The channel is the slab within
+// `width` of the cutter's surface, floored `depth` below the target's own surface, so it follows
+// curved targets. Its walls show the inside color, as with subtract.
+fn op_groove(res: SdfResult, cutter: f32, depth: f32, width: f32) -> SdfResult {
+    let channel = min(res.dist + depth, width - abs(cutter));
+    if (channel > res.dist) { return SdfResult(channel, res.inside, res.inside, res.coat, res.id); }
+    return res;
+}
+
+// iq's pipe: a tube of radius `r` around the curve where both surfaces are zero
+fn op_pipe(a: SdfResult, b: SdfResult, r: f32) -> SdfResult {
+    var out = a;
+    out.dist = length(vec2<f32>(a.dist, b.dist)) - r;
+    return out;
+}
+
+fn op_annular(res: SdfResult, thickness: f32) -> SdfResult {
+    var out = res;
+    out.dist = abs(res.dist) - thickness;
+    return out;
+}
+
+// Shells `spacing / 4` thick around the surface and its first `count - 1` inward offsets
+fn op_onion_rings(res: SdfResult, count: f32, spacing: f32) -> SdfResult {
+    var out = res;
+    let k = clamp(round(-res.dist / spacing), 0.0, count - 1.0);
+    out.dist = abs(res.dist + k * spacing) - 0.25 * spacing;
+    return out;
+}
+
 fn set_color(res: SdfResult, col: vec3<f32>) -> SdfResult {
     var out = res;
     out.color = col;
+    out.inside = col;
+    return out;
+}
+
+fn set_inside_color(res: SdfResult, col: vec3<f32>) -> SdfResult {
+    var out = res;
+    out.inside = col;
+    return out;
+}
+
+fn set_clearcoat(res: SdfResult, strength: f32, roughness: f32) -> SdfResult {
+    var out = res;
+    out.coat = vec2<f32>(strength, roughness);
     return out;
 }
 
+// GGX lobe of the clearcoat layer, with the Kelemen visibility term and a fixed 4% F0 (varnish)
+fn clearcoat_specular(n: vec3<f32>, v: vec3<f32>, l: vec3<f32>, roughness: f32) -> f32 {
+    let h = normalize(v + l);
+    let a = max(roughness * roughness, 0.002);
+    let a2 = a * a;
+    let nh = max(dot(n, h), 0.0);
+    let denom = nh * nh * (a2 - 1.0) + 1.0;
+    let d = a2 / (3.1415927 * denom * denom);
+    let vh = max(dot(v, h), 1e-3);
+    let f = 0.04 + 0.96 * pow(1.0 - vh, 5.0);
+    return d * f * 0.25 / (vh * vh) * max(dot(n, l), 0.0);
+}
+
+// --- Noise ---
+
+// Integer lattice hash in [-1, 1]; integer-only so the CPU evaluator can match it bit for bit
+fn hash3(c: vec3<i32>) -> f32 {
+    let u = bitcast<vec3<u32>>(c);
+    var h = (u.x * 73856093u) ^ (u.y * 19349663u) ^ (u.z * 83492791u);
+    h = h * 747796405u + 2891336453u;
+    var w = ((h >> ((h >> 28u) + 4u)) ^ h) * 277803737u;
+    w = (w >> 22u) ^ w;
+    return f32(w) / 4294967295.0 * 2.0 - 1.0;
+}
+
+// Smooth value noise in [-1, 1]
+fn value_noise(p: vec3<f32>) -> f32 {
+    let i = vec3<i32>(floor(p));
+    let f = fract(p);
+    let u = f * f * (3.0 - 2.0 * f);
+    let x00 = mix(hash3(i), hash3(i + vec3<i32>(1, 0, 0)), u.x);
+    let x10 = mix(hash3(i + vec3<i32>(0, 1, 0)), hash3(i + vec3<i32>(1, 1, 0)), u.x);
+    let x01 = mix(hash3(i + vec3<i32>(0, 0, 1)), hash3(i + vec3<i32>(1, 0, 1)), u.x);
+    let x11 = mix(hash3(i + vec3<i32>(0, 1, 1)), hash3(i + vec3<i32>(1, 1, 1)), u.x);
+    return mix(mix(x00, x10, u.y), mix(x01, x11, u.y), u.z);
+}
+
 // --- Transforms ---
 
+// Moves the sample point by a noise vector; the three lookups are offset to decorrelate them
+fn op_warp(p: vec3<f32>, amp: f32, freq: f32) -> vec3<f32> {
+    let q = p * freq;
+    let w = vec3<f32>(
+        value_noise(q),
+        value_noise(q + vec3<f32>(31.7, 11.3, 47.1)),
+        value_noise(q + vec3<f32>(-23.9, 57.5, 5.3)),
+    );
+    return p + amp * w;
+}
+
+// Reflects points behind the plane dot(p, n) = d onto its front; the field stays exact and
+// the normals of the reflected half follow from the gradient like anywhere else
+fn op_mirror_plane(p: vec3<f32>, n: vec3<f32>, d: f32) -> vec3<f32> {
+    return p - 2.0 * min(dot(p, n) - d, 0.0) * n;
+}
+
+// Triplanar height map displacement at the scaled point `q`; `n` is the target's (unnormalized)
+// gradient. Sampled at level 0 since `map` runs in non-uniform control flow.
+fn op_displace_map(res: SdfResult, q: vec3<f32>, n: vec3<f32>, layer: i32, amplitude: f32) -> SdfResult {
+    var w = pow(abs(n), vec3<f32>(4.0));
+    w = w / max(w.x + w.y + w.z, 1e-6);
+    let h = textureSampleLevel(height_maps, height_sampler, q.yz, layer, 0.0).r * w.x
+        + textureSampleLevel(height_maps, height_sampler, q.xz, layer, 0.0).r * w.y
+        + textureSampleLevel(height_maps, height_sampler, q.xy, layer, 0.0).r * w.z;
+    var out = res;
+    out.dist = res.dist + amplitude * (h - 0.5);
+    return out;
+}
+
 fn rotate_x(p: vec3<f32>, angle: f32) -> vec3<f32> {
     let c = cos(angle); let s = sin(angle);
     return vec3<f32>(p.x, c * p.y - s * p.z, s * p.y + c * p.z);
@@ -95,24 +506,69 @@ fn calc_normal(p: vec3<f32>) -> vec3<f32> {
     ));
 }
 
-fn ray_march(ro: vec3<f32>, rd: vec3<f32>) -> SdfResult {
-    var t = 0.0;
-    var res = SdfResult(100.0, vec3<f32>(0.0));
+// Distinct, stable color per primitive id for the material-ID debug view (golden-ratio hue steps
+// through a cosine palette, so neighboring ids differ clearly)
+fn id_color(id: f32) -> vec3<f32> {
+    let hue = fract(id * 0.618034);
+    return 0.5 + 0.5 * cos(6.2832 * (hue + vec3<f32>(0.0, 0.33, 0.67)));
+}
+
+// With `inside` set the ray starts within a solid and marches on the negated field, stopping
+// where it leaves the solid.
+// MARCH_RELAXATION above 1 selects over-relaxed sphere tracing (Keinert et al. 2014): steps are
+// lengthened, and when the distance spheres at the last two points stop overlapping (or the
+// point ends up inside) the ray retreats to a plain step from the previous point and stays plain.
+fn ray_march(ro: vec3<f32>, rd: vec3<f32>, inside: bool) -> SdfResult {
+    // MARCH_NEAR / MARCH_FAR / MARCH_RELAXATION are baked in from the viewport settings
+    var t = MARCH_NEAR;
+    var res = sdf_result(MARCH_FAR, vec3<f32>(0.0));
+    let side = select(1.0, -1.0, inside);
+    var omega = MARCH_RELAXATION;
+    var prev_t = t;
+    var prev_r = 0.0;
     for (var i = 0; i < 128; i++) {
         let p = ro + rd * t;
         res = map(p);
-        if (res.dist < 0.0005 || t > 50.0) { 
+        let d = res.dist * side;
+        // STEP_FACTOR comes from the generated code; below 1 when the scene has non-exact fields
+        let r = d * STEP_FACTOR;
+        if (omega > 1.0 && (d < 0.0 || r + prev_r < t - prev_t)) {
+            omega = 1.0;
+            t = prev_t + prev_r;
+            continue;
+        }
+        if (d < 0.0005 || t > MARCH_FAR) { 
             res.dist = t;
             break; 
         }
-        t += res.dist;
+        prev_t = t;
+        prev_r = r;
+        t += r * omega;
     }
     return res;
 }
 
-fn get_grid_color(p: vec3<f32>, rd: vec3<f32>) -> vec4<f32> {
+// Fraction of light per channel that crosses the volumes between MARCH_NEAR and `t_max`
+// (Beer-Lambert). Steps end at the nearest volume boundary, so uniform media take few steps.
+fn volume_transmittance(ro: vec3<f32>, rd: vec3<f32>, t_max: f32) -> vec3<f32> {
+    var t = MARCH_NEAR;
+    var optical_depth = vec3<f32>(0.0);
+    for (var i = 0; i < 128; i++) {
+        if (t >= t_max) { break; }
+        let v = map_volume(ro + rd * t);
+        // A minimum step gets across boundaries; a grazing ray still runs out of steps
+        let step = min(max(v.edge * STEP_FACTOR, 0.002), t_max - t);
+        optical_depth += v.extinction * step;
+        t += step;
+    }
+    return exp(-optical_depth);
+}
+
+fn get_grid_color(world_p: vec3<f32>, world_rd: vec3<f32>) -> vec4<f32> {
+    let p = to_y_up(world_p);
+    let rd = to_y_up(world_rd);
     let t = -p.y / rd.y;
-    if (t > 0.0 && t < 100.0) {
+    if (t > 0.0 && t < MARCH_FAR) {
         let pos = p + rd * t;
         let grid = abs(fract(pos.xz - 0.5) - 0.5) / fwidth(pos.xz);
         let line = min(grid.x, grid.y);
@@ -121,7 +577,8 @@ fn get_grid_color(p: vec3<f32>, rd: vec3<f32>) -> vec4<f32> {
         
         // Origin Axes
         var col = vec3<f32>(0.5);
-        if (abs(pos.x) < 0.05) { col = vec3<f32>(0.0, 0.0, 1.0); } // Z axis
+        // Z axis, or Y when Z is up
+        if (abs(pos.x) < 0.05) { col = select(vec3<f32>(0.0, 0.0, 1.0), vec3<f32>(0.0, 1.0, 0.0), uniforms.time_data.w > 0.5); }
         if (abs(pos.z) < 0.05) { col = vec3<f32>(1.0, 0.0, 0.0); } // X axis
 
         return vec4<f32>(col, alpha);
@@ -129,34 +586,107 @@ fn get_grid_color(p: vec3<f32>, rd: vec3<f32>) -> vec4<f32> {
     return vec4<f32>(0.0);
 }
 
-fn render_scene(uv: vec2<f32>) -> vec3<f32> {
-    let ro = uniforms.cam_pos.xyz;
+// Signed distance on the slice plane: red inside, blue outside, white at the surface, with
+// contour lines every 0.1 units and a dark line on the zero crossing
+fn render_slice(ro: vec3<f32>, rd: vec3<f32>) -> vec3<f32> {
+    let n = uniforms.slice_plane.xyz;
+    let denom = dot(rd, n);
+    let t = (uniforms.slice_plane.w - dot(ro, n)) / denom;
+    // No early return: fwidth below needs every lane in the quad to reach it
+    let hit = abs(denom) > 1e-5 && t > 0.0;
+    let d = map(ro + rd * select(0.0, t, hit)).dist;
+    let side = select(vec3<f32>(0.2, 0.4, 1.0), vec3<f32>(1.0, 0.25, 0.2), d < 0.0);
+    var col = mix(vec3<f32>(1.0), side, 1.0 - exp(-3.0 * abs(d)));
+    let band = abs(fract(d * 10.0 + 0.5) - 0.5) / max(fwidth(d * 10.0), 1e-4);
+    col *= 0.85 + 0.15 * clamp(band, 0.0, 1.0);
+    let zero = abs(d) / max(fwidth(d), 1e-4);
+    col = mix(vec3<f32>(0.0), col, clamp(zero - 0.5, 0.0, 1.0));
+    return select(uniforms.bg_color.rgb, col, hit);
+}
+
+// Normal and ray distance of the last `render_scene` sample, for the outline pass in `fs_main`;
+// a miss (or the slice view) leaves a zero normal at MARCH_FAR
+var<private> hit_normal: vec3<f32>;
+var<private> hit_depth: f32;
+
+// `lens_idx`: which of four evenly spaced points on the aperture rim this sample looks through
+fn render_scene(uv: vec2<f32>, lens_idx: u32) -> vec3<f32> {
+    hit_normal = vec3<f32>(0.0);
+    hit_depth = MARCH_FAR;
     let forward = normalize(uniforms.cam_front.xyz);
     let right = normalize(uniforms.cam_right.xyz);
     let up = normalize(uniforms.cam_up.xyz);
-    let rd = normalize(uv.x * right + uv.y * up + 1.8 * forward);
+    var ro = uniforms.cam_pos.xyz;
+    var rd = normalize(uv.x * right + uv.y * up + FOCAL_LENGTH * forward);
+
+    if (dot(uniforms.slice_plane.xyz, uniforms.slice_plane.xyz) > 0.0) {
+        return render_slice(ro, rd);
+    }
 
-    let res = ray_march(ro, rd);
+    // Depth of field: move the origin across the aperture and aim at the same point on the focal plane
+    // With a single sample there is only the lens center, which is a pinhole: no blur
+    let aperture = uniforms.dof_data.y;
+    if (aperture > 0.0 && u32(uniforms.time_data.y) > 1u) {
+        let focus_point = ro + rd * (uniforms.dof_data.x / dot(rd, forward));
+        let angle = f32(lens_idx) * 1.5707963 + 0.7853982;
+        let lens = vec2<f32>(cos(angle), sin(angle)) * (0.707 * aperture);
+        ro += right * lens.x + up * lens.y;
+        rd = normalize(focus_point - ro);
+    }
+
+    // Inside a solid, the visible surface is the far side of the shell facing back at the camera
+    let inside = map(ro + rd * MARCH_NEAR).dist < 0.0;
+    let res = ray_march(ro, rd, inside);
     let t = res.dist;
-    let bg_color = vec3<f32>(0.08, 0.08, 0.1);
+    let bg_color = uniforms.bg_color.rgb;
     
+    let use_env = uniforms.env_data.x > 0.5;
     var col = bg_color;
+    if (use_env) { col = sample_env(rd, 0.0); }
     let grid = get_grid_color(ro, rd);
     col = mix(col, grid.rgb, grid.a);
 
-    if (t < 50.0) {
+    if (t < MARCH_FAR) {
         let p = ro + rd * t;
-        let normal = calc_normal(p);
-        let light_dir = normalize(vec3<f32>(2.0, 4.0, 3.0) - p);
+        var normal = calc_normal(p);
+        var albedo = res.color;
+        if (inside) {
+            normal = -normal;
+            if (uniforms.inside_color.w > 0.5) { albedo = uniforms.inside_color.rgb; }
+        }
+        if (uniforms.time_data.z > 0.5) { albedo = id_color(res.id); }
+        hit_normal = normal;
+        hit_depth = t;
+        let light_dir = normalize(from_y_up(uniforms.light_dir.xyz));
         let diff = max(dot(normal, light_dir), 0.0);
         let view_dir = normalize(ro - p);
         let reflect_dir = reflect(-light_dir, normal);
         let spec = pow(max(dot(view_dir, reflect_dir), 0.0), 32.0);
         let fresnel = pow(1.0 - max(dot(normal, view_dir), 0.0), 5.0) * 0.3;
         
-        let lit_col = res.color * (diff + 0.1) + vec3<f32>(spec * 0.4) + vec3<f32>(fresnel);
+        var lit_col = albedo * (diff + uniforms.ambient.rgb) + vec3<f32>(spec * 0.4) + vec3<f32>(fresnel);
+        if (use_env) {
+            let reflection = sample_env(reflect(rd, normal), 1.0);
+            lit_col = albedo * (diff + sample_env_ambient(normal)) + vec3<f32>(spec * 0.4) + reflection * (fresnel + 0.04);
+        }
+
+        // Clearcoat: the varnish reflects some light before it reaches the base layer
+        let coat = res.coat.x;
+        if (coat > 0.0) {
+            let coat_fresnel = coat * (0.04 + 0.96 * pow(1.0 - max(dot(normal, view_dir), 0.0), 5.0));
+            lit_col = lit_col * (1.0 - coat_fresnel) + vec3<f32>(coat * clearcoat_specular(normal, view_dir, light_dir, res.coat.y));
+            if (use_env) {
+                let lod = res.coat.y * f32(textureNumLevels(env_tex) - 1u);
+                lit_col += sample_env(reflect(rd, normal), lod) * coat_fresnel;
+            }
+        }
         col = lit_col;
     }
+
+    // Translucent media only absorb what is behind them; opaque-only scenes skip this entirely
+    if (HAS_VOLUMES) {
+        col *= volume_transmittance(ro, rd, min(t, MARCH_FAR));
+    }
     
     return col;
 }
@@ -170,6 +700,12 @@ fn vs_main(@builtin(vertex_index) idx: u32) -> VertexOutput {
     return VertexOutput(vec4<f32>(x, y, 0.0, 1.0));
 }
 
+// Exact sRGB transfer function (linear segment near black)
+fn linear_to_srgb(c: vec3<f32>) -> vec3<f32> {
+    let x = clamp(c, vec3<f32>(0.0), vec3<f32>(1.0));
+    return select(1.055 * pow(x, vec3<f32>(1.0 / 2.4)) - 0.055, x * 12.92, x <= vec3<f32>(0.0031308));
+}
+
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
     let pixel_pos = in.clip_position.xy;
@@ -177,15 +713,48 @@ fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
     let rect_size = uniforms.rect_data.zw;
     let aspect = rect_size.x / rect_size.y;
     var total = vec3<f32>(0.0);
-    
-    var uv = (((pixel_pos + vec2<f32>(-0.25, -0.25) - rect_min) / rect_size) * 2.0 - 1.0) * vec2<f32>(aspect, -1.0);
-    total += render_scene(uv);
-    uv = (((pixel_pos + vec2<f32>(0.25, -0.25) - rect_min) / rect_size) * 2.0 - 1.0) * vec2<f32>(aspect, -1.0);
-    total += render_scene(uv);
-    uv = (((pixel_pos + vec2<f32>(-0.25, 0.25) - rect_min) / rect_size) * 2.0 - 1.0) * vec2<f32>(aspect, -1.0);
-    total += render_scene(uv);
-    uv = (((pixel_pos + vec2<f32>(0.25, 0.25) - rect_min) / rect_size) * 2.0 - 1.0) * vec2<f32>(aspect, -1.0);
-    total += render_scene(uv);
-
-    return vec4<f32>(total / 4.0, 1.0);
+
+    // SSAA in the pattern baked in as `ssaa_offset`, with the sample count from the uniforms so it
+    // can change without recompiling: one sample at the pixel center, two on a diagonal, or all
+    // four. Each sample also takes its own lens position for depth of field, which needs two or more.
+    let samples = clamp(u32(uniforms.time_data.y), 1u, 4u);
+    // Outline edge measures: largest normal difference (1 - cos) and relative depth jump between
+    // the first sample and the others, then against the neighboring pixels below
+    var normal0 = vec3<f32>(0.0);
+    var depth0 = MARCH_FAR;
+    var crease = 0.0;
+    var jump = 0.0;
+    for (var i = 0u; i < samples; i++) {
+        var offset = vec2<f32>(0.0);
+        if (samples == 2u) {
+            offset = ssaa_offset(i * 3u);
+        } else if (samples > 2u) {
+            offset = ssaa_offset(i);
+        }
+        let uv = (((pixel_pos + offset - rect_min) / rect_size) * 2.0 - 1.0) * vec2<f32>(aspect, -1.0);
+        // Two samples take opposite lens points, so the blur stays centered
+        total += render_scene(uv, i * (4u / samples));
+        if (i == 0u) {
+            normal0 = hit_normal;
+            depth0 = hit_depth;
+        } else {
+            crease = max(crease, 1.0 - dot(normal0, hit_normal));
+            jump = max(jump, abs(hit_depth - depth0) / max(min(hit_depth, depth0), 1e-4));
+        }
+    }
+
+    var col = total / f32(samples);
+    if (uniforms.output_data.y > 0.5) {
+        // Screen-space neighbors through the quad derivatives; for unit normals 1 - cos = |dn|^2 / 2
+        let dn = fwidth(normal0);
+        crease = max(crease, 0.5 * dot(dn, dn));
+        jump = max(jump, fwidth(depth0) / max(depth0, 1e-4));
+        let edge = max(smoothstep(uniforms.output_data.z, 2.0 * uniforms.output_data.z, crease),
+                       smoothstep(uniforms.output_data.w, 2.0 * uniforms.output_data.w, jump));
+        col = mix(col, vec3<f32>(0.02), edge);
+    }
+    if (uniforms.output_data.x > 0.5) {
+        col = linear_to_srgb(col);
+    }
+    return vec4<f32>(col, 1.0);
 }
\ No newline at end of file
@@ -0,0 +1,141 @@
+use glam::Vec2;
+
+/// Upper bound on polygon edges per profile; every edge is a loop iteration in the shader.
+pub const MAX_PROFILE_SEGMENTS: usize = 256;
+
+/// Line segments each curve is flattened into.
+const CURVE_STEPS: usize = 8;
+
+/// Parses the `d` attribute of an SVG path (`M`, `L`, `H`, `V`, `Q`, `C`, `Z`, absolute or
+/// relative) into closed polygon edges `[ax, ay, bx, by]`. Y is flipped so artwork drawn in
+/// y-down SVG space comes out upright, and coordinates are multiplied by `scale`.
+pub fn parse_svg_path(d: &str, scale: f32) -> Result<Vec<[f32; 4]>, String> {
+    let mut parser = Parser { src: d.as_bytes(), pos: 0 };
+    let mut segments = Vec::new();
+    let mut cur = Vec2::ZERO;
+    let mut start = Vec2::ZERO;
+    let mut cmd = None;
+
+    let line_to = |segments: &mut Vec<[f32; 4]>, a: Vec2, b: Vec2| {
+        if a != b {
+            let (a, b) = (Vec2::new(a.x, -a.y) * scale, Vec2::new(b.x, -b.y) * scale);
+            segments.push([a.x, a.y, b.x, b.y]);
+        }
+    };
+
+    loop {
+        parser.skip_separators();
+        let Some(&c) = parser.src.get(parser.pos) else { break };
+        if c.is_ascii_alphabetic() {
+            parser.pos += 1;
+            cmd = Some(c);
+            if c == b'Z' || c == b'z' {
+                line_to(&mut segments, cur, start);
+                cur = start;
+                continue;
+            }
+        }
+        // Numbers without a command letter repeat the previous command
+        let c = cmd.ok_or("path must start with a command")?;
+        let rel = c.is_ascii_lowercase();
+        let origin = if rel { cur } else { Vec2::ZERO };
+        match c.to_ascii_uppercase() {
+            b'M' => {
+                // A new subpath closes the previous one
+                line_to(&mut segments, cur, start);
+                cur = origin + parser.point()?;
+                start = cur;
+                // Further coordinate pairs after a moveto are implicit linetos
+                cmd = Some(if rel { b'l' } else { b'L' });
+            }
+            b'L' => {
+                let p = origin + parser.point()?;
+                line_to(&mut segments, cur, p);
+                cur = p;
+            }
+            b'H' => {
+                let x = parser.number()? + if rel { cur.x } else { 0.0 };
+                let p = Vec2::new(x, cur.y);
+                line_to(&mut segments, cur, p);
+                cur = p;
+            }
+            b'V' => {
+                let y = parser.number()? + if rel { cur.y } else { 0.0 };
+                let p = Vec2::new(cur.x, y);
+                line_to(&mut segments, cur, p);
+                cur = p;
+            }
+            b'Q' => {
+                let (c1, p) = (origin + parser.point()?, origin + parser.point()?);
+                let mut prev = cur;
+                for i in 1..=CURVE_STEPS {
+                    let t = i as f32 / CURVE_STEPS as f32;
+                    let q = cur.lerp(c1, t).lerp(c1.lerp(p, t), t);
+                    line_to(&mut segments, prev, q);
+                    prev = q;
+                }
+                cur = p;
+            }
+            b'C' => {
+                let (c1, c2, p) = (origin + parser.point()?, origin + parser.point()?, origin + parser.point()?);
+                let mut prev = cur;
+                for i in 1..=CURVE_STEPS {
+                    let t = i as f32 / CURVE_STEPS as f32;
+                    let u = 1.0 - t;
+                    let q = cur * (u * u * u) + c1 * (3.0 * u * u * t) + c2 * (3.0 * u * t * t) + p * (t * t * t);
+                    line_to(&mut segments, prev, q);
+                    prev = q;
+                }
+                cur = p;
+            }
+            other => return Err(format!("unsupported path command '{}'", other as char)),
+        }
+    }
+    line_to(&mut segments, cur, start);
+
+    if segments.len() > MAX_PROFILE_SEGMENTS {
+        return Err(format!("path has more than {MAX_PROFILE_SEGMENTS} segments after flattening"));
+    }
+    if segments.len() < 2 {
+        return Err("path does not enclose an area".to_string());
+    }
+    Ok(segments)
+}
+
+struct Parser<'a> {
+    src: &'a [u8],
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn skip_separators(&mut self) {
+        while self.src.get(self.pos).is_some_and(|c| c.is_ascii_whitespace() || *c == b',') {
+            self.pos += 1;
+        }
+    }
+
+    fn number(&mut self) -> Result<f32, String> {
+        self.skip_separators();
+        let start = self.pos;
+        let mut seen_dot = false;
+        let mut seen_exp = false;
+        while let Some(&c) = self.src.get(self.pos) {
+            let sign_ok = self.pos == start || matches!(self.src[self.pos - 1], b'e' | b'E');
+            match c {
+                b'0'..=b'9' => {}
+                b'+' | b'-' if sign_ok => {}
+                // A second dot starts the next number, as in "0.5.5"
+                b'.' if !seen_dot && !seen_exp => seen_dot = true,
+                b'e' | b'E' if !seen_exp && self.pos > start => seen_exp = true,
+                _ => break,
+            }
+            self.pos += 1;
+        }
+        let text = std::str::from_utf8(&self.src[start..self.pos]).unwrap_or_default();
+        text.parse().map_err(|_| format!("expected a number at offset {start}"))
+    }
+
+    fn point(&mut self) -> Result<Vec2, String> {
+        Ok(Vec2::new(self.number()?, self.number()?))
+    }
+}
@@ -0,0 +1,84 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::Arc;
+use crate::height_map::HeightMap;
+use crate::mesh_volume::{self, MeshVolume};
+
+const VIEWER_TEMPLATE: &str = include_str!("viewer_template.html");
+
+/// Writes a self-contained HTML page that renders `wgsl` with WebGPU and orbit controls.
+/// `uniforms` is the initial uniform block (see `sdf_widget::uniform_floats`); the page only
+/// rewrites the viewport, time and camera entries. No environment map is embedded.
+pub fn export_web_viewer(
+    path: impl AsRef<Path>, wgsl: &str, uniforms: &[f32], height_maps: &[Arc<HeightMap>], mesh_volumes: &[Arc<MeshVolume>], animated: bool,
+) -> io::Result<()> {
+    // The grid and slice views call `fwidth` inside branches; naga accepts that but browsers
+    // reject it under the default uniformity rules
+    let shader = format!("diagnostic(off, derivative_uniformity);\n{wgsl}");
+    let uniforms: Vec<String> = uniforms.iter().map(|v| format!("{v}")).collect();
+    let html = VIEWER_TEMPLATE
+        .replace("/*{{SHADER}}*/", &js_string(&shader))
+        .replace("/*{{UNIFORMS}}*/", &format!("[{}]", uniforms.join(", ")))
+        .replace("/*{{HEIGHT_MAPS}}*/", &height_maps_js(height_maps))
+        .replace("/*{{MESH_VOLUMES}}*/", &mesh_volumes_js(mesh_volumes))
+        .replace("/*{{ANIMATED}}*/", if animated { "true" } else { "false" });
+    fs::write(path, html)
+}
+
+/// `[width, height, "<base64 layers>"]`, with every map resampled to the largest size like the
+/// app's texture array, or `null`.
+fn height_maps_js(maps: &[Arc<HeightMap>]) -> String {
+    if maps.is_empty() {
+        return "null".to_string();
+    }
+    let (w, h) = maps.iter().fold((1, 1), |(w, h), m| (w.max(m.width), h.max(m.height)));
+    let data: Vec<u8> = maps.iter().flat_map(|m| m.resized(w, h)).collect();
+    format!("[{w}, {h}, \"{}\"]", base64(&data))
+}
+
+/// `[width, height, depth, "<base64 little-endian f32 texels>"]` of the stacked mesh texture
+/// (see `mesh_volume::atlas_layout`), or `null`.
+fn mesh_volumes_js(volumes: &[Arc<MeshVolume>]) -> String {
+    if volumes.is_empty() {
+        return "null".to_string();
+    }
+    let (_, [w, h, d]) = mesh_volume::atlas_layout(volumes);
+    let data: Vec<u8> = mesh_volume::atlas_data(volumes).iter().flat_map(|v| v.to_le_bytes()).collect();
+    format!("[{w}, {h}, {d}, \"{}\"]", base64(&data))
+}
+
+/// Double-quoted JS string literal; `</` is escaped so the text cannot close the script tag.
+fn js_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '/' if out.ends_with('<') => out.push_str("\\/"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+fn base64(data: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let n = chunk.iter().enumerate().fold(0u32, |n, (i, &b)| n | ((b as u32) << (16 - 8 * i)));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[((n >> (18 - 6 * i)) & 63) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
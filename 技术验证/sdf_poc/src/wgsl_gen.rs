@@ -1,83 +1,476 @@
-use crate::sdf_ast::{SdfNode, SdfOp};
+use glam::{Mat3, Quat, Vec3};
+use std::sync::Arc;
+use crate::sdf_ast::{AnimChannel, SdfNode, SdfOp, SmoothKind, TimeExpr, Wave};
+use crate::mesh_volume::{atlas_layout, MeshVolume};
+use crate::sdf_bounds::Aabb;
+use crate::sdf_eval::EMPTY_DIST;
+
+/// Bounded subtrees are evaluated exactly within this distance of their bounds, which keeps the
+/// normals and hit test away from the early-out.
+const BOUND_MARGIN: f32 = 0.1;
+/// Subtrees cheaper than this (in `SceneStats::estimated_instructions`) are not worth a bounds test.
+const MIN_BOUNDED_COST: usize = 20;
 
 pub struct WgslGenerator {
-    _unused: bool,
+    /// Per-node helper functions (e.g. polygon profiles) emitted ahead of `map`.
+    helpers: Vec<String>,
+    /// Height map paths by texture array layer, as uploaded by `SdfRenderResources`.
+    height_maps: Vec<String>,
+    /// Baked meshes and the first slice of each in the mesh texture (`atlas_layout`).
+    mesh_volumes: Vec<Arc<MeshVolume>>,
+    mesh_slabs: Vec<u32>,
+    /// Product of the `UvScale` nodes above the node being emitted.
+    uv_scale: Vec3,
+    /// Number of leaf primitives emitted so far; the next one gets this as its `id`.
+    next_id: u32,
 }
 
 impl WgslGenerator {
     pub fn new() -> Self {
-        Self { _unused: true }
+        Self { helpers: Vec::new(), height_maps: Vec::new(), mesh_volumes: Vec::new(), mesh_slabs: Vec::new(), uv_scale: Vec3::ONE, next_id: 0 }
     }
 
     pub fn generate(&mut self, root: &SdfNode) -> String {
-        let expression = self.emit_expression(root, "p_in");
+        self.height_maps = root.height_maps().iter().map(|m| m.path.clone()).collect();
+        self.mesh_volumes = root.mesh_volumes();
+        self.mesh_slabs = atlas_layout(&self.mesh_volumes).0;
+        let expression = self.emit_assembly(root, "p_in");
+        let mut volumes = Vec::new();
+        self.emit_volumes(root, "p_in", &mut volumes);
+        let volume_expression = volumes.iter().fold("volume_empty()".to_string(), |acc, v| format!("op_volume_union({acc}, {v})"));
         format!(
             "struct SdfResult {{
                 dist: f32,
                 color: vec3<f32>,
+                inside: vec3<f32>,
+                coat: vec2<f32>,
+                id: f32,
             }}
 
+            const STEP_FACTOR: f32 = {:.4};
+            // Whether `render_scene` runs the volume pass at all
+            const HAS_VOLUMES: bool = {};
+
+            {}
+
             fn map(p_in: vec3<f32>) -> SdfResult {{
                 return {};
+            }}
+
+            fn map_volume(p_in: vec3<f32>) -> VolumeSample {{
+                return {};
             }}",
-            expression
+            root.step_factor(),
+            !volumes.is_empty(),
+            self.helpers.join("\n"),
+            expression,
+            volume_expression,
         )
     }
 
-    fn emit_expression(&self, node: &SdfNode, p_var: &str) -> String {
+    /// Emits an even-odd polygon distance function over `segments` and returns its name.
+    /// The source path is kept as a comment to make the generated shader readable.
+    fn emit_polygon_fn(&mut self, path: &str, segments: &[[f32; 4]]) -> String {
+        let name = format!("sd_profile_{}", self.helpers.len());
+        let edges: Vec<String> = segments.iter()
+            .map(|s| format!("vec4<f32>({:.4}, {:.4}, {:.4}, {:.4})", s[0], s[1], s[2], s[3]))
+            .collect();
+        self.helpers.push(format!(
+            "// svg_profile: {path}
+            fn {name}(p: vec2<f32>) -> f32 {{
+                var edges = array<vec4<f32>, {count}>({edges});
+                var d = 1e20;
+                var s = 1.0;
+                for (var i = 0u; i < {count}u; i++) {{
+                    let a = edges[i].xy;
+                    let e = edges[i].zw - a;
+                    let w = p - a;
+                    let proj = w - e * clamp(dot(w, e) / dot(e, e), 0.0, 1.0);
+                    d = min(d, dot(proj, proj));
+                    if ((a.y <= p.y) != (edges[i].w <= p.y) && (e.x * w.y > e.y * w.x) == (edges[i].w > a.y)) {{ s = -s; }}
+                }}
+                return s * sqrt(d);
+            }}",
+            path = path.replace(['\n', '\r'], " "),
+            count = segments.len(),
+            edges = edges.join(", "),
+        ));
+        name
+    }
+
+    /// Emits `target` as a function of its own so the displacement can take its gradient (for the
+    /// triplanar blend) without inlining the subtree five times; returns the wrapper's name.
+    fn emit_displace_fn(&mut self, target: &SdfNode, path: &str, amplitude: f32, scale: f32) -> String {
+        let target_expr = self.emit_expression(target, "p");
+        let layer = self.height_maps.iter().position(|m| m == path).unwrap_or(0);
+        let uv = self.uv_scale * scale;
+        let id = self.helpers.len();
+        self.helpers.push(format!(
+            "// displace_map: {path}
+            fn displace_target_{id}(p: vec3<f32>) -> SdfResult {{
+                return {target_expr};
+            }}
+            fn displace_{id}(p: vec3<f32>) -> SdfResult {{
+                let k = vec2<f32>(1.0, -1.0) * 0.001;
+                let n = k.xyy * displace_target_{id}(p + k.xyy).dist + k.yyx * displace_target_{id}(p + k.yyx).dist
+                    + k.yxy * displace_target_{id}(p + k.yxy).dist + k.xxx * displace_target_{id}(p + k.xxx).dist;
+                return op_displace_map(displace_target_{id}(p), p * vec3<f32>({:.4}, {:.4}, {:.4}), n, {layer}, {amplitude:.4});
+            }}",
+            uv.x, uv.y, uv.z,
+            path = path.replace(['\n', '\r'], " "),
+        ));
+        format!("displace_{id}")
+    }
+
+    /// Emits the hard unions at the top of the tree with each operand bounded (see
+    /// `emit_bounded_fn`), so a point in a large assembly only pays for the parts it is near.
+    /// Only this union spine is bounded: a smaller distance (to the bounds) in place of the true
+    /// one leaves a hard union exact, but would move a smooth blend or a subtraction's cut.
+    fn emit_assembly(&mut self, node: &SdfNode, p_var: &str) -> String {
         match &node.op {
-            SdfOp::Sphere { radius } => format!("SdfResult(sd_sphere({p_var}, {radius:.4}), vec3<f32>(0.2, 0.55, 1.0))"),
-            SdfOp::Box { size } => format!("SdfResult(sd_box({p_var}, vec3<f32>({:.4}, {:.4}, {:.4})), vec3<f32>(0.2, 0.55, 1.0))", size[0], size[1], size[2]),
-            SdfOp::Cylinder { radius, height } => format!("SdfResult(sd_cylinder({p_var}, {radius:.4}, {height:.4}), vec3<f32>(0.2, 0.55, 1.0))"),
-            SdfOp::Torus { major_radius, minor_radius } => format!("SdfResult(sd_torus({p_var}, vec2<f32>({major_radius:.4}, {minor_radius:.4})), vec3<f32>(0.2, 0.55, 1.0))"),
+            SdfOp::Union { a, b, smooth, .. } if *smooth <= 0.0 => {
+                let res1 = self.emit_assembly(a, p_var);
+                let res2 = self.emit_assembly(b, p_var);
+                format!("op_union({res1}, {res2})")
+            }
+            _ => {
+                let bounds = node.bounds();
+                if bounds.is_finite() && !bounds.is_empty() && node.stats().estimated_instructions >= MIN_BOUNDED_COST {
+                    let func = self.emit_bounded_fn(node, &bounds);
+                    format!("{func}({p_var})")
+                } else {
+                    self.emit_expression(node, p_var)
+                }
+            }
+        }
+    }
+
+    /// Emits `node` as a function that, more than `BOUND_MARGIN` outside `bounds`, returns the
+    /// distance to the box instead of evaluating the subtree. That never exceeds the true distance,
+    /// so marching stays conservative; returns the function's name.
+    fn emit_bounded_fn(&mut self, node: &SdfNode, bounds: &Aabb) -> String {
+        let expr = self.emit_expression(node, "p");
+        let id = self.helpers.len();
+        let center = (bounds.min + bounds.max) * 0.5;
+        let half = bounds.size() * 0.5;
+        let vec = |v: Vec3| format!("vec3<f32>({:.4}, {:.4}, {:.4})", v.x, v.y, v.z);
+        self.helpers.push(format!(
+            "fn bounded_{id}(p: vec3<f32>) -> SdfResult {{
+                let outside = length(max(abs(p - {center}) - {half}, vec3<f32>(0.0)));
+                if (outside > {BOUND_MARGIN:.4} && bounds_skip_allowed()) {{
+                    return sdf_result(outside, vec3<f32>(0.0));
+                }}
+                return {expr};
+            }}",
+            center = vec(center),
+            half = vec(half),
+        ));
+        format!("bounded_{id}")
+    }
+
+    /// Emits `target` once as a function and a loop that unions its copies, so a large count does
+    /// not inline the subtree over and over; returns the loop function's name.
+    fn emit_array_fn(&mut self, target: &SdfNode, count: u32, offset: &[f32; 3], rotate_step_deg: &[f32; 3]) -> String {
+        let target_expr = self.emit_expression(target, "p");
+        let id = self.helpers.len();
+        let [rx, ry, rz] = rotate_step_deg.map(|deg| deg.to_radians());
+        self.helpers.push(format!(
+            "fn array_target_{id}(p: vec3<f32>) -> SdfResult {{
+                return {target_expr};
+            }}
+            fn array_{id}(p: vec3<f32>) -> SdfResult {{
+                var res = array_target_{id}(p);
+                for (var i = 1u; i < {count}u; i++) {{
+                    let k = f32(i);
+                    let q = rotate_x(rotate_y(rotate_z(p - vec3<f32>({:.4}, {:.4}, {:.4}) * k, {:.4} * k), {:.4} * k), {:.4} * k);
+                    res = op_union(res, array_target_{id}(q));
+                }}
+                return res;
+            }}",
+            offset[0], offset[1], offset[2], -rz, -ry, -rx,
+        ));
+        format!("array_{id}")
+    }
+
+    /// WGSL for the point at which a single-child node samples its target (the counterpart of
+    /// `SdfNode::local_point`), or `None` for nodes that do not move space this way.
+    fn local_point_expr(op: &SdfOp, p_var: &str) -> Option<String> {
+        let new_p = match op {
+            SdfOp::Translate { offset, .. } => format!("({p_var} - vec3<f32>({:.4}, {:.4}, {:.4}))", offset[0], offset[1], offset[2]),
+            SdfOp::Rotate { axis, angle_deg, .. } => {
+                let rad = (-angle_deg).to_radians();
+                let axis_name = if axis[0] > 0.9 { "x" } else if axis[1] > 0.9 { "y" } else { "z" };
+                format!("rotate_{axis_name}({p_var}, {rad:.4})")
+            }
+            SdfOp::Scale { factor, .. } => format!("({p_var} / {factor:.4})"),
+            SdfOp::Transform { rotation, offset, .. } => {
+                // p' = R^-1 (p - offset); more digits than elsewhere so the matrix stays orthonormal
+                let m = Mat3::from_quat(Quat::from_array(*rotation)).transpose().to_cols_array();
+                let cols: Vec<String> = m.iter().map(|v| format!("{v:.6}")).collect();
+                format!(
+                    "(mat3x3<f32>({}) * ({p_var} - vec3<f32>({:.4}, {:.4}, {:.4})))",
+                    cols.join(", "), offset[0], offset[1], offset[2],
+                )
+            }
+            SdfOp::Mirror { axis, .. } => {
+                // p' = abs(p) for the mirror axis
+                let mut p_parts = [format!("{p_var}.x"), format!("{p_var}.y"), format!("{p_var}.z")];
+                if axis[0] > 0.9 { p_parts[0] = format!("abs({})", p_parts[0]); }
+                if axis[1] > 0.9 { p_parts[1] = format!("abs({})", p_parts[1]); }
+                if axis[2] > 0.9 { p_parts[2] = format!("abs({})", p_parts[2]); }
+                format!("vec3<f32>({}, {}, {})", p_parts[0], p_parts[1], p_parts[2])
+            }
+            SdfOp::MirrorPlane { normal, offset, .. } => {
+                format!("op_mirror_plane({p_var}, vec3<f32>({:.4}, {:.4}, {:.4}), {offset:.4})", normal[0], normal[1], normal[2])
+            }
+            SdfOp::Repeat { spacing, .. } => {
+                // p' = p - s * round(p / s) on each repeated axis
+                let mut p_parts = [format!("{p_var}.x"), format!("{p_var}.y"), format!("{p_var}.z")];
+                for (part, s) in p_parts.iter_mut().zip(spacing) {
+                    if *s > 0.0 {
+                        *part = format!("({part} - {s:.4} * round({part} / {s:.4}))");
+                    }
+                }
+                format!("vec3<f32>({}, {}, {})", p_parts[0], p_parts[1], p_parts[2])
+            }
+            SdfOp::Warp { amplitude, frequency, .. } => format!("op_warp({p_var}, {amplitude:.4}, {frequency:.4})"),
+            SdfOp::Animated { channel: AnimChannel::Translate { dir }, expr, .. } => {
+                let value = Self::emit_time_expr(expr);
+                format!("({p_var} - vec3<f32>({:.4}, {:.4}, {:.4}) * {value})", dir[0], dir[1], dir[2])
+            }
+            SdfOp::Animated { channel: AnimChannel::Rotate { axis }, expr, .. } => {
+                let value = Self::emit_time_expr(expr);
+                let axis_name = if axis[0] > 0.9 { "x" } else if axis[1] > 0.9 { "y" } else { "z" };
+                format!("rotate_{axis_name}({p_var}, -radians({value}))")
+            }
+            _ => return None,
+        };
+        Some(new_p)
+    }
+
+    /// Appends a `volume_sample` for every `Volume` under `node` to `out`, each taking the
+    /// transforms above it (booleans and attributes are passed through). Array copies are
+    /// unrolled; a displacement map does not move the volume.
+    fn emit_volumes(&mut self, node: &SdfNode, p_var: &str, out: &mut Vec<String>) {
+        match &node.op {
+            SdfOp::Volume { target, density, absorption_color: [r, g, b] } => {
+                let res = self.emit_expression(target, p_var);
+                // Extinction per unit length, per channel: white absorbs nothing
+                let [er, eg, eb] = [r, g, b].map(|c| density * (1.0 - c));
+                out.push(format!("volume_sample(({res}).dist, vec3<f32>({er:.4}, {eg:.4}, {eb:.4}))"));
+            }
+            SdfOp::ArrayLinear { target, count, offset, rotate_step_deg } => {
+                for i in 0..*count {
+                    let k = i as f32;
+                    let [rx, ry, rz] = rotate_step_deg.map(|deg| -(deg * k).to_radians());
+                    let copy_p = format!(
+                        "rotate_x(rotate_y(rotate_z({p_var} - vec3<f32>({:.4}, {:.4}, {:.4}), {rz:.4}), {ry:.4}), {rx:.4})",
+                        offset[0] * k, offset[1] * k, offset[2] * k,
+                    );
+                    self.emit_volumes(target, &copy_p, out);
+                }
+            }
+            SdfOp::UvScale { target, scale } => {
+                let outer = self.uv_scale;
+                self.uv_scale *= Vec3::from(*scale);
+                self.emit_volumes(target, p_var, out);
+                self.uv_scale = outer;
+            }
+            SdfOp::Scale { target, factor } => {
+                let new_p = Self::local_point_expr(&node.op, p_var).expect("scale moves space");
+                let mut inner = Vec::new();
+                self.emit_volumes(target, &new_p, &mut inner);
+                out.extend(inner.into_iter().map(|v| format!("op_volume_scale({v}, {factor:.4})")));
+            }
+            op => match Self::local_point_expr(op, p_var) {
+                Some(new_p) => {
+                    for child in node.children() {
+                        self.emit_volumes(child, &new_p, out);
+                    }
+                }
+                None => {
+                    for child in node.children() {
+                        self.emit_volumes(child, p_var, out);
+                    }
+                }
+            },
+        }
+    }
+
+    fn emit_time_expr(expr: &TimeExpr) -> String {
+        let t = format!("({:.4} * uniforms.time_data.x)", expr.freq);
+        let wave = match expr.wave {
+            Wave::Sin => format!("sin(6.2832 * {t})"),
+            Wave::Cos => format!("cos(6.2832 * {t})"),
+            Wave::Saw => format!("fract({t})"),
+            Wave::Linear => t,
+        };
+        format!("({:.4} + {:.4} * {wave})", expr.base, expr.amp)
+    }
+
+    fn emit_expression(&mut self, node: &SdfNode, p_var: &str) -> String {
+        let expr = match &node.op {
+            SdfOp::Sphere { radius } => format!("sdf_result(sd_sphere({p_var}, {radius:.4}), vec3<f32>(0.2, 0.55, 1.0))"),
+            SdfOp::Box { size } => format!("sdf_result(sd_box({p_var}, vec3<f32>({:.4}, {:.4}, {:.4})), vec3<f32>(0.2, 0.55, 1.0))", size[0], size[1], size[2]),
+            SdfOp::Cylinder { radius, height } => format!("sdf_result(sd_cylinder({p_var}, {radius:.4}, {height:.4}), vec3<f32>(0.2, 0.55, 1.0))"),
+            SdfOp::Torus { major_radius, minor_radius, axis } => {
+                // A swizzle puts the axis on Y for `sd_torus`, which needs no rotation
+                let p = if axis[0] > 0.5 { format!("({p_var}).yxz") } else if axis[2] > 0.5 { format!("({p_var}).xzy") } else { p_var.to_string() };
+                format!("sdf_result(sd_torus({p}, vec2<f32>({major_radius:.4}, {minor_radius:.4})), vec3<f32>(0.2, 0.55, 1.0))")
+            }
+            SdfOp::Wedge { size } => format!("sdf_result(sd_wedge({p_var}, vec3<f32>({:.4}, {:.4}, {:.4})), vec3<f32>(0.2, 0.55, 1.0))", size[0], size[1], size[2]),
+            SdfOp::SuperEllipsoid { radii, e1, e2 } => format!("sdf_result(sd_superellipsoid({p_var}, vec3<f32>({:.4}, {:.4}, {:.4}), {e1:.4}, {e2:.4}), vec3<f32>(0.2, 0.55, 1.0))", radii[0], radii[1], radii[2]),
+            SdfOp::Mandelbulb { power, iterations } => format!("sdf_result(sd_mandelbulb({p_var}, {power:.4}, {iterations}u), vec3<f32>(0.2, 0.55, 1.0))"),
+            SdfOp::Capsule { a: [ax, ay, az], b: [bx, by, bz], radius } => format!(
+                "sdf_result(sd_capsule({p_var}, vec3<f32>({ax:.4}, {ay:.4}, {az:.4}), vec3<f32>({bx:.4}, {by:.4}, {bz:.4}), {radius:.4}), vec3<f32>(0.2, 0.55, 1.0))"
+            ),
+            SdfOp::Tetrahedron { size } => format!("sdf_result(sd_tetrahedron({p_var}, {size:.4}), vec3<f32>(0.2, 0.55, 1.0))"),
+            SdfOp::CutHollowSphere { radius, cut_height, thickness } => format!("sdf_result(sd_cut_hollow_sphere({p_var}, {radius:.4}, {cut_height:.4}, {thickness:.4}), vec3<f32>(0.2, 0.55, 1.0))"),
+            SdfOp::Helix { radius, pitch, thickness, turns } => format!("sdf_result(sd_helix({p_var}, {radius:.4}, {pitch:.4}, {thickness:.4}, {turns:.4}), vec3<f32>(0.2, 0.55, 1.0))"),
+            SdfOp::InfiniteCylinder { radius, axis: [x, y, z] } => format!("sdf_result(sd_inf_cylinder({p_var}, {radius:.4}, vec3<f32>({x:.4}, {y:.4}, {z:.4})), vec3<f32>(0.2, 0.55, 1.0))"),
+            SdfOp::InfiniteCone { angle_deg, axis: [x, y, z] } => {
+                let (sin, cos) = angle_deg.to_radians().sin_cos();
+                format!("sdf_result(sd_inf_cone({p_var}, vec2<f32>({sin:.4}, {cos:.4}), vec3<f32>({x:.4}, {y:.4}, {z:.4})), vec3<f32>(0.2, 0.55, 1.0))")
+            }
+            SdfOp::SvgProfile { path, segments } => {
+                let func = self.emit_polygon_fn(path, segments);
+                format!("sdf_result({func}(({p_var}).xy), vec3<f32>(0.2, 0.55, 1.0))")
+            }
+            SdfOp::MeshVolume { volume } => {
+                let slab = self.mesh_volumes.iter().position(|v| Arc::ptr_eq(v, volume)).unwrap_or(0);
+                let (o, [nx, ny, nz]) = (volume.origin, volume.size);
+                format!(
+                    "sdf_result(sd_mesh_volume({p_var}, vec3<f32>({:.5}, {:.5}, {:.5}), {:.6}, vec3<i32>({nx}, {ny}, {nz}), {}), vec3<f32>(0.2, 0.55, 1.0))",
+                    o.x, o.y, o.z, volume.cell, self.mesh_slabs[slab],
+                )
+            }
             
-            SdfOp::Union { a, b, smooth } => {
+            SdfOp::Union { a, b, smooth, kind } => {
                 let res1 = self.emit_expression(a, p_var);
                 let res2 = self.emit_expression(b, p_var);
                 if *smooth > 0.0 {
-                    format!("op_union_smooth({res1}, {res2}, {smooth:.4})")
+                    format!("op_union_smooth{}({res1}, {res2}, {smooth:.4})", smooth_suffix(*kind))
                 } else {
                     format!("op_union({res1}, {res2})")
                 }
             }
-            SdfOp::Subtract { a, b, smooth } => {
+            SdfOp::Subtract { a, b, smooth, kind } => {
                 let res1 = self.emit_expression(a, p_var);
                 let res2 = self.emit_expression(b, p_var);
                 if *smooth > 0.0 {
-                    format!("op_subtract_smooth({res1}, {res2}, {smooth:.4})")
+                    format!("op_subtract_smooth{}({res1}, {res2}, {smooth:.4})", smooth_suffix(*kind))
                 } else {
                     format!("op_subtract({res1}, {res2})")
                 }
             }
-            SdfOp::Intersect { a, b, smooth: _ } => {
+            SdfOp::Intersect { a, b, smooth, kind } => {
+                let res1 = self.emit_expression(a, p_var);
+                let res2 = self.emit_expression(b, p_var);
+                if *smooth > 0.0 {
+                    format!("op_intersect_smooth{}({res1}, {res2}, {smooth:.4})", smooth_suffix(*kind))
+                } else {
+                    format!("op_intersect({res1}, {res2})")
+                }
+            }
+            SdfOp::Nearest { a, b } => {
+                let res1 = self.emit_expression(a, p_var);
+                let res2 = self.emit_expression(b, p_var);
+                format!("op_union({res1}, {res2})")
+            }
+            SdfOp::Farthest { a, b } => {
                 let res1 = self.emit_expression(a, p_var);
                 let res2 = self.emit_expression(b, p_var);
                 format!("op_intersect({res1}, {res2})")
             }
-            SdfOp::Translate { target, offset } => {
-                let new_p = format!("({p_var} - vec3<f32>({:.4}, {:.4}, {:.4}))", offset[0], offset[1], offset[2]);
-                self.emit_expression(target, &new_p)
+            SdfOp::SmoothGroup { children, k } => {
+                let results: Vec<String> = children.iter().map(|c| self.emit_expression(c, p_var)).collect();
+                let (first, rest) = results.split_first().expect("smooth_group has children");
+                if *k > 0.0 {
+                    let acc = rest.iter().fold(format!("smooth_group_start({first})"), |acc, res| format!("smooth_group_add({acc}, {res}, {k:.4})"));
+                    format!("smooth_group_end({acc}, {k:.4})")
+                } else {
+                    rest.iter().fold(first.clone(), |acc, res| format!("op_union({acc}, {res})"))
+                }
             }
-            SdfOp::Rotate { target, axis, angle_deg } => {
-                let rad = (-angle_deg).to_radians();
-                let axis_name = if axis[0] > 0.9 { "x" } else if axis[1] > 0.9 { "y" } else { "z" };
-                let new_p = format!("rotate_{axis_name}({p_var}, {rad:.4})");
-                self.emit_expression(target, &new_p)
+            SdfOp::Groove { target, cutter, depth, width } => {
+                let res = self.emit_expression(target, p_var);
+                let cut = self.emit_expression(cutter, p_var);
+                format!("op_groove({res}, ({cut}).dist, {depth:.4}, {width:.4})")
             }
-            SdfOp::Mirror { target, axis } => {
-                // p' = abs(p) for the mirror axis
-                let mut p_parts = [format!("{p_var}.x"), format!("{p_var}.y"), format!("{p_var}.z")];
-                if axis[0] > 0.9 { p_parts[0] = format!("abs({})", p_parts[0]); }
-                if axis[1] > 0.9 { p_parts[1] = format!("abs({})", p_parts[1]); }
-                if axis[2] > 0.9 { p_parts[2] = format!("abs({})", p_parts[2]); }
-                let new_p = format!("vec3<f32>({}, {}, {})", p_parts[0], p_parts[1], p_parts[2]);
+            SdfOp::Pipe { a, b, radius } => {
+                let res1 = self.emit_expression(a, p_var);
+                let res2 = self.emit_expression(b, p_var);
+                format!("op_pipe({res1}, {res2}, {radius:.4})")
+            }
+            SdfOp::Translate { target, .. } | SdfOp::Rotate { target, .. } | SdfOp::Transform { target, .. } | SdfOp::Mirror { target, .. }
+            | SdfOp::MirrorPlane { target, .. } | SdfOp::Repeat { target, .. } | SdfOp::Warp { target, .. }
+            | SdfOp::Animated { target, channel: AnimChannel::Translate { .. } | AnimChannel::Rotate { .. }, .. } => {
+                let new_p = Self::local_point_expr(&node.op, p_var).expect("op moves space");
                 self.emit_expression(target, &new_p)
             }
+            SdfOp::ArrayLinear { target, count, offset, rotate_step_deg } => {
+                let func = self.emit_array_fn(target, *count, offset, rotate_step_deg);
+                format!("{func}({p_var})")
+            }
+            SdfOp::DisplaceMap { target, texture, amplitude, scale } => {
+                let func = self.emit_displace_fn(target, &texture.path, *amplitude, *scale);
+                format!("{func}({p_var})")
+            }
+            SdfOp::UvScale { target, scale } => {
+                let outer = self.uv_scale;
+                self.uv_scale *= Vec3::from(*scale);
+                let res = self.emit_expression(target, p_var);
+                self.uv_scale = outer;
+                res
+            }
+            SdfOp::Scale { target, factor } => {
+                let new_p = Self::local_point_expr(&node.op, p_var).expect("scale moves space");
+                let res = self.emit_expression(target, &new_p);
+                format!("op_scale({res}, {factor:.4})")
+            }
+            SdfOp::OnionRings { target, count, spacing } => {
+                let res = self.emit_expression(target, p_var);
+                format!("op_onion_rings({res}, {count}.0, {spacing:.4})")
+            }
+            SdfOp::Annular { target, thickness } => {
+                let res = self.emit_expression(target, p_var);
+                format!("op_annular({res}, {thickness:.4})")
+            }
             SdfOp::Color { target, color } => {
-                let mut res = self.emit_expression(target, p_var);
+                let res = self.emit_expression(target, p_var);
                 // We wrap the expression and just replace the color field
                 format!("set_color({}, vec3<f32>({:.4}, {:.4}, {:.4}))", res, color[0], color[1], color[2])
             }
+            SdfOp::Animated { target, channel: AnimChannel::Inflate, expr } => {
+                let res = self.emit_expression(target, p_var);
+                format!("op_offset({res}, {})", Self::emit_time_expr(expr))
+            }
+            SdfOp::Clearcoat { target, strength, roughness } => {
+                let res = self.emit_expression(target, p_var);
+                format!("set_clearcoat({res}, {strength:.4}, {roughness:.4})")
+            }
+            // Empty to surfaces; `emit_volumes` emits the medium itself
+            SdfOp::Volume { .. } => format!("sdf_result({EMPTY_DIST:.1}, vec3<f32>(0.2, 0.55, 1.0))"),
+            SdfOp::InsideColor { target, color } => {
+                let res = self.emit_expression(target, p_var);
+                format!("set_inside_color({}, vec3<f32>({:.4}, {:.4}, {:.4}))", res, color[0], color[1], color[2])
+            }
+        };
+        // Leaves are numbered in the order they are emitted: a depth-first walk, first child first
+        if node.children().is_empty() {
+            let id = self.next_id;
+            self.next_id += 1;
+            format!("set_id({expr}, {id}.0)")
+        } else {
+            expr
         }
     }
 }
+
+/// Helper-name suffix of the template's smooth boolean for `kind`.
+fn smooth_suffix(kind: SmoothKind) -> &'static str {
+    match kind {
+        SmoothKind::Poly => "",
+        SmoothKind::Exp => "_exp",
+    }
+}